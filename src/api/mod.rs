@@ -1 +1,20 @@
+pub mod posts;
 pub mod tags;
+pub mod users;
+pub mod wiki;
+
+/// Sorts the error of a `server_only` call: an `E` is for the caller to show
+/// and comes back as the inner `Err`, anything else is a `ServerFnError`.
+#[cfg(feature = "ssr")]
+pub fn split_error<T, E>(result: anyhow::Result<T>) -> Result<Result<T, E>, leptos::ServerFnError>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    match result {
+        Ok(value) => Ok(Ok(value)),
+        Err(e) => match e.downcast::<E>() {
+            Ok(e) => Ok(Err(e)),
+            Err(e) => Err(leptos::ServerFnError::ServerError(e.to_string())),
+        },
+    }
+}
@@ -0,0 +1,72 @@
+use leptos::*;
+
+use crate::models::post::{Post, PostDetails};
+use crate::models::search::SearchError;
+
+/// Lists posts matching `search`. A malformed query is not a server error, so
+/// it comes back as the inner `Err` for the search bar to point at.
+#[server(GetPaginatedPosts, "/api")]
+pub async fn get_paginated_posts(
+    page: u32,
+    per_page: u32,
+    search: Option<String>,
+) -> Result<Result<Vec<Post>, SearchError>, ServerFnError> {
+    use crate::server_only::db::get_db_connection;
+    let db = get_db_connection().await?;
+
+    crate::api::split_error(
+        crate::server_only::post::get_paginated_posts(&db, page, per_page, search).await,
+    )
+}
+
+#[server(GetPost, "/api")]
+pub async fn get_post(id: u64) -> Result<Option<PostDetails>, ServerFnError> {
+    use crate::server_only::db::get_db_connection;
+    let db = get_db_connection().await?;
+
+    let post = match crate::server_only::post::get_post_by_id(&db, id).await {
+        Ok(Some(post)) => post,
+        Ok(None) => return Ok(None),
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+
+    let uploader = crate::server_only::user::get_user_names(&db, vec![post.uploader_id]).await;
+    let tags = crate::server_only::tag::get_tags_by_ids(&db, post.tags.clone()).await;
+    match (tags, uploader) {
+        (Ok(tags), Ok(mut uploader)) => Ok(Some(PostDetails {
+            uploader_name: uploader.remove(&post.uploader_id),
+            post,
+            tags,
+            public_url: crate::server_only::config::public_url(),
+        })),
+        (Err(e), _) | (_, Err(e)) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Replaces the tags of a post with the whitespace separated tag names in
+/// `tags`. Returns the names the post ends up with, implied tags included.
+#[server(SetPostTags, "/api")]
+pub async fn set_post_tags(id: u64, tags: String) -> Result<Vec<String>, ServerFnError> {
+    use crate::models::permission::Permission;
+    use crate::server_only::alias::resolve_tag_ids;
+    use crate::server_only::tag::get_tags_by_ids;
+    let user_id = crate::server_only::auth::authorize(Permission::TagPosts).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    let tag_ids = match resolve_tag_ids(&db, &tags).await {
+        Ok(tag_ids) => tag_ids,
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+
+    match crate::server_only::post::set_post_tags(&db, id, tag_ids, user_id).await {
+        Ok(Some(tag_ids)) => match get_tags_by_ids(&db, tag_ids).await {
+            Ok(tags) => Ok(tags.into_iter().map(|tag| tag.name).collect()),
+            Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+        },
+        Ok(None) => Err(ServerFnError::ServerError(format!(
+            "there is no post #{}",
+            id
+        ))),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
@@ -1,13 +1,15 @@
 use leptos::*;
 
-use crate::models::tag::Tag;
+use crate::models::tag::{
+    ListedTag, TagChangeError, TagDeleteError, TagDetails, TagEdit, TagEditError,
+};
 
 #[server(GetPaginatedTags, "/api")]
 pub async fn get_paginated_tags(
     page: u32,
     per_page: u32,
     search: Option<String>,
-) -> Result<Vec<Tag>, ServerFnError> {
+) -> Result<Vec<ListedTag>, ServerFnError> {
     use crate::server_only::db::get_db_connection;
     let db = get_db_connection().await?;
 
@@ -15,11 +17,32 @@ pub async fn get_paginated_tags(
         .await
         .unwrap();
 
-    Ok(tags)
+    match crate::server_only::tag::with_alias_targets(&db, tags).await {
+        Ok(tags) => Ok(tags),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// How many suggestions `autocomplete_tags` returns.
+pub const AUTOCOMPLETE_LIMIT: u32 = 10;
+
+/// Tags whose name starts with `prefix`, most used first, for suggestions
+/// while typing.
+#[server(AutocompleteTags, "/api")]
+pub async fn autocomplete_tags(prefix: String) -> Result<Vec<ListedTag>, ServerFnError> {
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    match crate::server_only::tag::autocomplete_tags(&db, prefix, AUTOCOMPLETE_LIMIT).await {
+        Ok(tags) => Ok(tags),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
 }
 
 #[server(AddNewTag, "/api")]
 pub async fn add_new_tag(name: String) -> Result<u64, ServerFnError> {
+    use crate::models::permission::Permission;
+    use crate::models::tag::{Tag, TagCategory};
+    let user_id = crate::server_only::auth::authorize(Permission::CreateTag).await?;
     let db = crate::server_only::db::get_db_connection().await?;
 
     let new_tag = Tag {
@@ -28,13 +51,183 @@ pub async fn add_new_tag(name: String) -> Result<u64, ServerFnError> {
         use_count: 0,
         description: String::new(),
         is_alias: None,
-        category: 0,
+        category: TagCategory::General,
         implications: vec![],
     };
 
     // Use the add_new_tag function we created earlier
-    match crate::server_only::tag::add_new_tag(&db, &new_tag).await {
+    match crate::server_only::tag::add_new_tag(&db, &new_tag, user_id).await {
         Ok(custom_id) => Ok(custom_id),
         Err(e) => Err(ServerFnError::ServerError(e.to_string())),
     }
 }
+
+/// Replaces the tags `name` implies. With `back_apply`, posts that already
+/// have the tag get the implied tags as well.
+#[server(SetTagImplications, "/api")]
+pub async fn set_tag_implications(
+    name: String,
+    implications: Vec<String>,
+    back_apply: bool,
+) -> Result<(), ServerFnError> {
+    use crate::models::permission::Permission;
+    use crate::server_only::alias::resolve_tags_by_names;
+    use crate::server_only::tag::get_tag_by_name;
+    let user_id = crate::server_only::auth::authorize(Permission::EditTag).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    // Not resolved, an alias would change the implications of its target.
+    let tag = match get_tag_by_name(&db, name.clone()).await {
+        Ok(Some(tag)) if tag.is_alias.is_some() => {
+            return Err(ServerFnError::ServerError(format!(
+                "`{}` is an alias, aliases cannot imply other tags",
+                name
+            )))
+        }
+        Ok(Some(tag)) => tag,
+        Ok(None) => {
+            return Err(ServerFnError::ServerError(format!(
+                "the tag `{}` does not exist",
+                name
+            )))
+        }
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+
+    let targets = match resolve_tags_by_names(&db, implications.clone()).await {
+        Ok(targets) => targets,
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+    if let Some(missing) = implications
+        .iter()
+        .find(|name| !targets.contains_key(*name))
+    {
+        return Err(ServerFnError::ServerError(format!(
+            "the tag `{}` does not exist",
+            missing
+        )));
+    }
+
+    let target_ids = targets.values().map(|tag| tag.custom_id).collect();
+    match crate::server_only::implication::set_implications(
+        &db,
+        tag.custom_id,
+        target_ids,
+        back_apply,
+        user_id,
+    )
+    .await
+    {
+        Ok(()) => Ok(()),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Makes `name` an alias of `target`, moving its posts over to `target`.
+#[server(CreateTagAlias, "/api")]
+pub async fn create_tag_alias(name: String, target: String) -> Result<(), ServerFnError> {
+    use crate::models::permission::Permission;
+    use crate::server_only::tag::get_tag_by_name;
+    let user_id = crate::server_only::auth::authorize(Permission::EditTag).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    let mut ids = vec![];
+    for name in [name, target] {
+        match get_tag_by_name(&db, name.clone()).await {
+            Ok(Some(tag)) => ids.push(tag.custom_id),
+            Ok(None) => {
+                return Err(ServerFnError::ServerError(format!(
+                    "the tag `{}` does not exist",
+                    name
+                )))
+            }
+            Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+        }
+    }
+
+    match crate::server_only::alias::create_alias(&db, ids[0], ids[1], user_id).await {
+        Ok(()) => Ok(()),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Rebuilds every tag's `use_count` from the posts.
+#[server(RecountTagUses, "/api")]
+pub async fn recount_tag_uses() -> Result<(), ServerFnError> {
+    use crate::models::permission::Permission;
+    crate::server_only::auth::authorize(Permission::RecountTags).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    match crate::server_only::tag::recount_tag_uses(&db).await {
+        Ok(()) => Ok(()),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// The tag called `name`, for its page.
+#[server(GetTag, "/api")]
+pub async fn get_tag(name: String) -> Result<Option<TagDetails>, ServerFnError> {
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    match crate::server_only::tag_edit::get_tag_details(&db, name).await {
+        Ok(details) => Ok(details),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Changes the description, category, alias target and implications of the
+/// tag called `name`. Edits that cannot be made come back as a `TagEditError`.
+#[server(UpdateTag, "/api")]
+pub async fn update_tag(
+    name: String,
+    edit: TagEdit,
+) -> Result<Result<(), TagEditError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let user_id = crate::server_only::auth::authorize(Permission::EditTag).await?;
+    if edit.description.is_some() {
+        crate::server_only::auth::authorize(Permission::EditWiki).await?;
+    }
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(crate::server_only::tag_edit::edit_tag(&db, name, edit, user_id).await)
+}
+
+/// Renames the tag called `name` to `new_name`.
+#[server(RenameTag, "/api")]
+pub async fn rename_tag(
+    name: String,
+    new_name: String,
+) -> Result<Result<(), TagChangeError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let user_id = crate::server_only::auth::authorize(Permission::RenameTag).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(crate::server_only::tag::rename_tag(&db, name, new_name, user_id).await)
+}
+
+/// Merges the tag called `name` into `target`, leaving `name` as an alias.
+#[server(MergeTags, "/api")]
+pub async fn merge_tags(
+    name: String,
+    target: String,
+) -> Result<Result<(), TagChangeError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let user_id = crate::server_only::auth::authorize(Permission::MergeTags).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(crate::server_only::tag::merge_tags(&db, name, target, user_id).await)
+}
+
+/// Deletes the tag called `name`. Unless `force` is set, tags that are
+/// still in use are left alone.
+#[server(DeleteTag, "/api")]
+pub async fn delete_tag(
+    name: String,
+    force: bool,
+) -> Result<Result<(), TagDeleteError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let user_id = crate::server_only::auth::authorize(Permission::DeleteTag).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(crate::server_only::tag::delete_tag(&db, name, force, user_id).await)
+}
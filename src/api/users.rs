@@ -0,0 +1,98 @@
+use leptos::*;
+
+use crate::models::permission::Role;
+use crate::models::user::{User, UserError};
+
+/// The user this browser is logged in as.
+#[server(GetCurrentUser, "/api")]
+pub async fn get_current_user() -> Result<Option<User>, ServerFnError> {
+    crate::server_only::auth::current_user().await
+}
+
+#[cfg(feature = "ssr")]
+async fn start_session<C: surrealdb::Connection>(
+    db: &surrealdb::Surreal<C>,
+    user: &User,
+) -> Result<(), ServerFnError> {
+    use crate::server_only::auth::{session_cookie, set_cookie};
+
+    match crate::server_only::user::create_session(db, user.custom_id).await {
+        Ok(token) => set_cookie(&session_cookie(&token)),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Creates an account and logs it in.
+#[server(Register, "/api")]
+pub async fn register(
+    name: String,
+    password: String,
+) -> Result<Result<User, UserError>, ServerFnError> {
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    let user = crate::api::split_error(
+        crate::server_only::user::register_user(&db, &name, &password).await,
+    )?;
+    if let Ok(user) = &user {
+        start_session(&db, user).await?;
+    }
+    Ok(user)
+}
+
+#[server(Login, "/api")]
+pub async fn login(
+    name: String,
+    password: String,
+) -> Result<Result<User, UserError>, ServerFnError> {
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    let user = crate::api::split_error(
+        crate::server_only::user::authenticate(&db, &name, &password).await,
+    )?;
+    if let Ok(user) = &user {
+        start_session(&db, user).await?;
+    }
+    Ok(user)
+}
+
+/// Ends the session of this browser.
+#[server(Logout, "/api")]
+pub async fn logout() -> Result<(), ServerFnError> {
+    use crate::server_only::auth::{expired_session_cookie, session_token, set_cookie};
+
+    let headers: http::HeaderMap = leptos_axum::extract().await?;
+    if let Some(token) = session_token(&headers) {
+        let db = crate::server_only::db::get_db_connection().await?;
+        if let Err(e) = crate::server_only::user::end_session(&db, &token).await {
+            return Err(ServerFnError::ServerError(e.to_string()));
+        }
+    }
+
+    set_cookie(&expired_session_cookie())
+}
+
+/// Gives the user called `name` another role, up to the role of the caller.
+#[server(SetUserRole, "/api")]
+pub async fn set_user_role(
+    name: String,
+    role: Role,
+) -> Result<Result<User, UserError>, ServerFnError> {
+    use crate::models::permission::Permission;
+
+    crate::server_only::auth::authorize(Permission::ManageUsers).await?;
+    // Without a login the change is made as nobody, user #0, not as the
+    // server, which may change anyone.
+    let caller = crate::server_only::auth::current_user()
+        .await?
+        .unwrap_or(User {
+            custom_id: 0,
+            name: crate::models::user::ANONYMOUS.to_string(),
+            created_at: 0,
+            role: Role::Anonymous,
+        });
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(
+        crate::server_only::user::set_user_role(&db, &name, role, Some(&caller)).await,
+    )
+}
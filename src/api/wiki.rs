@@ -0,0 +1,47 @@
+use leptos::*;
+
+use crate::models::wiki::{WikiError, WikiPage};
+
+/// The wiki page of the tag called `name`.
+#[server(GetWikiPage, "/api")]
+pub async fn get_wiki_page(name: String) -> Result<Option<WikiPage>, ServerFnError> {
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    match crate::server_only::wiki::get_wiki_page(&db, name).await {
+        Ok(page) => Ok(page),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    }
+}
+
+/// Saves `body` as a new version of the wiki page of the tag called `name`,
+/// unless the page changed after `base_version`. Returns the new version.
+#[server(SaveWikiPage, "/api")]
+pub async fn save_wiki_page(
+    name: String,
+    body: String,
+    base_version: u32,
+) -> Result<Result<u32, WikiError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let user_id = crate::server_only::auth::authorize(Permission::EditWiki).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(
+        crate::server_only::wiki::save_wiki_page(&db, name, body, base_version, user_id).await,
+    )
+}
+
+/// Restores `version` of the wiki page of the tag called `name` as a new
+/// version.
+#[server(RevertWikiPage, "/api")]
+pub async fn revert_wiki_page(
+    name: String,
+    version: u32,
+) -> Result<Result<u32, WikiError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let user_id = crate::server_only::auth::authorize(Permission::EditWiki).await?;
+    let db = crate::server_only::db::get_db_connection().await?;
+
+    crate::api::split_error(
+        crate::server_only::wiki::revert_wiki_page(&db, name, version, user_id).await,
+    )
+}
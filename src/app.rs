@@ -1,4 +1,5 @@
 use crate::components::file_upload::FileUpload;
+use crate::components::user_menu::UserMenu;
 use crate::error_template::{AppError, ErrorTemplate};
 use leptos::*;
 use leptos_meta::*;
@@ -21,10 +22,16 @@ pub fn App() -> impl IntoView {
             outside_errors.insert_with_default_key(AppError::NotFound);
             view! { <ErrorTemplate outside_errors /> }.into_view()
         }>
+            <UserMenu />
             <main>
                 <Routes>
                     <Route path="" view=FileUpload />
+                    <Route path="/posts" view=crate::pages::PostGrid />
+                    // Async so the title and OpenGraph tags are in the initial HTML.
+                    <Route path="/post/:id" view=crate::pages::PostPage ssr=SsrMode::Async />
                     <Route path="/tags" view=crate::pages::TagTable />
+                    <Route path="/tag/:name" view=crate::pages::TagPage />
+                    <Route path="/login" view=crate::pages::LoginPage />
                 </Routes>
             </main>
         </Router>
@@ -1,4 +1,8 @@
+use crate::components::tag_input::TagInput;
+use crate::error_template::server_error_message;
 use leptos::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use web_sys::{window, FormData, HtmlFormElement, SubmitEvent};
 
 #[component]
@@ -73,6 +77,9 @@ pub fn FileUpload() -> impl IntoView {
                             }
                             class="mb-6"
                         >
+                            <div class="mb-4">
+                                <TagInput name="tags" placeholder="Tags" dark_mode />
+                            </div>
                             <div class="mb-4">
                                 <input
                                     type="file"
@@ -101,13 +108,33 @@ pub fn FileUpload() -> impl IntoView {
                                 if upload_action.input().get().is_none()
                                     && upload_action.value().get().is_none()
                                 {
-                                    "Upload a file.".to_string()
+                                    "Upload a file.".into_view()
                                 } else if upload_action.pending().get() {
-                                    "Uploading...".to_string()
-                                } else if let Some(Ok(())) = upload_action.value().get() {
-                                    "Success!".to_string()
+                                    "Uploading...".into_view()
                                 } else {
-                                    format!("Server error: {:?}", upload_action.value().get())
+                                    match upload_action.value().get() {
+                                        Some(Ok(Ok(post_id))) => {
+                                            view! {
+                                                "Success! "
+                                                <a href=format!("/post/{}", post_id) class="underline">
+                                                    {format!("View post #{}", post_id)}
+                                                </a>
+                                            }
+                                                .into_view()
+                                        }
+                                        Some(Ok(Err(UploadError::Duplicate(post_id)))) => {
+                                            view! {
+                                                "This file was already uploaded as "
+                                                <a href=format!("/post/{}", post_id) class="underline">
+                                                    {format!("post #{}", post_id)}
+                                                </a>
+                                            }
+                                                .into_view()
+                                        }
+                                        Some(Ok(Err(e))) => e.to_string().into_view(),
+                                        Some(Err(e)) => server_error_message(&e).into_view(),
+                                        None => "".into_view(),
+                                    }
                                 }
                             }}
                         </p>
@@ -133,69 +160,226 @@ pub fn FileUpload() -> impl IntoView {
     }
 }
 
+/// Why an upload was refused. Server failures come back as the outer
+/// `ServerFnError` instead.
+#[derive(Clone, Debug, PartialEq, Eq, Error, Serialize, Deserialize)]
+pub enum UploadError {
+    #[error("Duplicate of post #{0}")]
+    Duplicate(u64),
+    #[error("Invalid file: {0}")]
+    InvalidFile(String),
+    #[error("File is larger than the upload limit of {0} bytes")]
+    TooLarge(u64),
+    #[error("Unknown tag: {0}")]
+    UnknownTag(String),
+}
+
+/// Stores the uploaded file and returns the id of the new post.
 #[server(input = server_fn::codec::MultipartFormData)]
-pub async fn upload_post(data: server_fn::codec::MultipartData) -> Result<(), ServerFnError> {
+pub async fn upload_post(
+    data: server_fn::codec::MultipartData,
+) -> Result<Result<u64, UploadError>, ServerFnError> {
+    use crate::models::permission::Permission;
+    let uploader_id = crate::server_only::auth::authorize(Permission::UploadPost).await?;
+
+    crate::api::split_error(save_upload(data, uploader_id).await)
+}
+
+#[cfg(feature = "ssr")]
+async fn save_upload(
+    data: server_fn::codec::MultipartData,
+    uploader_id: u64,
+) -> anyhow::Result<u64> {
+    use crate::models::post::{Post, PostType, Safety};
+    use crate::server_only::config::{
+        max_image_pixels, max_upload_size, upload_temp_dir, uploads_dir,
+    };
+    use crate::server_only::media::{frame_count, image_dimensions, sniff_mime};
+    use crate::server_only::post::{add_new_post, delete_post, get_post_by_hash};
+    use crate::server_only::thumbnail::queue_thumbnails;
+    use crate::server_only::upload::{receive_file, ReceivedFile};
+    use crate::server_only::video::video_metadata;
+
     let mut data = data.into_inner().unwrap();
-    let acceptable_extensions = ["png", "webp", "avif", "jpg", "jpeg"];
+    // The tags may arrive before or after the file, so the post is only
+    // created once every field has been read.
+    let mut upload = None;
+    let mut tags = vec![];
 
-    while let Ok(Some(mut field)) = data.next_field().await {
-        let file_name = field.file_name().unwrap_or_default().to_string();
-        let file_extension = std::path::Path::new(field.file_name().unwrap_or_default())
-            .extension()
-            .and_then(std::ffi::OsStr::to_str)
-            .unwrap_or_default()
-            .to_string();
-        if !acceptable_extensions.contains(&file_extension.as_str()) {
-            return Err(ServerFnError::Args(
-                "Invalid file extension. Upload a image.".to_string(),
-            ));
+    let uploads_dir = uploads_dir();
+    match tokio::fs::create_dir(&uploads_dir).await {
+        Ok(()) => (),
+        Err(_error) => (),
+    };
+
+    while let Ok(Some(field)) = data.next_field().await {
+        if field.name() == Some("tags") {
+            use crate::server_only::alias::{resolve_tag_ids, AliasError};
+
+            let names = field
+                .text()
+                .await
+                .map_err(|e| UploadError::InvalidFile(e.to_string()))?;
+            let db = crate::server_only::db::get_db_connection().await?;
+            tags = resolve_tag_ids(&db, &names).await.map_err(|e| {
+                match e.downcast::<AliasError>() {
+                    Ok(AliasError::UnknownTagName(name)) => UploadError::UnknownTag(name).into(),
+                    Ok(e) => e.into(),
+                    Err(e) => e,
+                }
+            })?;
+            continue;
         }
 
+        let file_name = field.file_name().unwrap_or_default().to_string();
+
         if !file_name.is_empty() {
-            match tokio::fs::create_dir("./uploads/").await {
-                Ok(()) => (),
-                Err(_error) => (),
+            if upload.is_some() {
+                return Err(
+                    UploadError::InvalidFile("Upload one file at a time.".to_string()).into(),
+                );
+            }
+
+            let ReceivedFile {
+                temp_file,
+                header,
+                sha256_hash,
+                md5_hash,
+                file_size,
+            } = receive_file(field, &upload_temp_dir(), max_upload_size()).await?;
+
+            let Some(mime_type) = sniff_mime(&header) else {
+                return Err(UploadError::InvalidFile(
+                    "Unsupported file type. Upload a PNG, JPEG, GIF, WebP or AVIF image, or a WebM or MP4 video."
+                        .to_string(),
+                ).into());
             };
-            let mut total_file: Vec<bytes::Bytes> = vec![];
 
-            while let Ok(Some(chunk)) = field.chunk().await {
-                total_file.push(chunk.clone());
-            }
+            let post_type = if mime_type.type_() == mime::VIDEO {
+                PostType::Video
+            } else {
+                PostType::Image
+            };
 
-            let file_name = format!(
-                "./uploads/{}.{}",
-                hash_bytes_vec(&total_file)
-                    .iter()
-                    .map(|b| format!("{:02X}", b))
-                    .collect::<Vec<String>>()
-                    .join(""),
-                file_extension
-            )
-            .to_lowercase();
+            let (image_width, image_height, duration, has_audio, frame_count) = match post_type {
+                PostType::Image => {
+                    let Some((width, height)) = image_dimensions(&header) else {
+                        return Err(UploadError::InvalidFile(
+                            "Could not read the image dimensions.".to_string(),
+                        )
+                        .into());
+                    };
+
+                    // Frames can be spread over the whole file, so count them
+                    // from the file rather than from the header.
+                    let path = temp_file.path.clone();
+                    let image_mime = mime_type.clone();
+                    let frame_count = tokio::task::spawn_blocking(move || {
+                        let file = std::fs::File::open(path).ok()?;
+                        frame_count(&image_mime, file)
+                    })
+                    .await?;
+
+                    let Some(frame_count) = frame_count else {
+                        return Err(UploadError::InvalidFile(
+                            "Could not read the image frames.".to_string(),
+                        )
+                        .into());
+                    };
+                    (width, height, None, false, Some(frame_count))
+                }
+                PostType::Video => {
+                    // The metadata may sit at the very end of an MP4, so read
+                    // it back from the file rather than from the header.
+                    let path = temp_file.path.clone();
+                    let video_mime = mime_type.clone();
+                    let metadata = tokio::task::spawn_blocking(move || {
+                        let file = std::fs::File::open(path).ok()?;
+                        video_metadata(&video_mime, std::io::BufReader::new(file))
+                    })
+                    .await?;
+
+                    let Some(metadata) = metadata else {
+                        return Err(UploadError::InvalidFile(
+                            "Could not read the video metadata.".to_string(),
+                        )
+                        .into());
+                    };
+                    (
+                        metadata.width,
+                        metadata.height,
+                        Some(metadata.duration),
+                        metadata.has_audio,
+                        None,
+                    )
+                }
+            };
 
-            let mut file = tokio::fs::File::create(&file_name).await?;
-            for byte in total_file {
-                tokio::io::AsyncWriteExt::write_all(&mut file, &byte).await?;
+            if image_width as u64 * image_height as u64 > max_image_pixels() {
+                return Err(UploadError::InvalidFile(format!(
+                    "Image is too large ({}x{}), the limit is {} pixels.",
+                    image_width,
+                    image_height,
+                    max_image_pixels()
+                ))
+                .into());
             }
 
-            println!("File '{}' saved successfully.", file_name);
+            let db = crate::server_only::db::get_db_connection().await?;
+            if let Some(existing) = get_post_by_hash(&db, &sha256_hash).await? {
+                return Err(UploadError::Duplicate(existing.custom_id).into());
+            }
+
+            let post = Post {
+                custom_id: 0, // This will be replaced by the database
+                image_height,
+                image_width,
+                post_type: post_type.clone(),
+                mime_type,
+                safety: Safety::Unsafe,
+                sha256_hash,
+                md5_hash,
+                uploader_id,
+                tags: vec![], // Set once all fields are read
+                duration,
+                has_audio,
+                frame_count,
+                animated: post_type == PostType::Video || frame_count.is_some_and(|n| n > 1),
+                file_size,
+                uploaded_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs())
+                    .unwrap_or_default(),
+                score: 0,
+            };
+
+            upload = Some((post, temp_file));
         }
     }
 
-    Ok(())
-}
-#[cfg(feature = "ssr")]
-fn hash_bytes_vec(vec: &Vec<bytes::Bytes>) -> [u8; 32] {
-    use sha2::Digest;
-    use sha2::Sha256;
-    // Create a hasher
-    let mut hasher = Sha256::new();
-
-    // Update the hasher with each Bytes object
-    for bytes in vec {
-        hasher.update(bytes);
+    let Some((post, temp_file)) = upload else {
+        return Err(UploadError::InvalidFile("No file was uploaded.".to_string()).into());
+    };
+    let post = Post { tags, ..post };
+
+    // The file only moves into place once the post exists, so a failed
+    // insert leaves nothing behind, and a failed move takes the post out again.
+    let db = crate::server_only::db::get_db_connection().await?;
+    let post = Post {
+        custom_id: add_new_post(&db, &post).await?,
+        ..post
+    };
+    if let Err(e) = temp_file.persist(&uploads_dir.join(post.file_name())).await {
+        delete_post(&db, post.custom_id).await?;
+        return Err(e.into());
+    }
+    if let Err(e) = queue_thumbnails(&post, &uploads_dir) {
+        logging::error!(
+            "could not queue thumbnails for post #{}: {}",
+            post.custom_id,
+            e
+        );
     }
 
-    // Finalize and return the hash
-    hasher.finalize().into()
+    Ok(post.custom_id)
 }
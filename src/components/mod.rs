@@ -1,3 +1,7 @@
 pub mod file_upload;
 pub mod modal;
+pub mod post;
 pub mod tag;
+pub mod tag_input;
+pub mod user_menu;
+pub mod wiki;
@@ -0,0 +1,80 @@
+use crate::models::post::{Post, PostType, Safety};
+use leptos::*;
+use web_sys::HtmlImageElement;
+
+/// The full size media of a post: an image, or a video using its sample as
+/// the poster until playback starts.
+#[component]
+pub fn PostMedia(post: Post) -> impl IntoView {
+    let src = format!("/uploads/{}", post.file_name());
+
+    match post.post_type {
+        PostType::Image => view! { <img src=src class="max-w-full h-auto" /> }.into_view(),
+        PostType::Video => view! {
+            <video
+                src=src
+                poster=format!("/uploads/{}", post.sample_file_name())
+                controls=true
+                loop=true
+                muted=!post.has_audio
+                class="max-w-full h-auto"
+            ></video>
+        }
+        .into_view(),
+    }
+}
+
+#[component]
+pub fn SafetyBadge(safety: Safety) -> impl IntoView {
+    let (label, colour) = match safety {
+        Safety::Safe => ("Safe", "bg-green-600"),
+        Safety::Sketchy => ("Sketchy", "bg-yellow-500"),
+        Safety::Unsafe => ("Unsafe", "bg-red-600"),
+    };
+
+    view! {
+        <span class=format!(
+            "px-2 py-0.5 text-xs font-semibold text-white rounded {}",
+            colour,
+        )>{label}</span>
+    }
+}
+
+/// A grid tile linking to the post page, showing its thumbnail.
+#[component]
+pub fn PostTile(post: Post, #[prop(into)] dark_mode: Signal<bool>) -> impl IntoView {
+    let original = format!("/uploads/{}", post.file_name());
+    let is_image = post.post_type == PostType::Image;
+
+    // Thumbnails are generated in the background (and not at all for formats
+    // the server cannot decode), so fall back to the original image.
+    let on_error = move |ev: ev::ErrorEvent| {
+        let img = event_target::<HtmlImageElement>(&ev);
+        if is_image && !img.src().ends_with(&original) {
+            img.set_src(&original);
+        }
+    };
+
+    view! {
+        <a
+            href=format!("/post/{}", post.custom_id)
+            class=move || {
+                format!(
+                    "relative flex items-center justify-center w-48 h-48 rounded-lg overflow-hidden shadow {}",
+                    if dark_mode() { "bg-gray-800" } else { "bg-gray-100" },
+                )
+            }
+        >
+            <img
+                src=format!("/uploads/{}", post.thumbnail_file_name())
+                alt=format!("Post #{}", post.custom_id)
+                loading="lazy"
+                on:error=on_error
+                class="max-w-full max-h-full"
+            />
+            <span class="absolute top-1 left-1">
+                <SafetyBadge safety=post.safety />
+            </span>
+        </a>
+    }
+}
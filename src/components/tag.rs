@@ -1,4 +1,6 @@
-use crate::api::tags::AddNewTag;
+use crate::api::tags::{AddNewTag, DeleteTag};
+use crate::error_template::server_error_message;
+use crate::models::tag::TagDeleteError;
 use leptos::*;
 
 #[component]
@@ -73,7 +75,7 @@ pub fn AddTagForm(#[prop(into)] dark_mode: Signal<bool>) -> impl IntoView {
                             } else if let Some(Ok(custom_id)) = add_tag.value().get() {
                                 format!("Tag added successfully with ID: {}", custom_id)
                             } else if let Some(Err(e)) = add_tag.value().get() {
-                                format!("Error: {}", e)
+                                server_error_message(&e)
                             } else {
                                 "Add a new tag.".to_string()
                             }
@@ -84,3 +86,77 @@ pub fn AddTagForm(#[prop(into)] dark_mode: Signal<bool>) -> impl IntoView {
         </div>
     }
 }
+
+/// Asks before deleting the tag called `name`, and again if it is still in
+/// use, since deleting it then removes it from posts and other tags.
+#[component]
+pub fn DeleteTagForm(
+    name: String,
+    delete_tag: Action<DeleteTag, Result<Result<(), TagDeleteError>, ServerFnError>>,
+    #[prop(into)] dark_mode: Signal<bool>,
+) -> impl IntoView {
+    let force_name = name.clone();
+    let dispatch = move |force: bool| {
+        delete_tag.dispatch(DeleteTag {
+            name: name.clone(),
+            force,
+        })
+    };
+    let dispatch_force = dispatch.clone();
+
+    view! {
+        <div class="mx-auto max-w-md">
+            <h3 class=move || {
+                format!(
+                    "mb-4 text-2xl font-semibold {}",
+                    if dark_mode() { "text-gray-100" } else { "text-gray-900" },
+                )
+            }>{format!("Delete {}?", force_name)}</h3>
+            {move || match delete_tag.value().get() {
+                Some(Ok(Err(TagDeleteError::InUse { posts, aliases, implied_by }))) => {
+                    let dispatch_force = dispatch_force.clone();
+                    view! {
+                        <p class="mb-4">
+                            {format!(
+                                "It is still used by {} posts, {} aliases and {} implications. Deleting it anyway removes it from all of them and deletes the aliases.",
+                                posts,
+                                aliases,
+                                implied_by,
+                            )}
+                        </p>
+                        <button
+                            class="py-2 px-4 w-full font-bold text-white bg-red-600 rounded-lg hover:bg-red-700"
+                            on:click=move |_| dispatch_force(true)
+                        >
+                            "Delete Anyway"
+                        </button>
+                    }
+                        .into_view()
+                }
+                result => {
+                    let dispatch = dispatch.clone();
+                    view! {
+                        {match result {
+                            Some(Ok(Err(e))) => {
+                                view! { <p class="mb-4 text-red-600">{e.to_string()}</p> }.into_view()
+                            }
+                            Some(Err(e)) => {
+                                view! { <p class="mb-4 text-red-600">{server_error_message(&e)}</p> }
+                                    .into_view()
+                            }
+                            _ => ().into_view(),
+                        }}
+                        <button
+                            class="py-2 px-4 w-full font-bold text-white bg-red-500 rounded-lg hover:bg-red-600"
+                            disabled=move || delete_tag.pending().get()
+                            on:click=move |_| dispatch(false)
+                        >
+                            "Delete"
+                        </button>
+                    }
+                        .into_view()
+                }
+            }}
+        </div>
+    }
+}
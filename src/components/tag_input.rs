@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use crate::api::tags::autocomplete_tags;
+use crate::models::tag::ListedTag;
+use leptos::*;
+
+/// How long typing has to pause before suggestions are fetched.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The start of the tag being typed at the end of `text`, without a search
+/// operator in front. Empty after whitespace and for metatags like `rating:`.
+pub fn current_prefix(text: &str) -> String {
+    if text.is_empty() || text.ends_with(char::is_whitespace) {
+        return String::new();
+    }
+
+    let word = text.split_whitespace().last().unwrap_or_default();
+    let word = word.strip_prefix(['-', '~']).unwrap_or(word);
+    if word.contains(':') {
+        return String::new();
+    }
+
+    word.to_lowercase()
+}
+
+/// Replaces the tag being typed at the end of `text` with `name`, keeping
+/// its search operator, and starts the next tag.
+pub fn complete(text: &str, name: &str) -> String {
+    let start = text
+        .rfind(char::is_whitespace)
+        .map(|i| i + text[i..].chars().next().unwrap().len_utf8())
+        .unwrap_or(0);
+    let word = &text[start..];
+    let operator = match word.chars().next() {
+        Some(c @ ('-' | '~')) => c.to_string(),
+        _ => String::new(),
+    };
+
+    format!("{}{}{} ", &text[..start], operator, name)
+}
+
+/// A text input for whitespace separated tags that suggests tags for the
+/// one being typed. Up and down pick a suggestion, enter or tab take it.
+#[component]
+pub fn TagInput(
+    /// Name of the input in its form.
+    name: &'static str,
+    /// The text the input starts with. It is reset whenever this changes.
+    #[prop(optional, into)]
+    initial: MaybeSignal<String>,
+    /// Holds the text of the input, for use outside of forms. Takes the
+    /// place of `initial`.
+    #[prop(optional)]
+    value: Option<RwSignal<String>>,
+    #[prop(optional)] placeholder: &'static str,
+    #[prop(into)] dark_mode: Signal<bool>,
+) -> impl IntoView {
+    let text = value.unwrap_or_else(|| {
+        let text = create_rw_signal(initial.get_untracked());
+        create_effect(move |_| text.set(initial.get()));
+        text
+    });
+
+    let (prefix, set_prefix) = create_signal(String::new());
+    let highlighted = create_rw_signal(None::<usize>);
+    let pending = store_value(None::<leptos_dom::helpers::TimeoutHandle>);
+
+    let suggestions = create_local_resource(prefix, |prefix| async move {
+        if prefix.is_empty() {
+            return vec![];
+        }
+        autocomplete_tags(prefix).await.unwrap_or_default()
+    });
+    let current = move || {
+        if prefix.with(String::is_empty) {
+            vec![]
+        } else {
+            suggestions.get().unwrap_or_default()
+        }
+    };
+
+    let close = move || {
+        if let Some(handle) = pending.get_value() {
+            handle.clear();
+        }
+        set_prefix.set(String::new());
+        highlighted.set(None);
+    };
+
+    let accept = move |tag: ListedTag| {
+        let name = tag.alias_of.unwrap_or(tag.tag.name);
+        text.update(|text| *text = complete(text, &name));
+        close();
+    };
+
+    let on_input = move |ev| {
+        let value = event_target_value(&ev);
+        text.set(value.clone());
+        highlighted.set(None);
+
+        if let Some(handle) = pending.get_value() {
+            handle.clear();
+        }
+        let handle =
+            set_timeout_with_handle(move || set_prefix.set(current_prefix(&value)), DEBOUNCE);
+        pending.set_value(handle.ok());
+    };
+
+    let on_keydown = move |ev: ev::KeyboardEvent| {
+        let count = current().len();
+        match ev.key().as_str() {
+            "ArrowDown" if count > 0 => {
+                ev.prevent_default();
+                highlighted.update(|i| *i = Some(i.map_or(0, |i| (i + 1) % count)));
+            }
+            "ArrowUp" if count > 0 => {
+                ev.prevent_default();
+                highlighted.update(|i| *i = Some(i.map_or(count - 1, |i| (i + count - 1) % count)));
+            }
+            "Enter" | "Tab" => {
+                if let Some(tag) = highlighted.get().and_then(|i| current().get(i).cloned()) {
+                    ev.prevent_default();
+                    accept(tag);
+                }
+            }
+            "Escape" => close(),
+            _ => {}
+        }
+    };
+
+    view! {
+        <div class="relative grow">
+            <input
+                type="text"
+                name=name
+                autocomplete="off"
+                placeholder=placeholder
+                prop:value=text
+                on:input=on_input
+                on:keydown=on_keydown
+                on:blur=move |_| close()
+                class=move || {
+                    format!(
+                        "w-full px-4 py-2 rounded-lg border {}",
+                        if dark_mode() {
+                            "bg-gray-800 border-gray-600 text-white"
+                        } else {
+                            "bg-white border-gray-300 text-black"
+                        },
+                    )
+                }
+            />
+            <Show when=move || !current().is_empty()>
+                <ul class=move || {
+                    format!(
+                        "absolute z-10 mt-1 w-full rounded-lg border shadow-lg {}",
+                        if dark_mode() {
+                            "bg-gray-800 border-gray-600"
+                        } else {
+                            "bg-white border-gray-300"
+                        },
+                    )
+                }>
+                    {move || {
+                        current()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, suggestion)| {
+                                let ListedTag { tag, alias_of } = suggestion.clone();
+                                // Aliases are never used themselves, so their count
+                                // would always be zero.
+                                let (label, use_count) = match alias_of {
+                                    Some(target) => (format!("{} → {}", tag.name, target), None),
+                                    None => (tag.name.clone(), Some(tag.use_count)),
+                                };
+                                view! {
+                                    <li
+                                        class=move || {
+                                            format!(
+                                                "flex justify-between px-4 py-1 cursor-pointer {}",
+                                                if highlighted.get() == Some(i) {
+                                                    if dark_mode() { "bg-gray-700" } else { "bg-gray-100" }
+                                                } else {
+                                                    ""
+                                                },
+                                            )
+                                        }
+                                        // Taking the suggestion on mousedown beats
+                                        // the blur that would close the list.
+                                        on:mousedown=move |ev| {
+                                            ev.prevent_default();
+                                            accept(suggestion.clone());
+                                        }
+                                    >
+                                        <span class=tag.category.colour()>{label}</span>
+                                        <span class="text-xs text-gray-500">{use_count}</span>
+                                    </li>
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    }}
+                </ul>
+            </Show>
+        </div>
+    }
+}
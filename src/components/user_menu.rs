@@ -0,0 +1,44 @@
+use crate::api::users::{get_current_user, Logout};
+use leptos::*;
+use leptos_router::*;
+
+/// Links to the main pages and the account of the current user.
+#[component]
+pub fn UserMenu() -> impl IntoView {
+    let logout = create_server_action::<Logout>();
+    let location = use_location();
+    // Logging in navigates, so looking again on every page keeps this current.
+    let user = create_resource(
+        move || (location.pathname.get(), logout.version().get()),
+        |_| async move { get_current_user().await.ok().flatten() },
+    );
+
+    view! {
+        <nav class="flex gap-4 justify-between py-2 px-4 text-sm border-b border-gray-200">
+            <div class="flex gap-4">
+                <A href="/posts" class="hover:underline">"Posts"</A>
+                <A href="/tags" class="hover:underline">"Tags"</A>
+                <A href="/" class="hover:underline">"Upload"</A>
+            </div>
+            <Transition fallback=|| ()>
+                {move || {
+                    user.get()
+                        .map(|user| match user {
+                            Some(user) => {
+                                view! {
+                                    <div class="flex gap-4">
+                                        <span>{user.name}</span>
+                                        <button class="hover:underline" on:click=move |_| logout.dispatch(Logout {})>
+                                            "Log out"
+                                        </button>
+                                    </div>
+                                }
+                                    .into_view()
+                            }
+                            None => view! { <A href="/login" class="hover:underline">"Log in"</A> }.into_view(),
+                        })
+                }}
+            </Transition>
+        </nav>
+    }
+}
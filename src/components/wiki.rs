@@ -0,0 +1,186 @@
+use crate::api::wiki::{get_wiki_page, RevertWikiPage, SaveWikiPage};
+use crate::error_template::server_error_message;
+use crate::models::date::format_timestamp;
+use crate::models::user::user_label;
+use crate::models::wiki::{diff_lines, DiffLine, WikiError, WikiPage, WikiVersion};
+use leptos::*;
+
+fn wiki_message(result: Option<Result<Result<u32, WikiError>, ServerFnError>>) -> View {
+    match result {
+        Some(Ok(Err(error))) => {
+            view! { <p class="text-red-700">{error.to_string()}</p> }.into_view()
+        }
+        Some(Err(e)) => {
+            view! { <p class="text-red-700">{server_error_message(&e)}</p> }.into_view()
+        }
+        _ => ().into_view(),
+    }
+}
+
+/// The wiki page of the tag called `name`, with its editor and versions.
+#[component]
+pub fn TagWiki(name: String) -> impl IntoView {
+    let save = create_server_action::<SaveWikiPage>();
+    let revert = create_server_action::<RevertWikiPage>();
+    let page = create_resource(
+        move || (name.clone(), save.version().get(), revert.version().get()),
+        move |(name, _, _)| async move { get_wiki_page(name).await.ok().flatten() },
+    );
+
+    view! {
+        <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+            {move || page.get().flatten().map(|page| view! { <WikiContent page save revert /> })}
+        </Suspense>
+    }
+}
+
+#[component]
+fn WikiContent(
+    page: WikiPage,
+    save: Action<SaveWikiPage, Result<Result<u32, WikiError>, ServerFnError>>,
+    revert: Action<RevertWikiPage, Result<Result<u32, WikiError>, ServerFnError>>,
+) -> impl IntoView {
+    let WikiPage {
+        tag,
+        body,
+        html,
+        versions,
+        user_names,
+    } = page;
+    let base_version = versions.first().map_or(0, |version| version.version);
+
+    let editing = create_rw_signal(false);
+    let text = create_rw_signal(body.clone());
+
+    let name = tag.name.clone();
+    let on_save = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        save.dispatch(SaveWikiPage {
+            name: name.clone(),
+            body: text.get(),
+            base_version,
+        });
+    };
+
+    // Each version is compared with the one before it.
+    let previous_bodies = versions
+        .iter()
+        .skip(1)
+        .map(|version| version.body.clone())
+        .chain([String::new()]);
+    let history = versions
+        .iter()
+        .cloned()
+        .zip(previous_bodies)
+        .map(|(version, previous)| {
+            let latest = version.version == base_version;
+            let author = user_label(&user_names, version.author_id);
+            view! { <WikiVersionEntry name=tag.name.clone() version author previous latest revert /> }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        <h2 class="mb-2 text-lg font-semibold">"Wiki"</h2>
+        <Show
+            when=move || editing.get()
+            fallback=move || {
+                view! {
+                    <div class="mb-2 space-y-2" inner_html=html.clone()></div>
+                    {body.is_empty().then(|| view! { <p class="text-gray-500">"This tag has no wiki page yet."</p> })}
+                    <button class="text-sm text-blue-600 hover:underline" on:click=move |_| editing.set(true)>
+                        "Edit"
+                    </button>
+                }
+            }
+        >
+            <form on:submit=on_save.clone() class="flex flex-col gap-2">
+                <textarea
+                    rows="10"
+                    class="w-full px-3 py-2 font-mono text-sm rounded border border-gray-300"
+                    prop:value=text
+                    on:input=move |ev| text.set(event_target_value(&ev))
+                ></textarea>
+                <p class="text-xs text-gray-500">
+                    "**bold**, *italic*, [text](https://…), [[tag_name]] or [[tag_name|text]], !post #123"
+                </p>
+                <div class="flex gap-2">
+                    <button
+                        type="submit"
+                        class="py-2 px-4 text-white bg-blue-500 rounded-lg hover:bg-blue-600"
+                        disabled=move || save.pending().get()
+                    >
+                        "Save"
+                    </button>
+                    <button type="button" class="py-2 px-4 rounded-lg hover:bg-gray-100" on:click=move |_| editing.set(false)>
+                        "Cancel"
+                    </button>
+                </div>
+            </form>
+        </Show>
+        {move || wiki_message(save.value().get())}
+        {move || wiki_message(revert.value().get())}
+
+        {(!history.is_empty()).then(|| view! { <h3 class="mt-6 mb-2 font-semibold">"Versions"</h3> })}
+        <ul class="text-sm">{history}</ul>
+    }
+}
+
+#[component]
+fn WikiVersionEntry(
+    name: String,
+    version: WikiVersion,
+    author: String,
+    /// Body of the version before this one, empty for the first.
+    previous: String,
+    latest: bool,
+    revert: Action<RevertWikiPage, Result<Result<u32, WikiError>, ServerFnError>>,
+) -> impl IntoView {
+    let showing_changes = create_rw_signal(false);
+    let number = version.version;
+    let body = version.body.clone();
+
+    view! {
+        <li class="mb-1">
+            <span class="text-gray-500">{format_timestamp(version.created_at)}</span>
+            {format!(" Version {}", number)}
+            {version.reverted_from.map(|from| format!(", restoring version {}", from))}
+            {format!(" by {}", author)}
+            " "
+            <button class="text-blue-600 hover:underline" on:click=move |_| showing_changes.update(|showing| *showing = !*showing)>
+                "Changes"
+            </button>
+            {(!latest)
+                .then(|| {
+                    view! {
+                        " "
+                        <button
+                            class="text-red-600 hover:underline"
+                            disabled=move || revert.pending().get()
+                            on:click=move |_| {
+                                revert.dispatch(RevertWikiPage { name: name.clone(), version: number })
+                            }
+                        >
+                            "Revert"
+                        </button>
+                    }
+                })}
+            // The diff is only worked out once the changes are opened.
+            <Show when=move || showing_changes.get()>
+                <pre class="overflow-x-auto p-2 mt-1 text-xs rounded border border-gray-200">
+                    {diff_lines(&previous, &body)
+                        .into_iter()
+                        .map(|line| match line {
+                            DiffLine::Same(line) => view! { <div>{format!("  {}", line)}</div> },
+                            DiffLine::Removed(line) => {
+                                view! { <div class="bg-red-50 text-red-700">{format!("- {}", line)}</div> }
+                            }
+                            DiffLine::Added(line) => {
+                                view! { <div class="bg-green-50 text-green-700">{format!("+ {}", line)}</div> }
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </pre>
+            </Show>
+        </li>
+    }
+}
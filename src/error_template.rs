@@ -1,19 +1,50 @@
+use std::fmt::Display;
+
 use http::status::StatusCode;
 use leptos::*;
+use server_fn::error::NoCustomError;
 use thiserror::Error;
 
+use crate::models::permission::FORBIDDEN_PREFIX;
+
 #[derive(Clone, Debug, Error)]
 pub enum AppError {
     #[error("Not Found")]
     NotFound,
+    /// A `PermissionError` message.
+    #[error("{0}")]
+    Forbidden(String),
 }
 
 impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
             AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
         }
     }
+
+    /// The `AppError` a failed server function call stands for, if any.
+    /// Permission failures arrive as server errors starting with
+    /// `FORBIDDEN_PREFIX`.
+    pub fn from_server_error<E: Display>(error: &ServerFnError<E>) -> Option<AppError> {
+        let message = match error {
+            ServerFnError::ServerError(message) => message.clone(),
+            ServerFnError::WrappedServerError(e) => e.to_string(),
+            _ => return None,
+        };
+        message
+            .starts_with(FORBIDDEN_PREFIX)
+            .then_some(AppError::Forbidden(message))
+    }
+}
+
+/// How a failed server function call is shown next to the form that made it.
+pub fn server_error_message<E: Display>(error: &ServerFnError<E>) -> String {
+    match AppError::from_server_error(error) {
+        Some(error) => error.to_string(),
+        None => format!("Error: {}", error),
+    }
 }
 
 // A basic function to display errors served by the error boundaries.
@@ -36,7 +67,13 @@ pub fn ErrorTemplate(
     // Downcast lets us take a type that implements `std::error::Error`
     let errors: Vec<AppError> = errors
         .into_iter()
-        .filter_map(|(_k, v)| v.downcast_ref::<AppError>().cloned())
+        .filter_map(|(_k, v)| {
+            v.downcast_ref::<AppError>().cloned().or_else(|| {
+                v.downcast_ref::<ServerFnErrorErr>().and_then(|e| {
+                    AppError::from_server_error(&ServerFnError::<NoCustomError>::from(e.clone()))
+                })
+            })
+        })
         .collect();
 
     // Only the response code for the first error is actually sent from the server
@@ -7,6 +7,56 @@ async fn main() {
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use maerbooru::app::*;
     use maerbooru::fileserv::file_and_error_handler;
+    use maerbooru::server_only::config::{permissions, uploads_dir};
+    use maerbooru::server_only::thumbnail::{regenerate_all_thumbnails, start_thumbnail_worker};
+    use tower_http::services::ServeDir;
+
+    // A typo in PERMISSIONS should stop the server rather than quietly
+    // leave the defaults in place.
+    if let Err(e) = permissions() {
+        logging::error!("invalid PERMISSIONS: {}", e);
+        return;
+    }
+
+    // Admin commands run instead of the server, e.g. after changing the
+    // THUMBNAIL_SIZE or SAMPLE_SIZE environment variables.
+    if let Some(command) = std::env::args().nth(1) {
+        match command.as_str() {
+            "regenerate-thumbnails" => {
+                let db = maerbooru::server_only::db::get_db_connection()
+                    .await
+                    .unwrap();
+                let failed = regenerate_all_thumbnails(&db, &uploads_dir())
+                    .await
+                    .unwrap();
+                logging::log!("regenerated thumbnails, {} posts failed", failed);
+            }
+            "set-role" => {
+                let (Some(name), Some(role)) = (std::env::args().nth(2), std::env::args().nth(3))
+                else {
+                    logging::error!("usage: maerbooru set-role <user> <role>");
+                    return;
+                };
+                let role = match role.parse() {
+                    Ok(role) => role,
+                    Err(e) => {
+                        logging::error!("{}", e);
+                        return;
+                    }
+                };
+                let db = maerbooru::server_only::db::get_db_connection()
+                    .await
+                    .unwrap();
+                // Whoever runs the server may change anyone's role.
+                match maerbooru::server_only::user::set_user_role(&db, &name, role, None).await {
+                    Ok(user) => logging::log!("{} is now {}", user.name, user.role.name()),
+                    Err(e) => logging::error!("{}", e),
+                }
+            }
+            _ => logging::error!("unknown command: {}", command),
+        }
+        return;
+    }
 
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
@@ -18,9 +68,12 @@ async fn main() {
     let addr = leptos_options.site_addr;
     let routes = generate_route_list(App);
 
+    start_thumbnail_worker();
+
     // build our application with a route
     let app = Router::new()
         .leptos_routes(&leptos_options, routes, App)
+        .nest_service("/uploads", ServeDir::new(uploads_dir()))
         .fallback(file_and_error_handler)
         .with_state(leptos_options);
 
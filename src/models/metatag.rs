@@ -0,0 +1,346 @@
+//! Metatags of the post search language, such as `width:>=1000`. Every entry
+//! in `METATAGS` declares which values it accepts and turns them into a
+//! `Filter`, which the server compiles into the query.
+//!
+//! Numeric metatags share one value grammar: `N`, `>N`, `>=N`, `<N`, `<=N`
+//! and the inclusive ranges `N..M`, `N..` and `..M`.
+
+use crate::models::date::{days_from_civil, days_in_month};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Filter {
+    /// A numeric field, or an expression over fields, within a range.
+    Range { field: &'static str, range: Range },
+    /// A field equal to a value, compared as a string.
+    Equals { field: &'static str, value: String },
+    /// A boolean field that is `value`.
+    Flag { field: &'static str, value: bool },
+    /// Posts uploaded by the user with this name.
+    Uploader(String),
+    /// Not a filter, but the sort order of the results.
+    Order(Order),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bound {
+    pub value: f64,
+    pub inclusive: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Range {
+    pub min: Option<Bound>,
+    pub max: Option<Bound>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Order {
+    #[default]
+    IdDesc,
+    IdAsc,
+    ScoreDesc,
+    ScoreAsc,
+    Random,
+}
+
+impl Order {
+    /// The `ORDER BY` clause of the post query.
+    pub fn order_by(&self) -> &'static str {
+        match self {
+            Order::IdDesc => "custom_id DESC",
+            Order::IdAsc => "custom_id ASC",
+            Order::ScoreDesc => "score DESC, custom_id DESC",
+            Order::ScoreAsc => "score ASC, custom_id DESC",
+            Order::Random => "rand()",
+        }
+    }
+}
+
+pub struct Metatag {
+    pub name: &'static str,
+    /// What a valid value looks like, for error messages.
+    pub expected: &'static str,
+    /// Parses the (lowercased) value, `None` if it is invalid.
+    pub parse: fn(&str) -> Option<Filter>,
+}
+
+const NUMBER: &str = "a number or range like `>=1000` or `100..200`";
+
+pub const METATAGS: &[Metatag] = &[
+    Metatag {
+        name: "rating",
+        expected: "`s`, `q` or `e`",
+        parse: |value| {
+            let safety = match value {
+                "s" | "safe" => "Safe",
+                "q" | "sketchy" | "questionable" => "Sketchy",
+                "e" | "unsafe" | "explicit" => "Unsafe",
+                _ => return None,
+            };
+            Some(Filter::Equals {
+                field: "safety",
+                value: safety.to_string(),
+            })
+        },
+    },
+    Metatag {
+        name: "width",
+        expected: NUMBER,
+        parse: |value| range("image_width", value, number),
+    },
+    Metatag {
+        name: "height",
+        expected: NUMBER,
+        parse: |value| range("image_height", value, number),
+    },
+    Metatag {
+        name: "mpixels",
+        expected: NUMBER,
+        parse: |value| {
+            range("image_width * image_height", value, |value| {
+                scale(number(value)?, 1_000_000.0)
+            })
+        },
+    },
+    Metatag {
+        name: "filesize",
+        expected: "a size or range like `>500kb` or `1mb..5mb`",
+        parse: |value| range("file_size", value, file_size),
+    },
+    Metatag {
+        name: "date",
+        expected: "a date or range like `2026-01-31` or `2026-01..2026-06`",
+        parse: |value| range("uploaded_at", value, date),
+    },
+    Metatag {
+        name: "score",
+        expected: NUMBER,
+        parse: |value| range("score", value, number),
+    },
+    Metatag {
+        name: "uploader",
+        expected: "a user name",
+        parse: |value| Some(Filter::Uploader(value.to_string())),
+    },
+    Metatag {
+        name: "type",
+        expected: "`image` or `video`",
+        parse: |value| {
+            let post_type = match value {
+                "image" => "Image",
+                "video" => "Video",
+                _ => return None,
+            };
+            Some(Filter::Equals {
+                field: "post_type",
+                value: post_type.to_string(),
+            })
+        },
+    },
+    Metatag {
+        name: "animated",
+        expected: "`yes` or `no`",
+        parse: |value| {
+            let animated = match value {
+                "yes" | "true" => true,
+                "no" | "false" => false,
+                _ => return None,
+            };
+            Some(Filter::Flag {
+                field: "animated",
+                value: animated,
+            })
+        },
+    },
+    Metatag {
+        name: "md5",
+        expected: "32 hexadecimal digits",
+        parse: |value| hash("md5_hash", value, 32),
+    },
+    Metatag {
+        name: "sha256",
+        expected: "64 hexadecimal digits",
+        parse: |value| hash("sha256_hash", value, 64),
+    },
+    Metatag {
+        name: "order",
+        expected: "`id`, `id_asc`, `score`, `score_asc` or `random`",
+        parse: |value| {
+            let order = match value {
+                "id" | "id_desc" => Order::IdDesc,
+                "id_asc" => Order::IdAsc,
+                "score" | "score_desc" => Order::ScoreDesc,
+                "score_asc" => Order::ScoreAsc,
+                "random" => Order::Random,
+                _ => return None,
+            };
+            Some(Filter::Order(order))
+        },
+    },
+];
+
+pub fn find_metatag(name: &str) -> Option<&'static Metatag> {
+    METATAGS.iter().find(|metatag| metatag.name == name)
+}
+
+/// The values a single term like `2026-01` stands for: from `start` up to
+/// `end`, which is excluded unless `end_inclusive` is set.
+struct Interval {
+    start: f64,
+    end: f64,
+    end_inclusive: bool,
+}
+
+fn range(field: &'static str, value: &str, single: fn(&str) -> Option<Interval>) -> Option<Filter> {
+    let from = |interval: Interval| Bound {
+        value: interval.start,
+        inclusive: true,
+    };
+    let to = |interval: Interval| Bound {
+        value: interval.end,
+        inclusive: interval.end_inclusive,
+    };
+
+    let range = if let Some(value) = value.strip_prefix(">=") {
+        Range {
+            min: Some(from(single(value)?)),
+            max: None,
+        }
+    } else if let Some(value) = value.strip_prefix("<=") {
+        Range {
+            min: None,
+            max: Some(to(single(value)?)),
+        }
+    } else if let Some(value) = value.strip_prefix('>') {
+        let interval = single(value)?;
+        Range {
+            min: Some(Bound {
+                value: interval.end,
+                inclusive: !interval.end_inclusive,
+            }),
+            max: None,
+        }
+    } else if let Some(value) = value.strip_prefix('<') {
+        Range {
+            min: None,
+            max: Some(Bound {
+                value: single(value)?.start,
+                inclusive: false,
+            }),
+        }
+    } else if let Some((start, end)) = value.split_once("..") {
+        if start.is_empty() && end.is_empty() {
+            return None;
+        }
+        Range {
+            min: match start {
+                "" => None,
+                start => Some(from(single(start)?)),
+            },
+            max: match end {
+                "" => None,
+                end => Some(to(single(end)?)),
+            },
+        }
+    } else {
+        let interval = single(value)?;
+        Range {
+            min: Some(Bound {
+                value: interval.start,
+                inclusive: true,
+            }),
+            max: Some(to(interval)),
+        }
+    };
+
+    Some(Filter::Range { field, range })
+}
+
+fn number(value: &str) -> Option<Interval> {
+    let number: f64 = value
+        .parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite())?;
+    Some(Interval {
+        start: number,
+        end: number,
+        end_inclusive: true,
+    })
+}
+
+fn scale(interval: Interval, factor: f64) -> Option<Interval> {
+    Some(Interval {
+        start: interval.start * factor,
+        end: interval.end * factor,
+        ..interval
+    })
+}
+
+fn file_size(value: &str) -> Option<Interval> {
+    let (value, factor) = match value {
+        value if value.ends_with("kb") => (&value[..value.len() - 2], 1024.0),
+        value if value.ends_with("mb") => (&value[..value.len() - 2], 1024.0 * 1024.0),
+        value if value.ends_with("gb") => (&value[..value.len() - 2], 1024.0 * 1024.0 * 1024.0),
+        value => (value.strip_suffix('b').unwrap_or(value), 1.0),
+    };
+    scale(number(value)?, factor)
+}
+
+/// `YYYY`, `YYYY-MM` or `YYYY-MM-DD` in UTC, as the unix timestamps of the
+/// whole year, month or day.
+fn date(value: &str) -> Option<Interval> {
+    let mut parts = value.split('-');
+    let year: i64 = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+    let month: Option<u32> = match parts.next() {
+        Some(month) if month.len() == 2 => {
+            Some(month.parse().ok().filter(|m| (1..=12).contains(m))?)
+        }
+        Some(_) => return None,
+        None => None,
+    };
+    let day: Option<u32> = match parts.next() {
+        Some(day) if day.len() == 2 => {
+            let days = days_in_month(year, month?);
+            Some(day.parse().ok().filter(|d| (1..=days).contains(d))?)
+        }
+        Some(_) => return None,
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let (start, end) = match (month, day) {
+        (Some(month), Some(day)) => {
+            let start = days_from_civil(year, month, day);
+            (start, start + 1)
+        }
+        (Some(12), None) => (
+            days_from_civil(year, 12, 1),
+            days_from_civil(year + 1, 1, 1),
+        ),
+        (Some(month), None) => (
+            days_from_civil(year, month, 1),
+            days_from_civil(year, month + 1, 1),
+        ),
+        (None, _) => (days_from_civil(year, 1, 1), days_from_civil(year + 1, 1, 1)),
+    };
+
+    Some(Interval {
+        start: (start * 86_400) as f64,
+        end: (end * 86_400) as f64,
+        end_inclusive: false,
+    })
+}
+
+fn hash(field: &'static str, value: &str, digits: usize) -> Option<Filter> {
+    if value.len() != digits || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // Hashes are stored in uppercase, see `hex_hash`.
+    Some(Filter::Equals {
+        field,
+        value: value.to_ascii_uppercase(),
+    })
+}
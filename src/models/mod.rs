@@ -1,2 +1,8 @@
+pub mod date;
+pub mod metatag;
+pub mod permission;
 pub mod post;
+pub mod search;
 pub mod tag;
+pub mod user;
+pub mod wiki;
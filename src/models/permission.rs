@@ -0,0 +1,232 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// How much a user is trusted, from least to most. Every role may do what
+/// the ones before it may. Stored as its number, so the discriminants must
+/// never change.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum Role {
+    /// Not logged in.
+    Anonymous = 0,
+    /// What new accounts start as.
+    #[default]
+    Member = 1,
+    Contributor = 2,
+    Janitor = 3,
+    Moderator = 4,
+    Admin = 5,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+#[error("there is no role {0}")]
+pub struct UnknownRole(pub String);
+
+impl Role {
+    pub const ALL: [Role; 6] = [
+        Role::Anonymous,
+        Role::Member,
+        Role::Contributor,
+        Role::Janitor,
+        Role::Moderator,
+        Role::Admin,
+    ];
+
+    /// The lowercase name used in the `PERMISSIONS` setting and messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Anonymous => "anonymous",
+            Role::Member => "member",
+            Role::Contributor => "contributor",
+            Role::Janitor => "janitor",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl From<Role> for u8 {
+    fn from(role: Role) -> u8 {
+        role as u8
+    }
+}
+
+impl TryFrom<u8> for Role {
+    type Error = UnknownRole;
+
+    fn try_from(number: u8) -> Result<Role, UnknownRole> {
+        Role::ALL
+            .into_iter()
+            .find(|role| *role as u8 == number)
+            .ok_or_else(|| UnknownRole(number.to_string()))
+    }
+}
+
+impl FromStr for Role {
+    type Err = UnknownRole;
+
+    fn from_str(name: &str) -> Result<Role, UnknownRole> {
+        let name = name.trim().to_lowercase();
+        Role::ALL
+            .into_iter()
+            .find(|role| role.name() == name)
+            .ok_or(UnknownRole(name))
+    }
+}
+
+/// Something that changes the site and needs a minimum role.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Permission {
+    UploadPost = 0,
+    TagPosts = 1,
+    CreateTag = 2,
+    EditWiki = 3,
+    /// Category, alias and implications.
+    EditTag = 4,
+    RenameTag = 5,
+    MergeTags = 6,
+    DeleteTag = 7,
+    RecountTags = 8,
+    ManageUsers = 9,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 10] = [
+        Permission::UploadPost,
+        Permission::TagPosts,
+        Permission::CreateTag,
+        Permission::EditWiki,
+        Permission::EditTag,
+        Permission::RenameTag,
+        Permission::MergeTags,
+        Permission::DeleteTag,
+        Permission::RecountTags,
+        Permission::ManageUsers,
+    ];
+
+    /// The name used in the `PERMISSIONS` setting.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Permission::UploadPost => "upload_post",
+            Permission::TagPosts => "tag_posts",
+            Permission::CreateTag => "create_tag",
+            Permission::EditWiki => "edit_wiki",
+            Permission::EditTag => "edit_tag",
+            Permission::RenameTag => "rename_tag",
+            Permission::MergeTags => "merge_tags",
+            Permission::DeleteTag => "delete_tag",
+            Permission::RecountTags => "recount_tags",
+            Permission::ManageUsers => "manage_users",
+        }
+    }
+
+    /// What the permission allows, to finish "… to {}".
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::UploadPost => "upload posts",
+            Permission::TagPosts => "change the tags of posts",
+            Permission::CreateTag => "create tags",
+            Permission::EditWiki => "edit tag wiki pages",
+            Permission::EditTag => "edit tags",
+            Permission::RenameTag => "rename tags",
+            Permission::MergeTags => "merge tags",
+            Permission::DeleteTag => "delete tags",
+            Permission::RecountTags => "recount tag uses",
+            Permission::ManageUsers => "manage users",
+        }
+    }
+}
+
+/// Start of the message of every permission failure, which is how it is
+/// recognised once it has become a `ServerFnError`.
+pub const FORBIDDEN_PREFIX: &str = "Forbidden: ";
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+#[error("Forbidden: you need to be {} or higher to {}", .required.name(), .permission.description())]
+pub struct PermissionError {
+    pub permission: Permission,
+    pub required: Role,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum PermissionConfigError {
+    #[error("there is no permission {0}")]
+    UnknownPermission(String),
+    #[error(transparent)]
+    UnknownRole(#[from] UnknownRole),
+    #[error("expected `permission=role`, got `{0}`")]
+    Malformed(String),
+}
+
+/// The least role each permission needs.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PermissionMatrix {
+    required: [Role; Permission::ALL.len()],
+}
+
+impl Default for PermissionMatrix {
+    fn default() -> PermissionMatrix {
+        let mut matrix = PermissionMatrix {
+            required: [Role::Admin; Permission::ALL.len()],
+        };
+        for permission in Permission::ALL {
+            let role = match permission {
+                Permission::UploadPost
+                | Permission::TagPosts
+                | Permission::CreateTag
+                | Permission::EditWiki => Role::Member,
+                Permission::EditTag => Role::Contributor,
+                Permission::RenameTag | Permission::MergeTags => Role::Janitor,
+                Permission::DeleteTag => Role::Moderator,
+                Permission::RecountTags | Permission::ManageUsers => Role::Admin,
+            };
+            matrix.set(permission, role);
+        }
+        matrix
+    }
+}
+
+impl PermissionMatrix {
+    pub fn required(&self, permission: Permission) -> Role {
+        self.required[permission as usize]
+    }
+
+    pub fn set(&mut self, permission: Permission, role: Role) {
+        self.required[permission as usize] = role;
+    }
+
+    /// Applies comma separated `permission=role` overrides, like
+    /// `upload_post=contributor, delete_tag=janitor`.
+    pub fn with_overrides(mut self, overrides: &str) -> Result<Self, PermissionConfigError> {
+        for entry in overrides
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+        {
+            let (name, role) = entry
+                .split_once('=')
+                .ok_or_else(|| PermissionConfigError::Malformed(entry.trim().to_string()))?;
+            let name = name.trim();
+            let permission = Permission::ALL
+                .into_iter()
+                .find(|permission| permission.name() == name)
+                .ok_or_else(|| PermissionConfigError::UnknownPermission(name.to_string()))?;
+            self.set(permission, role.parse()?);
+        }
+        Ok(self)
+    }
+
+    pub fn check(&self, role: Role, permission: Permission) -> Result<(), PermissionError> {
+        let required = self.required(permission);
+        if role < required {
+            return Err(PermissionError {
+                permission,
+                required,
+            });
+        }
+        Ok(())
+    }
+}
@@ -1,25 +1,150 @@
-#[derive(Clone, PartialEq, Eq, Debug)]
+use serde::{Deserialize, Serialize};
+
+use crate::models::tag::Tag;
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Safety {
     Safe,
     Sketchy,
     Unsafe,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum PostType {
     Image,
     Video,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Post {
-    id: u64,
-    image_height: u32,
-    image_width: u32,
-    mime_type: mime::Mime,
-    post_type: PostType,
-    safety: Safety,
-    sha256_hash: [u8; 32],
-    uploader_id: u32,
-    tags: Vec<u64>,
+    pub custom_id: u64,
+    pub image_height: u32,
+    pub image_width: u32,
+    #[serde(with = "mime_string")]
+    pub mime_type: mime::Mime,
+    pub post_type: PostType,
+    pub safety: Safety,
+    #[serde(with = "hex_hash")]
+    pub sha256_hash: [u8; 32],
+    /// Only kept for `md5:` searches, duplicates are detected by SHA-256.
+    #[serde(default, with = "hex_hash")]
+    pub md5_hash: [u8; 16],
+    pub uploader_id: u64,
+    pub tags: Vec<u64>,
+    /// Length in seconds, only set for videos.
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub has_audio: bool,
+    /// Number of frames for images, `None` for videos.
+    #[serde(default)]
+    pub frame_count: Option<u32>,
+    /// Set for multi-frame images and for videos.
+    #[serde(default)]
+    pub animated: bool,
+    /// Size of the original file in bytes.
+    #[serde(default)]
+    pub file_size: u64,
+    /// Unix timestamp of the upload.
+    #[serde(default)]
+    pub uploaded_at: u64,
+    /// Sum of the votes on the post.
+    #[serde(default)]
+    pub score: i64,
+}
+
+/// Everything the post page shows about a post.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PostDetails {
+    pub post: Post,
+    pub tags: Vec<Tag>,
+    /// Name of the uploader, `None` for anonymous uploads.
+    pub uploader_name: Option<String>,
+    /// Absolute URL of the site, for links that leave it (OpenGraph tags).
+    /// Empty when `PUBLIC_URL` is not set.
+    pub public_url: String,
+}
+
+impl Post {
+    /// Uppercase hex form of the hash, as used for file names under `./uploads/`.
+    pub fn hash_hex(&self) -> String {
+        hex_hash::encode(&self.sha256_hash)
+    }
+
+    /// Extension the file is stored under, derived from its detected type.
+    pub fn file_extension(&self) -> &'static str {
+        match self.mime_type.essence_str() {
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/avif" => "avif",
+            "video/mp4" => "mp4",
+            "video/webm" => "webm",
+            _ => "bin",
+        }
+    }
+
+    /// Name of the stored file inside `./uploads/`.
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.hash_hex(), self.file_extension()).to_lowercase()
+    }
+
+    /// Name of the small grid preview, stored next to the original.
+    pub fn thumbnail_file_name(&self) -> String {
+        format!("{}_thumb.jpg", self.hash_hex()).to_lowercase()
+    }
+
+    /// Name of the medium sized sample, stored next to the original.
+    pub fn sample_file_name(&self) -> String {
+        format!("{}_sample.jpg", self.hash_hex()).to_lowercase()
+    }
+}
+
+mod mime_string {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(mime: &mime::Mime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(mime.as_ref())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<mime::Mime, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+pub mod hex_hash {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn encode<const N: usize>(hash: &[u8; N]) -> String {
+        hash.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    pub fn decode<const N: usize>(s: &str) -> Option<[u8; N]> {
+        if s.len() != N * 2 || !s.is_ascii() {
+            return None;
+        }
+
+        let mut hash = [0u8; N];
+        for (i, byte) in hash.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        Some(hash)
+    }
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        hash: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        decode(&s).ok_or_else(|| D::Error::custom(format!("invalid hash: {}", s)))
+    }
 }
@@ -0,0 +1,257 @@
+//! The post search query language.
+//!
+//! A query is a whitespace separated list of terms. Plain terms are tags the
+//! post must have, `-tag` excludes a tag and every `~tag` term goes into one
+//! group of which at least one has to match:
+//!
+//! ```text
+//! cat_ears -dog ~red ~blue rating:safe
+//! ```
+//!
+//! Besides tags a term can be one of the metatags in `models::metatag`
+//! (`rating:safe`). Parsing only checks the syntax; tag names are resolved
+//! and the query is compiled on the server.
+//!
+//! Tags named before tag names were validated may contain colons or other
+//! characters a term cannot. The server looks such terms up first (see
+//! `unusual_terms`), and a term that names an existing tag is that tag.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::metatag::{find_metatag, Filter, Order};
+use crate::models::tag::is_tag_name_char;
+
+/// Upper limit on the number of terms, to keep the generated query small.
+pub const MAX_TERMS: usize = 32;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Term {
+    Tag(String),
+    Metatag(Filter),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Clause {
+    pub term: Term,
+    pub negated: bool,
+    /// Byte range of the whole term in the query, operators included.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A parsed query. A post matches if it matches every clause in `all` and,
+/// unless it is empty, at least one clause in `any`.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PostSearch {
+    pub all: Vec<Clause>,
+    pub any: Vec<Clause>,
+    pub order: Order,
+}
+
+impl PostSearch {
+    pub fn clauses(&self) -> impl Iterator<Item = &Clause> {
+        self.all.iter().chain(self.any.iter())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum SearchErrorKind {
+    #[error("expected a tag after the operator")]
+    MissingTerm,
+    #[error("`-` and `~` cannot be combined")]
+    CombinedOperators,
+    #[error("`{0}` is not allowed in tag names")]
+    InvalidCharacter(char),
+    #[error("wildcards are not supported in post search")]
+    Wildcard,
+    #[error("there is no metatag `{0}:`")]
+    UnknownMetatag(String),
+    #[error("`{value}` is not a valid value for `{metatag}:`, expected {expected}")]
+    InvalidValue {
+        metatag: String,
+        value: String,
+        expected: String,
+    },
+    #[error("`order:` cannot be negated or used in an OR group")]
+    OrderNotFilter,
+    #[error("only one `order:` is allowed")]
+    DuplicateOrder,
+    #[error("the tag `{0}` does not exist")]
+    UnknownTag(String),
+    #[error("the user `{0}` does not exist")]
+    UnknownUser(String),
+    #[error("the query has more than {MAX_TERMS} terms")]
+    TooManyTerms,
+}
+
+/// An error pointing at the part of the query it is about.
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+#[error("{kind} (at {start}..{end})")]
+pub struct SearchError {
+    pub kind: SearchErrorKind,
+    /// Byte range in the query.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SearchError {
+    pub fn new(kind: SearchErrorKind, start: usize, end: usize) -> SearchError {
+        SearchError { kind, start, end }
+    }
+}
+
+/// Characters accepted in tag names, see `normalize_tag_name`.
+fn is_tag_char(c: char) -> bool {
+    is_tag_name_char(c) || c == '(' || c == ')'
+}
+
+/// Splits `query` into whitespace separated words with their byte offsets.
+fn words(query: &str) -> impl Iterator<Item = (usize, &str)> {
+    query
+        .split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - query.as_ptr() as usize, word))
+}
+
+/// The terms of `query`, lowercased and without their operator, that are not
+/// tag names by the current grammar but could still name an older tag.
+pub fn unusual_terms(query: &str) -> Vec<String> {
+    words(query)
+        .map(|(_, word)| word.strip_prefix(['-', '~']).unwrap_or(word))
+        .map(|body| body.to_ascii_lowercase())
+        .filter(|body| !body.is_empty() && !body.chars().all(is_tag_char))
+        .collect()
+}
+
+pub fn parse_search(query: &str) -> Result<PostSearch, SearchError> {
+    parse_search_with_tags(query, &HashSet::new())
+}
+
+/// Like `parse_search`, but a term that is exactly one of `existing_tags` is
+/// that tag, even if it looks like a metatag or has characters new tag names
+/// cannot have.
+pub fn parse_search_with_tags(
+    query: &str,
+    existing_tags: &HashSet<String>,
+) -> Result<PostSearch, SearchError> {
+    let mut search = PostSearch::default();
+    let mut order_seen = false;
+
+    for (count, (start, word)) in words(query).enumerate() {
+        let end = start + word.len();
+        if count == MAX_TERMS {
+            return Err(SearchError::new(SearchErrorKind::TooManyTerms, start, end));
+        }
+
+        let (negated, in_group, body) = match word.as_bytes()[0] {
+            b'-' => (true, false, &word[1..]),
+            b'~' => (false, true, &word[1..]),
+            _ => (false, false, word),
+        };
+        let body_start = end - body.len();
+
+        if body.is_empty() {
+            return Err(SearchError::new(SearchErrorKind::MissingTerm, start, end));
+        }
+        if body.starts_with(['-', '~']) && body_start != start {
+            return Err(SearchError::new(
+                SearchErrorKind::CombinedOperators,
+                start,
+                body_start + 1,
+            ));
+        }
+
+        let term = parse_term(body, body_start, existing_tags)?;
+        if let Term::Metatag(Filter::Order(order)) = term {
+            if negated || in_group {
+                return Err(SearchError::new(
+                    SearchErrorKind::OrderNotFilter,
+                    start,
+                    end,
+                ));
+            }
+            if order_seen {
+                return Err(SearchError::new(
+                    SearchErrorKind::DuplicateOrder,
+                    start,
+                    end,
+                ));
+            }
+            search.order = order;
+            order_seen = true;
+            continue;
+        }
+
+        let clause = Clause {
+            term,
+            negated,
+            start,
+            end,
+        };
+        if in_group {
+            search.any.push(clause);
+        } else {
+            search.all.push(clause);
+        }
+    }
+
+    Ok(search)
+}
+
+fn parse_term(
+    body: &str,
+    start: usize,
+    existing_tags: &HashSet<String>,
+) -> Result<Term, SearchError> {
+    // Only ASCII is lowercased, so byte offsets stay the same.
+    let lowercase = body.to_ascii_lowercase();
+
+    if existing_tags.contains(&lowercase) {
+        return Ok(Term::Tag(lowercase));
+    }
+
+    // New tag names cannot contain colons, so this is a metatag.
+    if let Some((name, value)) = lowercase.split_once(':') {
+        let Some(metatag) = find_metatag(name) else {
+            return Err(SearchError::new(
+                SearchErrorKind::UnknownMetatag(name.to_string()),
+                start,
+                start + name.len(),
+            ));
+        };
+        return match (metatag.parse)(value) {
+            Some(filter) => Ok(Term::Metatag(filter)),
+            None => Err(SearchError::new(
+                SearchErrorKind::InvalidValue {
+                    metatag: name.to_string(),
+                    value: value.to_string(),
+                    expected: metatag.expected.to_string(),
+                },
+                start + name.len() + 1,
+                start + body.len(),
+            )),
+        };
+    }
+
+    for (offset, c) in lowercase.char_indices() {
+        let position = start + offset;
+        if c == '*' {
+            return Err(SearchError::new(
+                SearchErrorKind::Wildcard,
+                position,
+                position + 1,
+            ));
+        }
+        if !is_tag_char(c) {
+            return Err(SearchError::new(
+                SearchErrorKind::InvalidCharacter(c),
+                position,
+                position + c.len_utf8(),
+            ));
+        }
+    }
+
+    Ok(Term::Tag(lowercase))
+}
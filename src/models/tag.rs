@@ -1,4 +1,78 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// What kind of thing a tag describes. Stored as its number, so the
+/// discriminants must never change.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum TagCategory {
+    #[default]
+    General = 0,
+    Artist = 1,
+    Copyright = 2,
+    Character = 3,
+    Meta = 4,
+    Species = 5,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+#[error("there is no tag category {0}")]
+pub struct UnknownCategory(pub u8);
+
+impl TagCategory {
+    pub const ALL: [TagCategory; 6] = [
+        TagCategory::General,
+        TagCategory::Artist,
+        TagCategory::Copyright,
+        TagCategory::Character,
+        TagCategory::Meta,
+        TagCategory::Species,
+    ];
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TagCategory::General => "General",
+            TagCategory::Artist => "Artist",
+            TagCategory::Copyright => "Copyright",
+            TagCategory::Character => "Character",
+            TagCategory::Meta => "Meta",
+            TagCategory::Species => "Species",
+        }
+    }
+
+    /// The Tailwind text colour tags of this category are shown in.
+    pub fn colour(&self) -> &'static str {
+        match self {
+            TagCategory::General => "text-blue-600",
+            TagCategory::Artist => "text-red-600",
+            TagCategory::Copyright => "text-purple-600",
+            TagCategory::Character => "text-green-600",
+            TagCategory::Meta => "text-orange-500",
+            TagCategory::Species => "text-teal-600",
+        }
+    }
+}
+
+impl From<TagCategory> for u8 {
+    fn from(category: TagCategory) -> u8 {
+        category as u8
+    }
+}
+
+impl TryFrom<u8> for TagCategory {
+    type Error = UnknownCategory;
+
+    fn try_from(value: u8) -> Result<TagCategory, UnknownCategory> {
+        TagCategory::ALL
+            .into_iter()
+            .find(|category| *category as u8 == value)
+            .ok_or(UnknownCategory(value))
+    }
+}
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Tag {
@@ -6,17 +80,213 @@ pub struct Tag {
     pub name: String,
     pub description: String,
     pub is_alias: Option<u64>,
-    pub category: u8,
+    pub category: TagCategory,
     pub implications: Vec<u64>,
     pub use_count: u64,
 }
 
+/// A tag as shown in tag lists, with the name of the tag it is an alias of.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ListedTag {
+    pub tag: Tag,
+    pub alias_of: Option<String>,
+}
+
+/// A tag with the names of the tags it refers to, for the tag page.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TagDetails {
+    pub tag: Tag,
+    pub alias_of: Option<String>,
+    pub implications: Vec<String>,
+    pub history: Vec<TagHistoryEntry>,
+    /// Names of the users in `history`.
+    pub user_names: HashMap<u64, String>,
+}
+
+/// The editable parts of a tag. Other tags are given by name.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TagEdit {
+    /// The new wiki page of the tag, `None` to leave it as it is.
+    #[serde(default)]
+    pub description: Option<String>,
+    pub category: TagCategory,
+    #[serde(default)]
+    pub alias_of: Option<String>,
+    #[serde(default)]
+    pub implications: Vec<String>,
+    /// Whether posts that already have the tag get new implied tags too.
+    #[serde(default)]
+    pub back_apply: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum TagEditError {
+    #[error("the tag `{0}` does not exist")]
+    UnknownTag(String),
+    #[error("a tag cannot be an alias of itself")]
+    SelfAlias,
+    #[error("an alias cannot imply other tags")]
+    AliasWithImplications,
+    #[error("`{0}` would end up implying itself")]
+    Cycle(String),
+    #[error(
+        "the description is too long, it can have at most {} bytes in {} lines",
+        crate::models::wiki::MAX_WIKI_BYTES,
+        crate::models::wiki::MAX_WIKI_LINES
+    )]
+    DescriptionTooLong,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TagAction {
+    Rename,
+    Merge,
+    Create,
+    /// A change to the category, alias target or implications on the tag
+    /// page.
+    Edit,
+    /// Made an alias of `target_id`.
+    Alias,
+    Implications,
+    Delete,
+    /// Added to `post_id`.
+    Tag,
+    /// Removed from `post_id`.
+    Untag,
+}
+
+/// A change to a tag, kept so changes to tags can be traced back.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TagHistoryEntry {
+    pub custom_id: u64,
+    pub tag_id: u64,
+    pub action: TagAction,
+    pub old_name: String,
+    /// The new name, or for merges and aliases the name of the target.
+    /// Other changes leave the name as it was.
+    pub new_name: String,
+    /// The tag merged into or made the alias target.
+    pub target_id: Option<u64>,
+    /// The post the tag was added to or removed from.
+    #[serde(default)]
+    pub post_id: Option<u64>,
+    pub changed_at: u64,
+    /// Who made the change, 0 for nobody. Older entries have none.
+    #[serde(default)]
+    pub user_id: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum TagChangeError {
+    #[error("the tag `{0}` does not exist")]
+    UnknownTag(String),
+    #[error(transparent)]
+    InvalidName(#[from] TagNameError),
+    #[error("there already is a tag called `{0}`")]
+    NameTaken(String),
+    #[error("a tag cannot be merged into itself")]
+    SelfMerge,
+    #[error("`{0}` is an alias, merge the tag it stands for instead")]
+    MergeAlias(String),
+    #[error("merging would make `{0}` imply itself")]
+    Cycle(String),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum TagDeleteError {
+    #[error("the tag `{0}` does not exist")]
+    UnknownTag(String),
+    #[error(
+        "the tag is still used by {posts} posts, {aliases} aliases and {implied_by} implications"
+    )]
+    InUse {
+        posts: u64,
+        aliases: u64,
+        implied_by: u64,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum TagNameError {
+    #[error("tag names cannot be empty")]
+    Empty,
+    #[error("tag names cannot start with `{0}`, it is a search operator")]
+    LeadingOperator(char),
+    #[error("tag names cannot contain `*`, it is the search wildcard")]
+    Wildcard,
+    #[error("`{0}` is not allowed in tag names")]
+    InvalidCharacter(char),
+    #[error("parentheses in tag names must be balanced")]
+    UnbalancedParentheses,
+    #[error("qualifiers in parentheses cannot be empty")]
+    EmptyQualifier,
+    #[error("qualifiers go at the end of tag names, after an underscore, like `lain_(serial_experiments_lain)`")]
+    MisplacedQualifier,
+}
+
+/// Characters tag names are made of, besides the parentheses of qualifiers.
+pub fn is_tag_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-'
+}
+
+/// Turns user input into a tag name: trimmed, lowercased and with spaces as
+/// underscores. Names may end in qualifiers like `_(serial_experiments_lain)`,
+/// which can nest but must be balanced.
+pub fn normalize_tag_name(input: &str) -> Result<String, TagNameError> {
+    let name = input.split_whitespace().collect::<Vec<_>>().join("_");
+    let name = name.to_lowercase();
+
+    let chars: Vec<char> = name.chars().collect();
+    match chars.first() {
+        None => return Err(TagNameError::Empty),
+        Some(c) if *c == '-' || *c == '~' => return Err(TagNameError::LeadingOperator(*c)),
+        _ => {}
+    }
+
+    let mut depth = 0usize;
+    let mut in_qualifiers = false;
+    for (i, c) in chars.iter().copied().enumerate() {
+        let previous = i.checked_sub(1).map(|i| chars[i]);
+        match c {
+            '(' => {
+                if depth == 0 && previous != Some('_') {
+                    return Err(TagNameError::MisplacedQualifier);
+                }
+                depth += 1;
+                in_qualifiers = true;
+            }
+            ')' => {
+                if depth == 0 {
+                    return Err(TagNameError::UnbalancedParentheses);
+                }
+                if previous == Some('(') {
+                    return Err(TagNameError::EmptyQualifier);
+                }
+                depth -= 1;
+            }
+            '*' => return Err(TagNameError::Wildcard),
+            c if !is_tag_name_char(c) => return Err(TagNameError::InvalidCharacter(c)),
+            // Only the underscore in front of another qualifier may follow one.
+            c if depth == 0 && in_qualifiers && (c != '_' || chars.get(i + 1) != Some(&'(')) => {
+                return Err(TagNameError::MisplacedQualifier);
+            }
+            _ => {}
+        }
+    }
+
+    if depth > 0 {
+        return Err(TagNameError::UnbalancedParentheses);
+    }
+
+    Ok(name)
+}
+
 impl Tag {
     pub fn new(
         name: String,
         description: String,
         is_alias: Option<u64>,
-        category: u8,
+        category: TagCategory,
         implications: Vec<u64>,
     ) -> Tag {
         Tag {
@@ -38,7 +308,7 @@ impl Default for Tag {
             name: String::from("newtag"),
             description: String::new(),
             is_alias: None,
-            category: 1,
+            category: TagCategory::General,
             implications: vec![],
             use_count: 0,
         }
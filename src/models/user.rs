@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::permission::Role;
+
+/// Shortest password accepted on registration.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+/// Longest user name accepted on registration.
+pub const MAX_USER_NAME_LENGTH: usize = 32;
+/// Name searches use for posts without an uploader, so nobody can take it.
+pub const ANONYMOUS: &str = "anonymous";
+
+/// A registered user, as anyone may see it. Id 0 stands for nobody.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct User {
+    pub custom_id: u64,
+    pub name: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub role: Role,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum UserError {
+    #[error("User names are 3 to {MAX_USER_NAME_LENGTH} letters, digits, `_` or `-`")]
+    InvalidName,
+    #[error("The name {0} is taken")]
+    NameTaken(String),
+    #[error("Passwords need at least {MIN_PASSWORD_LENGTH} characters")]
+    PasswordTooShort,
+    #[error("Wrong user name or password")]
+    WrongCredentials,
+    #[error("There is no user called {0}")]
+    UnknownUser(String),
+    #[error("You cannot give out a role above your own")]
+    RoleAboveYours,
+    #[error("{0} ranks as high as you or higher")]
+    OutranksYou(String),
+}
+
+/// Trims and lowercases a user name, then checks it. Names are compared
+/// lowercased everywhere, like in `uploader:` searches.
+pub fn normalize_user_name(name: &str) -> Result<String, UserError> {
+    let name = name.trim().to_lowercase();
+    let valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-';
+    if !(3..=MAX_USER_NAME_LENGTH).contains(&name.len()) || !name.chars().all(valid_char) {
+        return Err(UserError::InvalidName);
+    }
+    if name == ANONYMOUS {
+        return Err(UserError::NameTaken(name));
+    }
+    Ok(name)
+}
+
+/// How the user `id` is shown, given the names of the users known so far.
+pub fn user_label(names: &HashMap<u64, String>, id: u64) -> String {
+    match (id, names.get(&id)) {
+        (0, _) => String::from("Anonymous"),
+        (_, Some(name)) => name.clone(),
+        (id, None) => format!("User #{}", id),
+    }
+}
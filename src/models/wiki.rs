@@ -0,0 +1,515 @@
+//! Tag wiki pages and their markup.
+//!
+//! Paragraphs are separated by blank lines and single line breaks are kept.
+//! Inside a paragraph:
+//!
+//! - `**bold**` and `*italic*`
+//! - `[text](https://example.com)` links to `http(s)://` or site relative URLs
+//! - `[[tag_name]]` and `[[tag_name|text]]` link to the page of a tag
+//! - `!post #123` shows the thumbnail of a post
+//!
+//! Anything else is text. The markup is rendered on the server, which also
+//! sanitizes the resulting HTML.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::models::post::Post;
+use crate::models::tag::{normalize_tag_name, Tag};
+
+/// One saved version of the wiki page of a tag. Versions of a page are
+/// numbered from 1.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct WikiVersion {
+    pub custom_id: u64,
+    pub tag_id: u64,
+    pub version: u32,
+    pub body: String,
+    pub created_at: u64,
+    /// The version this one restored, if it was made by reverting.
+    pub reverted_from: Option<u32>,
+    /// Who saved the version, 0 for nobody.
+    #[serde(default)]
+    pub author_id: u64,
+}
+
+/// Everything the wiki page of a tag shows.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WikiPage {
+    pub tag: Tag,
+    /// The markup of the latest version.
+    pub body: String,
+    /// `body` rendered and sanitized.
+    pub html: String,
+    /// Every version, newest first.
+    pub versions: Vec<WikiVersion>,
+    /// Names of the authors of `versions`.
+    pub user_names: HashMap<u64, String>,
+}
+
+impl WikiPage {
+    /// Number of the latest version, 0 if the page was never saved.
+    pub fn latest_version(&self) -> u32 {
+        self.versions.first().map_or(0, |version| version.version)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Error, Serialize, Deserialize)]
+pub enum WikiError {
+    #[error("The tag {0} does not exist")]
+    UnknownTag(String),
+    #[error("The page has no version {0}")]
+    UnknownVersion(u32),
+    #[error("The page was changed while you were editing it, it is now at version {0}")]
+    Outdated(u32),
+    #[error(
+        "The page is too long, it can have at most {} bytes in {} lines",
+        MAX_WIKI_BYTES,
+        MAX_WIKI_LINES
+    )]
+    TooLong,
+}
+
+/// Largest wiki page body that can be saved, in bytes.
+pub const MAX_WIKI_BYTES: usize = 64 * 1024;
+/// Most lines a wiki page body can have.
+pub const MAX_WIKI_LINES: usize = 2000;
+
+/// Refuses bodies too large to be saved as a wiki page.
+pub fn check_wiki_body(body: &str) -> Result<(), WikiError> {
+    if body.len() > MAX_WIKI_BYTES || body.lines().count() > MAX_WIKI_LINES {
+        return Err(WikiError::TooLong);
+    }
+    Ok(())
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Inline {
+    Text(String),
+    LineBreak,
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Link {
+        text: String,
+        url: String,
+    },
+    /// A link to a tag page. `name` is normalized, `text` is shown as is.
+    TagLink {
+        name: String,
+        text: String,
+    },
+    Post(u64),
+}
+
+/// A paragraph of markup.
+pub type Paragraph = Vec<Inline>;
+
+pub fn parse_markup(body: &str) -> Vec<Paragraph> {
+    let body = body.replace("\r\n", "\n");
+    let mut paragraphs = vec![];
+    let mut lines: Vec<&str> = vec![];
+
+    for line in body.lines().chain([""]) {
+        if !line.trim().is_empty() {
+            lines.push(line.trim_end());
+            continue;
+        }
+        if lines.is_empty() {
+            continue;
+        }
+
+        let mut paragraph = vec![];
+        for (i, line) in lines.drain(..).enumerate() {
+            if i > 0 {
+                paragraph.push(Inline::LineBreak);
+            }
+            paragraph.extend(parse_inline(line));
+        }
+        paragraphs.push(paragraph);
+    }
+
+    paragraphs
+}
+
+/// Longest tag name a `[[tag_name]]` link can have, in bytes.
+const MAX_TAG_LINK_NAME: usize = 200;
+
+/// For every byte offset of a line, where the next closing delimiter of each
+/// kind starts. Looking them up instead of searching keeps parsing linear
+/// when a line is full of openers that are never closed.
+struct Closers {
+    /// `**`
+    bold: Vec<Option<usize>>,
+    /// A `*` that is not part of a `**`, counting pairs from the offset.
+    italic: Vec<Option<usize>>,
+    /// `]]`
+    tag_link: Vec<Option<usize>>,
+    /// `|`
+    pipe: Vec<Option<usize>>,
+    /// `]`
+    bracket: Vec<Option<usize>>,
+    /// `](`
+    link_url: Vec<Option<usize>>,
+    /// `)`
+    paren: Vec<Option<usize>>,
+}
+
+fn next_positions(bytes: &[u8], pattern: &[u8]) -> Vec<Option<usize>> {
+    let mut next = vec![None; bytes.len() + 1];
+    for i in (0..bytes.len()).rev() {
+        next[i] = if bytes[i..].starts_with(pattern) {
+            Some(i)
+        } else {
+            next[i + 1]
+        };
+    }
+    next
+}
+
+impl Closers {
+    fn new(text: &str) -> Closers {
+        let bytes = text.as_bytes();
+
+        let mut italic = vec![None; bytes.len() + 2];
+        for i in (0..bytes.len()).rev() {
+            italic[i] = match (bytes[i], bytes.get(i + 1)) {
+                (b'*', Some(b'*')) => italic[i + 2],
+                (b'*', _) => Some(i),
+                _ => italic[i + 1],
+            };
+        }
+
+        Closers {
+            bold: next_positions(bytes, b"**"),
+            italic,
+            tag_link: next_positions(bytes, b"]]"),
+            pipe: next_positions(bytes, b"|"),
+            bracket: next_positions(bytes, b"]"),
+            link_url: next_positions(bytes, b"]("),
+            paren: next_positions(bytes, b")"),
+        }
+    }
+}
+
+fn is_allowed_url(url: &str) -> bool {
+    url.starts_with("https://")
+        || url.starts_with("http://")
+        || (url.starts_with('/') && !url.starts_with("//"))
+}
+
+fn parse_inline(text: &str) -> Vec<Inline> {
+    let closers = Closers::new(text);
+    let mut inlines = vec![];
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let at = text.len() - rest.len();
+        let parsed = if rest.starts_with("**") {
+            closers.bold[at + 2].filter(|&end| end > at + 2).map(|end| {
+                (
+                    Inline::Bold(parse_inline(&text[at + 2..end])),
+                    &text[end + 2..],
+                )
+            })
+        } else if rest.starts_with('*') {
+            closers.italic[at + 1]
+                .filter(|&end| end > at + 1)
+                .map(|end| {
+                    (
+                        Inline::Italic(parse_inline(&text[at + 1..end])),
+                        &text[end + 1..],
+                    )
+                })
+        } else if rest.starts_with("[[") {
+            closers.tag_link[at + 2].and_then(|end| {
+                let (name, label) = match closers.pipe[at + 2].filter(|&pipe| pipe < end) {
+                    Some(pipe) => (&text[at + 2..pipe], &text[pipe + 1..end]),
+                    None => (&text[at + 2..end], ""),
+                };
+                if name.len() > MAX_TAG_LINK_NAME {
+                    return None;
+                }
+                let name = normalize_tag_name(name).ok()?;
+                let label = match label.trim() {
+                    "" => name.replace('_', " "),
+                    label => label.to_string(),
+                };
+                let link = Inline::TagLink { name, text: label };
+                Some((link, &text[end + 2..]))
+            })
+        } else if rest.starts_with('[') {
+            closers.link_url[at + 1]
+                .filter(|&middle| middle > at + 1 && closers.bracket[at + 1] == Some(middle))
+                .and_then(|middle| {
+                    let end = closers.paren[middle + 2]?;
+                    let url = text[middle + 2..end].trim();
+                    is_allowed_url(url).then(|| {
+                        let link = Inline::Link {
+                            text: text[at + 1..middle].to_string(),
+                            url: url.to_string(),
+                        };
+                        (link, &text[end + 1..])
+                    })
+                })
+        } else if let Some(after) = rest.strip_prefix("!post #") {
+            let end = after
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after.len());
+            after[..end]
+                .parse()
+                .ok()
+                .map(|id| (Inline::Post(id), &after[end..]))
+        } else {
+            None
+        };
+
+        match parsed {
+            Some((inline, after)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                inlines.push(inline);
+                rest = after;
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+    inlines
+}
+
+fn visit(inlines: &[Inline], f: &mut impl FnMut(&Inline)) {
+    for inline in inlines {
+        f(inline);
+        if let Inline::Bold(inner) | Inline::Italic(inner) = inline {
+            visit(inner, f);
+        }
+    }
+}
+
+/// Names of the tags linked from `paragraphs`, in order and without repeats.
+pub fn tag_links(paragraphs: &[Paragraph]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for paragraph in paragraphs {
+        visit(paragraph, &mut |inline| {
+            if let Inline::TagLink { name, .. } = inline {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        });
+    }
+    names
+}
+
+/// Ids of the posts shown in `paragraphs`, in order and without repeats.
+pub fn example_posts(paragraphs: &[Paragraph]) -> Vec<u64> {
+    let mut ids = vec![];
+    for paragraph in paragraphs {
+        visit(paragraph, &mut |inline| {
+            if let Inline::Post(id) = inline {
+                if !ids.contains(id) {
+                    ids.push(*id);
+                }
+            }
+        });
+    }
+    ids
+}
+
+/// Class of links to tags that do not exist.
+pub const MISSING_TAG_CLASS: &str = "text-gray-400";
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_inline(
+    inlines: &[Inline],
+    tags: &HashMap<String, Tag>,
+    posts: &HashMap<u64, Post>,
+    html: &mut String,
+) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => html.push_str(&escape_html(text)),
+            Inline::LineBreak => html.push_str("<br>"),
+            Inline::Bold(inner) => {
+                html.push_str("<strong>");
+                render_inline(inner, tags, posts, html);
+                html.push_str("</strong>");
+            }
+            Inline::Italic(inner) => {
+                html.push_str("<em>");
+                render_inline(inner, tags, posts, html);
+                html.push_str("</em>");
+            }
+            Inline::Link { text, url } => html.push_str(&format!(
+                r#"<a href="{}">{}</a>"#,
+                escape_html(url),
+                escape_html(text)
+            )),
+            Inline::TagLink { name, text } => {
+                let (name, class) = match tags.get(name) {
+                    Some(tag) => (&tag.name, tag.category.colour()),
+                    None => (name, MISSING_TAG_CLASS),
+                };
+                html.push_str(&format!(
+                    r#"<a href="/tag/{}" class="{}">{}</a>"#,
+                    escape_html(name),
+                    class,
+                    escape_html(text)
+                ));
+            }
+            Inline::Post(id) => match posts.get(id) {
+                Some(post) => html.push_str(&format!(
+                    r#"<a href="/post/{id}"><img src="/uploads/{}" alt="post #{id}"></a>"#,
+                    post.thumbnail_file_name()
+                )),
+                None => html.push_str(&format!("post #{id}")),
+            },
+        }
+    }
+}
+
+/// Renders `paragraphs` to HTML. Tag links go to the canonical tag found in
+/// `tags` under the linked name, or are greyed out if there is none. Posts
+/// missing from `posts` are shown as text.
+pub fn render_markup(
+    paragraphs: &[Paragraph],
+    tags: &HashMap<String, Tag>,
+    posts: &HashMap<u64, Post>,
+) -> String {
+    let mut html = String::new();
+    for paragraph in paragraphs {
+        html.push_str("<p>");
+        render_inline(paragraph, tags, posts, &mut html);
+        html.push_str("</p>");
+    }
+    html
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Length of the longest common subsequence of `old` and each prefix of
+/// `new`, keeping only one row of the table.
+fn common_lengths<'a>(old: impl Iterator<Item = &'a str>, new: &[&'a str]) -> Vec<usize> {
+    let mut row = vec![0; new.len() + 1];
+    for old_line in old {
+        let mut diagonal = 0;
+        for (j, new_line) in new.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if old_line == *new_line {
+                diagonal + 1
+            } else {
+                row[j + 1].max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row
+}
+
+/// Pairs of indices of lines in a longest common subsequence of `old` and
+/// `new`, found by splitting `old` in half and recursing (Hirschberg).
+fn common_lines(
+    old: &[&str],
+    new: &[&str],
+    offsets: (usize, usize),
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    if old.is_empty() || new.is_empty() {
+        return;
+    }
+    if let [line] = old {
+        if let Some(j) = new.iter().position(|new_line| new_line == line) {
+            pairs.push((offsets.0, offsets.1 + j));
+        }
+        return;
+    }
+
+    let middle = old.len() / 2;
+    let front = common_lengths(old[..middle].iter().copied(), new);
+    let reversed: Vec<&str> = new.iter().rev().copied().collect();
+    let back = common_lengths(old[middle..].iter().rev().copied(), &reversed);
+    let split = (0..=new.len())
+        .max_by_key(|&j| (front[j] + back[new.len() - j], std::cmp::Reverse(j)))
+        .unwrap_or(0);
+
+    common_lines(&old[..middle], &new[..split], offsets, pairs);
+    common_lines(
+        &old[middle..],
+        &new[split..],
+        (offsets.0 + middle, offsets.1 + split),
+        pairs,
+    );
+}
+
+/// Line by line difference between `old` and `new`, based on their longest
+/// common subsequence of lines. Takes space linear in the number of lines.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    common_lines(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+        (prefix, prefix),
+        &mut pairs,
+    );
+    pairs.extend((1..=suffix).rev().map(|k| (old.len() - k, new.len() - k)));
+
+    let mut diff = vec![];
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs.into_iter().chain([(old.len(), new.len())]) {
+        diff.extend(
+            old[i..next_i]
+                .iter()
+                .map(|line| DiffLine::Removed(line.to_string())),
+        );
+        diff.extend(
+            new[j..next_j]
+                .iter()
+                .map(|line| DiffLine::Added(line.to_string())),
+        );
+        if next_i < old.len() {
+            diff.push(DiffLine::Same(old[next_i].to_string()));
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    diff
+}
@@ -0,0 +1,83 @@
+use crate::api::users::{Login, Register};
+use crate::models::user::{User, UserError, MIN_PASSWORD_LENGTH};
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+const INPUT_CLASS: &str = "w-full px-3 py-2 text-sm rounded border border-gray-300";
+
+fn account_message(result: Option<Result<Result<User, UserError>, ServerFnError>>) -> View {
+    match result {
+        Some(Ok(Err(error))) => {
+            view! { <p class="text-red-700">{error.to_string()}</p> }.into_view()
+        }
+        Some(Err(e)) => view! { <p class="text-red-700">{format!("Error: {}", e)}</p> }.into_view(),
+        _ => ().into_view(),
+    }
+}
+
+#[component]
+pub fn LoginPage() -> impl IntoView {
+    let login = create_server_action::<Login>();
+    let register = create_server_action::<Register>();
+
+    // Both log in, after which the post grid is the place to be.
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        let logged_in = matches!(login.value().get(), Some(Ok(Ok(_))))
+            || matches!(register.value().get(), Some(Ok(Ok(_))));
+        if logged_in {
+            navigate("/posts", Default::default());
+        }
+    });
+
+    view! {
+        <Title text="Log in" />
+        <div class="flex flex-col py-6 min-h-screen sm:py-12">
+            <div class="container grid gap-12 px-4 mx-auto max-w-3xl sm:grid-cols-2 sm:px-8">
+                <div>
+                    <h1 class="mb-4 text-2xl font-semibold">"Log in"</h1>
+                    <ActionForm action=login class="flex flex-col gap-4">
+                        <input type="text" name="name" placeholder="User name" autocomplete="username" class=INPUT_CLASS />
+                        <input
+                            type="password"
+                            name="password"
+                            placeholder="Password"
+                            autocomplete="current-password"
+                            class=INPUT_CLASS
+                        />
+                        <button
+                            type="submit"
+                            class="py-2 px-4 font-bold text-white bg-blue-500 rounded-lg hover:bg-blue-600"
+                            disabled=move || login.pending().get()
+                        >
+                            "Log in"
+                        </button>
+                    </ActionForm>
+                    {move || account_message(login.value().get())}
+                </div>
+                <div>
+                    <h2 class="mb-4 text-2xl font-semibold">"Register"</h2>
+                    <ActionForm action=register class="flex flex-col gap-4">
+                        <input type="text" name="name" placeholder="User name" autocomplete="username" class=INPUT_CLASS />
+                        <input
+                            type="password"
+                            name="password"
+                            placeholder=format!("Password, at least {} characters", MIN_PASSWORD_LENGTH)
+                            autocomplete="new-password"
+                            class=INPUT_CLASS
+                        />
+                        <button
+                            type="submit"
+                            class="py-2 px-4 font-bold text-white bg-blue-500 rounded-lg hover:bg-blue-600"
+                            disabled=move || register.pending().get()
+                        >
+                            "Register"
+                        </button>
+                    </ActionForm>
+                    {move || account_message(register.value().get())}
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,3 +1,11 @@
+mod login_page;
+mod post_grid;
+mod post_page;
+mod tag_page;
 mod tag_table;
 
+pub use login_page::*;
+pub use post_grid::*;
+pub use post_page::*;
+pub use tag_page::*;
 pub use tag_table::*;
@@ -0,0 +1,191 @@
+use crate::api::posts::get_paginated_posts;
+use crate::components::post::PostTile;
+use crate::components::tag_input::TagInput;
+use crate::models::search::SearchError;
+use leptos::*;
+use leptos_router::*;
+use web_sys::window;
+
+/// Shows the query with the part the error is about highlighted.
+#[component]
+fn SearchErrorMessage(query: String, error: SearchError) -> impl IntoView {
+    let start = error.start.min(query.len());
+    let end = error.end.clamp(start, query.len());
+
+    view! {
+        <div class="p-4 mb-4 text-red-700 bg-red-100 rounded-lg">
+            <p class="font-semibold">{error.kind.to_string()}</p>
+            <p class="font-mono whitespace-pre-wrap">
+                {query[..start].to_string()}
+                <span class="underline decoration-wavy">{query[start..end].to_string()}</span>
+                {query[end..].to_string()}
+            </p>
+        </div>
+    }
+}
+
+#[component]
+pub fn PostGrid() -> impl IntoView {
+    let (page, set_page) = create_signal(1u32);
+    let per_page = 40u32;
+
+    let query = use_query_map();
+    let search = move || query.with(|query| query.get("tags").cloned().unwrap_or_default());
+
+    // A new search starts again on the first page.
+    create_effect(move |_| {
+        search();
+        set_page.set(1);
+    });
+
+    let (dark_mode, set_dark_mode) = create_signal(false);
+
+    // Load the initial dark mode preference from localStorage
+    create_effect(move |_| {
+        if let Some(window) = window() {
+            if let Ok(Some(storage)) = window.local_storage() {
+                if let Ok(Some(preference)) = storage.get_item("dark_mode") {
+                    set_dark_mode.set(preference == "true");
+                }
+            }
+        }
+    });
+
+    // Update localStorage when dark mode changes
+    create_effect(move |_| {
+        if let Some(window) = window() {
+            if let Ok(Some(storage)) = window.local_storage() {
+                let _ = storage.set_item("dark_mode", &dark_mode.get().to_string());
+            }
+        }
+    });
+
+    let toggle_dark_mode = move |_| {
+        set_dark_mode.update(|dm| *dm = !*dm);
+    };
+
+    let posts = create_resource(
+        move || (page.get(), search()),
+        move |(current_page, search)| async move {
+            let query = (!search.trim().is_empty()).then(|| search.clone());
+            get_paginated_posts(current_page, per_page, query)
+                .await
+                .unwrap_or_else(|_| Ok(vec![]))
+        },
+    );
+
+    view! {
+        <div class=move || {
+            format!(
+                "flex flex-col py-6 min-h-screen {} sm:py-12",
+                if dark_mode() { "bg-gray-900 text-white" } else { "bg-white text-black" },
+            )
+        }>
+            <div class="container mx-auto px-4 sm:px-8">
+                <Form method="GET" action="/posts" class="flex gap-2 mb-6">
+                    <TagInput
+                        name="tags"
+                        initial=Signal::derive(search)
+                        placeholder="cat_ears -dog ~red ~blue rating:safe"
+                        dark_mode
+                    />
+                    <button
+                        type="submit"
+                        class="px-4 py-2 text-white bg-blue-600 rounded-lg hover:bg-blue-700"
+                    >
+                        "Search"
+                    </button>
+                </Form>
+                <Suspense fallback=move || {
+                    view! {
+                        <p class=move || {
+                            if dark_mode() { "text-white" } else { "text-black" }
+                        }>"Loading..."</p>
+                    }
+                }>
+                    {move || {
+                        posts
+                            .get()
+                            .map(|posts| match posts {
+                                Err(error) => {
+                                    view! { <SearchErrorMessage query=search() error /> }
+                                        .into_view()
+                                }
+                                Ok(posts) if posts.is_empty() => {
+                                    view! { <p>"No posts here."</p> }.into_view()
+                                }
+                                Ok(posts) => {
+                                    view! {
+                                        <div class="flex flex-wrap gap-4 justify-center">
+                                            {posts
+                                                .into_iter()
+                                                .map(|post| view! { <PostTile post dark_mode /> })
+                                                .collect::<Vec<_>>()}
+                                        </div>
+                                    }
+                                        .into_view()
+                                }
+                            })
+                    }}
+                </Suspense>
+                <div class="flex justify-center py-5">
+                    <div class="inline-flex">
+                        <button
+                            class=move || {
+                                format!(
+                                    "text-sm font-semibold py-2 px-4 rounded-l transition duration-300 ease-in-out {}",
+                                    if dark_mode() {
+                                        "bg-gray-700 text-white hover:bg-gray-600"
+                                    } else {
+                                        "bg-gray-300 text-gray-800 hover:bg-gray-400"
+                                    },
+                                )
+                            }
+                            on:click=move |_| {
+                                set_page
+                                    .update(|p| {
+                                        if *p > 1 {
+                                            *p -= 1;
+                                        }
+                                    });
+                            }
+                        >
+                            "Prev"
+                        </button>
+                        <button
+                            class=move || {
+                                format!(
+                                    "text-sm font-semibold py-2 px-4 rounded-r transition duration-300 ease-in-out {}",
+                                    if dark_mode() {
+                                        "bg-gray-700 text-white hover:bg-gray-600"
+                                    } else {
+                                        "bg-gray-300 text-gray-800 hover:bg-gray-400"
+                                    },
+                                )
+                            }
+                            on:click=move |_| set_page.update(|p| *p += 1)
+                        >
+                            "Next"
+                        </button>
+                    </div>
+                </div>
+
+                <button
+                    on:click=toggle_dark_mode
+                    class=move || {
+                        format!(
+                            "mt-4 px-4 py-2 rounded-lg transition duration-300 ease-in-out {}",
+                            if dark_mode() {
+                                "bg-gray-700 text-white hover:bg-gray-600"
+                            } else {
+                                "bg-gray-200 text-gray-800 hover:bg-gray-300"
+                            },
+                        )
+                    }
+                >
+                    {move || if dark_mode() { "Light Mode" } else { "Dark Mode" }}
+                </button>
+            </div>
+        </div>
+    }
+}
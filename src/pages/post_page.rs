@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use crate::api::posts::get_post;
+use crate::components::post::{PostMedia, SafetyBadge};
+use crate::models::date::format_timestamp;
+use crate::models::post::PostDetails;
+use crate::models::tag::{Tag, TagCategory};
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+/// Formats a byte count the way file managers do, e.g. `1.4 MiB`.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[component]
+pub fn PostPage() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || {
+        params
+            .with(|params| params.get("id").and_then(|id| id.parse::<u64>().ok()))
+            .unwrap_or_default()
+    };
+
+    let details = create_resource(
+        id,
+        move |id| async move { get_post(id).await.ok().flatten() },
+    );
+
+    view! {
+        <div class="flex flex-col py-6 min-h-screen sm:py-12">
+            <div class="container mx-auto px-4 sm:px-8">
+                <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                    {move || {
+                        details
+                            .get()
+                            .map(|details| match details {
+                                Some(details) => view! { <PostView details /> }.into_view(),
+                                None => {
+                                    view! {
+                                        <Title text="Post not found" />
+                                        <p>"There is no such post."</p>
+                                    }
+                                        .into_view()
+                                }
+                            })
+                    }}
+                </Suspense>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn PostView(details: PostDetails) -> impl IntoView {
+    let PostDetails {
+        post,
+        tags,
+        uploader_name,
+        public_url,
+    } = details;
+
+    let title = format!("Post #{}", post.custom_id);
+    // Unfurlers ignore relative image URLs, so without `PUBLIC_URL` there is
+    // no preview image to offer.
+    let preview = (!public_url.is_empty())
+        .then(|| format!("{}/uploads/{}", public_url, post.sample_file_name()));
+    let original = format!("/uploads/{}", post.file_name());
+    let uploader = match (post.uploader_id, uploader_name) {
+        (0, _) => String::from("Anonymous"),
+        (_, Some(name)) => name,
+        (id, None) => format!("User #{}", id),
+    };
+
+    let mut categories: BTreeMap<TagCategory, Vec<Tag>> = BTreeMap::new();
+    for tag in tags {
+        categories.entry(tag.category).or_default().push(tag);
+    }
+
+    view! {
+        <Title text=title.clone() />
+        <Meta property="og:title" content=title.clone() />
+        <Meta property="og:type" content="website" />
+        {preview.map(|preview| view! { <Meta property="og:image" content=preview /> })}
+
+        <div class="flex flex-col gap-6 md:flex-row">
+            <aside class="md:w-64 shrink-0">
+                <h2 class="mb-2 text-lg font-semibold">"Tags"</h2>
+                {categories
+                    .into_iter()
+                    .map(|(category, tags)| {
+                        view! {
+                            <h3 class="mt-3 text-sm font-semibold uppercase">
+                                {category.display_name()}
+                            </h3>
+                            <ul>
+                                {tags
+                                    .into_iter()
+                                    .map(|tag| {
+                                        view! {
+                                            <li>
+                                                <a
+                                                    href=format!("/posts?tags={}", tag.name)
+                                                    class=format!("{} hover:underline", category.colour())
+                                                >
+                                                    {tag.name.clone()}
+                                                </a>
+                                                <span class="ml-1 text-xs text-gray-500">
+                                                    {tag.use_count}
+                                                </span>
+                                            </li>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </ul>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+
+                <h2 class="mt-6 mb-2 text-lg font-semibold">"Information"</h2>
+                <ul class="text-sm">
+                    <li>{format!("ID: {}", post.custom_id)}</li>
+                    <li>{format!("Size: {}", human_size(post.file_size))}</li>
+                    <li>{format!("Dimensions: {}x{}", post.image_width, post.image_height)}</li>
+                    <li>{format!("Type: {}", post.mime_type)}</li>
+                    <li>{format!("Uploader: {}", uploader)}</li>
+                    <li>{format!("Uploaded: {}", format_timestamp(post.uploaded_at))}</li>
+                    <li>"Safety: " <SafetyBadge safety=post.safety.clone() /></li>
+                    <li class="break-all">{format!("SHA-256: {}", post.hash_hex())}</li>
+                    <li>
+                        <a href=original class="text-blue-600 hover:underline">
+                            "Original file"
+                        </a>
+                    </li>
+                </ul>
+            </aside>
+
+            <section class="grow">
+                <PostMedia post />
+            </section>
+        </div>
+    }
+}
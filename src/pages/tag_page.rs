@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use crate::api::tags::{get_tag, MergeTags, RenameTag, UpdateTag};
+use crate::components::tag_input::TagInput;
+use crate::components::wiki::TagWiki;
+use crate::error_template::server_error_message;
+use crate::models::date::format_timestamp;
+use crate::models::tag::{
+    normalize_tag_name, TagAction, TagCategory, TagChangeError, TagDetails, TagEdit, TagEditError,
+    TagHistoryEntry,
+};
+use crate::models::user::user_label;
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+const INPUT_CLASS: &str = "w-full px-3 py-2 text-sm rounded border border-gray-300";
+
+#[component]
+pub fn TagPage() -> impl IntoView {
+    let params = use_params_map();
+    let name = move || params.with(|params| params.get("name").cloned().unwrap_or_default());
+
+    let update = create_server_action::<UpdateTag>();
+    let rename = create_server_action::<RenameTag>();
+    let merge = create_server_action::<MergeTags>();
+    let details = create_resource(
+        move || (name(), update.version().get(), merge.version().get()),
+        move |(name, _, _)| async move { get_tag(name).await.ok().flatten() },
+    );
+
+    // The page of a renamed tag is under its new name.
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if let Some(Ok(Ok(()))) = rename.value().get() {
+            let new_name = rename
+                .input()
+                .get_untracked()
+                .and_then(|input| normalize_tag_name(&input.new_name).ok());
+            if let Some(new_name) = new_name {
+                navigate(&format!("/tag/{}", new_name), Default::default());
+            }
+        }
+    });
+
+    view! {
+        <div class="flex flex-col py-6 min-h-screen sm:py-12">
+            <div class="container mx-auto px-4 max-w-xl sm:px-8">
+                <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                    {move || {
+                        details
+                            .get()
+                            .map(|details| match details {
+                                Some(details) => {
+                                    let tag = details.tag.clone();
+                                    let name = tag.name.clone();
+                                    let history = details.history.clone();
+                                    let user_names = details.user_names.clone();
+                                    view! {
+                                        <Title text=format!("Tag: {}", tag.name) />
+                                        <h1 class=format!(
+                                            "mb-1 text-2xl font-semibold {}",
+                                            tag.category.colour(),
+                                        )>{tag.name.clone()}</h1>
+                                        <p class="mb-6 text-sm text-gray-500">
+                                            {format!(
+                                                "{} · used {} times",
+                                                tag.category.display_name(),
+                                                tag.use_count,
+                                            )}
+                                        </p>
+                                        <TagWiki name=name.clone() />
+                                        <h2 class="mt-8 mb-2 text-lg font-semibold">"Settings"</h2>
+                                        <TagEditor details update />
+                                        <TagChanges name history user_names rename merge />
+                                    }
+                                        .into_view()
+                                }
+                                None => {
+                                    view! {
+                                        <Title text="Tag not found" />
+                                        <p>"There is no such tag."</p>
+                                    }
+                                        .into_view()
+                                }
+                            })
+                    }}
+                </Suspense>
+                <p class="mt-4">
+                    {move || match update.value().get() {
+                        Some(Ok(Ok(()))) => view! { <span class="text-green-700">"Saved."</span> }.into_view(),
+                        Some(Ok(Err(error))) => {
+                            view! { <span class="text-red-700">{error.to_string()}</span> }.into_view()
+                        }
+                        Some(Err(e)) => {
+                            view! { <span class="text-red-700">{server_error_message(&e)}</span> }
+                                .into_view()
+                        }
+                        None => ().into_view(),
+                    }}
+                </p>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn TagEditor(
+    details: TagDetails,
+    update: Action<UpdateTag, Result<Result<(), TagEditError>, ServerFnError>>,
+) -> impl IntoView {
+    let TagDetails {
+        tag,
+        alias_of,
+        implications,
+        ..
+    } = details;
+
+    let category = create_rw_signal(tag.category);
+    let alias = create_rw_signal(alias_of.unwrap_or_default());
+    let implied = create_rw_signal(implications.join(" "));
+    let back_apply = create_rw_signal(false);
+    // Like the post page, this page has no dark mode yet.
+    let dark_mode = Signal::derive(|| false);
+
+    let name = tag.name.clone();
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let alias = alias.get();
+        update.dispatch(UpdateTag {
+            name: name.clone(),
+            edit: TagEdit {
+                // The description is the wiki page, which has its own editor.
+                description: None,
+                category: category.get(),
+                alias_of: (!alias.trim().is_empty()).then_some(alias),
+                implications: implied.get().split_whitespace().map(String::from).collect(),
+                back_apply: back_apply.get(),
+            },
+        });
+    };
+
+    view! {
+        <form on:submit=on_submit class="flex flex-col gap-4">
+            <label class="flex flex-col gap-1">
+                "Category"
+                <select
+                    class=INPUT_CLASS
+                    on:change=move |ev| {
+                        let category_number = event_target_value(&ev).parse::<u8>().ok();
+                        if let Some(new_category) = category_number
+                            .and_then(|number| TagCategory::try_from(number).ok())
+                        {
+                            category.set(new_category);
+                        }
+                    }
+                >
+                    {TagCategory::ALL
+                        .into_iter()
+                        .map(|option| {
+                            view! {
+                                <option
+                                    value=(option as u8).to_string()
+                                    selected=move || category.get() == option
+                                >
+                                    {option.display_name()}
+                                </option>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+            </label>
+            <label class="flex flex-col gap-1">
+                "Alias of"
+                <TagInput name="alias_of" value=alias placeholder="Not an alias" dark_mode />
+            </label>
+            <label class="flex flex-col gap-1">
+                "Implies"
+                <TagInput name="implications" value=implied placeholder="No implications" dark_mode />
+            </label>
+            <label class="flex gap-2 items-center">
+                <input
+                    type="checkbox"
+                    prop:checked=back_apply
+                    on:change=move |ev| back_apply.set(event_target_checked(&ev))
+                />
+                "Add new implied tags to posts that already have this tag"
+            </label>
+            <button
+                type="submit"
+                class="py-2 px-4 font-bold text-white bg-blue-500 rounded-lg hover:bg-blue-600"
+                disabled=move || update.pending().get()
+            >
+                "Save"
+            </button>
+        </form>
+    }
+}
+
+fn change_message(result: Option<Result<Result<(), TagChangeError>, ServerFnError>>) -> View {
+    match result {
+        Some(Ok(Err(error))) => {
+            view! { <p class="text-red-700">{error.to_string()}</p> }.into_view()
+        }
+        Some(Err(e)) => {
+            view! { <p class="text-red-700">{server_error_message(&e)}</p> }.into_view()
+        }
+        _ => ().into_view(),
+    }
+}
+
+/// Renaming and merging the tag, and the history of its changes.
+#[component]
+fn TagChanges(
+    name: String,
+    history: Vec<TagHistoryEntry>,
+    user_names: HashMap<u64, String>,
+    rename: Action<RenameTag, Result<Result<(), TagChangeError>, ServerFnError>>,
+    merge: Action<MergeTags, Result<Result<(), TagChangeError>, ServerFnError>>,
+) -> impl IntoView {
+    let new_name = create_rw_signal(String::new());
+    let target = create_rw_signal(String::new());
+    let dark_mode = Signal::derive(|| false);
+
+    let rename_name = name.clone();
+    let on_rename = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        rename.dispatch(RenameTag {
+            name: rename_name.clone(),
+            new_name: new_name.get(),
+        });
+    };
+    let on_merge = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        merge.dispatch(MergeTags {
+            name: name.clone(),
+            target: target.get().trim().to_string(),
+        });
+    };
+
+    view! {
+        <h2 class="mt-8 mb-2 text-lg font-semibold">"Rename"</h2>
+        <form on:submit=on_rename class="flex gap-2">
+            <input
+                type="text"
+                class=INPUT_CLASS
+                placeholder="New name"
+                prop:value=new_name
+                on:input=move |ev| new_name.set(event_target_value(&ev))
+            />
+            <button type="submit" class="py-2 px-4 text-white bg-blue-500 rounded-lg hover:bg-blue-600">
+                "Rename"
+            </button>
+        </form>
+        {move || change_message(rename.value().get())}
+
+        <h2 class="mt-8 mb-2 text-lg font-semibold">"Merge"</h2>
+        <p class="mb-2 text-sm text-gray-500">
+            "Moves every post to the other tag and leaves this one as its alias."
+        </p>
+        <form on:submit=on_merge class="flex gap-2">
+            <TagInput name="target" value=target placeholder="Merge into" dark_mode />
+            <button type="submit" class="py-2 px-4 text-white bg-red-500 rounded-lg hover:bg-red-600">
+                "Merge"
+            </button>
+        </form>
+        {move || change_message(merge.value().get())}
+
+        {(!history.is_empty())
+            .then(|| view! { <h2 class="mt-8 mb-2 text-lg font-semibold">"History"</h2> })}
+        <ul class="text-sm">
+            {history
+                .iter()
+                .map(|entry| {
+                    let change = match entry.action {
+                        TagAction::Rename => {
+                            format!("Renamed from {} to {}", entry.old_name, entry.new_name)
+                        }
+                        TagAction::Merge => {
+                            format!("Merged {} into {}", entry.old_name, entry.new_name)
+                        }
+                        TagAction::Create => format!("Created {}", entry.new_name),
+                        TagAction::Edit => "Changed the category, alias or implications".to_string(),
+                        TagAction::Alias => {
+                            format!("Made {} an alias of {}", entry.old_name, entry.new_name)
+                        }
+                        TagAction::Implications => "Changed the implications".to_string(),
+                        TagAction::Delete => format!("Deleted {}", entry.old_name),
+                        TagAction::Tag => {
+                            format!("Added to post #{}", entry.post_id.unwrap_or_default())
+                        }
+                        TagAction::Untag => {
+                            format!("Removed from post #{}", entry.post_id.unwrap_or_default())
+                        }
+                    };
+                    view! {
+                        <li>
+                            <span class="text-gray-500">{format_timestamp(entry.changed_at)}</span>
+                            " "
+                            {change}
+                            {format!(" by {}", user_label(&user_names, entry.user_id))}
+                        </li>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </ul>
+    }
+}
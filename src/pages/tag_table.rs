@@ -1,5 +1,6 @@
-use crate::api::tags::get_paginated_tags;
+use crate::api::tags::{get_paginated_tags, DeleteTag};
 use crate::components::modal::Modal;
+use crate::models::tag::ListedTag;
 use leptos::*;
 use web_sys::window;
 use web_sys::SubmitEvent;
@@ -37,9 +38,13 @@ pub fn TagTable() -> impl IntoView {
         set_dark_mode.update(|dm| *dm = !*dm);
     };
 
+    let delete_tag = create_server_action::<DeleteTag>();
+    // The name of the tag the delete dialog is open for.
+    let (deleting, set_deleting) = create_signal(None::<String>);
+
     let tags = create_resource(
-        move || (page.get(), current_search.get()),
-        move |(current_page, search)| async move {
+        move || (page.get(), current_search.get(), delete_tag.version().get()),
+        move |(current_page, search, _)| async move {
             let search_option = if search.is_empty() {
                 None
             } else {
@@ -63,6 +68,12 @@ pub fn TagTable() -> impl IntoView {
     let open_modal = move |_| set_show_modal(true);
     let close_modal = move |_| set_show_modal(false);
 
+    create_effect(move |_| {
+        if let Some(Ok(Ok(()))) = delete_tag.value().get() {
+            set_deleting(None);
+        }
+    });
+
     view! {
         <div class=move || {
             format!(
@@ -163,12 +174,22 @@ pub fn TagTable() -> impl IntoView {
                                                                     },
                                                                 )
                                                             }>"Amount of Uses"</th>
+                                                            <th class=move || {
+                                                                format!(
+                                                                    "px-5 py-3 border-b-2 {}",
+                                                                    if dark_mode() {
+                                                                        "bg-gray-700 border-gray-600"
+                                                                    } else {
+                                                                        "bg-gray-100 border-gray-200"
+                                                                    },
+                                                                )
+                                                            }></th>
                                                         </tr>
                                                     </thead>
                                                     <tbody>
                                                         {tags
                                                             .into_iter()
-                                                            .map(|tag| {
+                                                            .map(|ListedTag { tag, alias_of }| {
                                                                 view! {
                                                                     <tr>
                                                                         <td class=move || {
@@ -181,7 +202,25 @@ pub fn TagTable() -> impl IntoView {
                                                                                 },
                                                                             )
                                                                         }>
-                                                                            <p class="whitespace-no-wrap">{&tag.name}</p>
+                                                                            <p class=format!(
+                                                                                "whitespace-no-wrap {}",
+                                                                                tag.category.colour(),
+                                                                            )>
+                                                                                <a
+                                                                                    href=format!("/tag/{}", tag.name)
+                                                                                    class="hover:underline"
+                                                                                >
+                                                                                    {&tag.name}
+                                                                                </a>
+                                                                                {alias_of
+                                                                                    .map(|target| {
+                                                                                        view! {
+                                                                                            <span class="ml-1 text-gray-500">
+                                                                                                {format!("→ {}", target)}
+                                                                                            </span>
+                                                                                        }
+                                                                                    })}
+                                                                            </p>
                                                                         </td>
                                                                         <td class=move || {
                                                                             format!(
@@ -193,7 +232,10 @@ pub fn TagTable() -> impl IntoView {
                                                                                 },
                                                                             )
                                                                         }>
-                                                                            <p class="whitespace-no-wrap">{tag.category}</p>
+                                                                            <p class=format!(
+                                                                                "whitespace-no-wrap {}",
+                                                                                tag.category.colour(),
+                                                                            )>{tag.category.display_name()}</p>
                                                                         </td>
                                                                         <td class=move || {
                                                                             format!(
@@ -207,6 +249,29 @@ pub fn TagTable() -> impl IntoView {
                                                                         }>
                                                                             <p class="whitespace-no-wrap">{tag.use_count}</p>
                                                                         </td>
+                                                                        <td class=move || {
+                                                                            format!(
+                                                                                "px-5 py-5 border-b text-sm text-right {}",
+                                                                                if dark_mode() {
+                                                                                    "border-gray-700"
+                                                                                } else {
+                                                                                    "border-gray-200"
+                                                                                },
+                                                                            )
+                                                                        }>
+                                                                            <button
+                                                                                class="text-red-600 hover:underline"
+                                                                                on:click={
+                                                                                    let name = tag.name.clone();
+                                                                                    move |_| {
+                                                                                        delete_tag.value().set(None);
+                                                                                        set_deleting(Some(name.clone()));
+                                                                                    }
+                                                                                }
+                                                                            >
+                                                                                "Delete"
+                                                                            </button>
+                                                                        </td>
                                                                     </tr>
                                                                 }
                                                             })
@@ -294,6 +359,26 @@ pub fn TagTable() -> impl IntoView {
                         <crate::components::tag::AddTagForm dark_mode=dark_mode />
                     </Modal>
 
+                    <Modal
+                        is_open=Signal::derive(move || deleting.with(Option::is_some))
+                        on_close=move |_| set_deleting(None)
+                        dark_mode=dark_mode
+                    >
+                        {move || {
+                            deleting
+                                .get()
+                                .map(|name| {
+                                    view! {
+                                        <crate::components::tag::DeleteTagForm
+                                            name
+                                            delete_tag
+                                            dark_mode
+                                        />
+                                    }
+                                })
+                        }}
+                    </Modal>
+
                     <button
                         on:click=toggle_dark_mode
                         class=move || {
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use surrealdb::{Connection, Surreal};
+use thiserror::Error;
+
+use crate::models::tag::{Tag, TagAction, TagHistoryEntry};
+use crate::server_only::implication::with_implied_tags;
+use crate::server_only::tag::{
+    get_tag_by_id, get_tag_by_name, get_tags_by_ids, get_tags_by_names, history_entry,
+    retag_history_entries, ADD_HISTORY_ENTRY, ADD_POST_HISTORY_ENTRIES,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum AliasError {
+    #[error("there is no tag #{0}")]
+    UnknownTag(u64),
+    #[error("a tag cannot be an alias of itself")]
+    SelfAlias,
+    #[error("the tag `{0}` does not exist")]
+    UnknownTagName(String),
+}
+
+/// Follows `tag` to the tag it is an alias of, if it is one. Aliases always
+/// point at canonical tags, so one step is enough.
+pub async fn canonical_tag<C: Connection>(db: &Surreal<C>, tag: Tag) -> anyhow::Result<Tag> {
+    match tag.is_alias {
+        Some(target) => get_tag_by_id(db, target)
+            .await?
+            .ok_or_else(|| AliasError::UnknownTag(target).into()),
+        None => Ok(tag),
+    }
+}
+
+/// Like `get_tag_by_name`, but returns the canonical tag for aliases.
+pub async fn resolve_tag_by_name<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+) -> anyhow::Result<Option<Tag>> {
+    match get_tag_by_name(db, name).await? {
+        Some(tag) => Ok(Some(canonical_tag(db, tag).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Looks up `names` and maps every one that exists to its canonical tag.
+pub async fn resolve_tags_by_names<C: Connection>(
+    db: &Surreal<C>,
+    names: Vec<String>,
+) -> anyhow::Result<HashMap<String, Tag>> {
+    let tags = get_tags_by_names(db, names).await?;
+
+    let target_ids: Vec<u64> = tags.iter().filter_map(|tag| tag.is_alias).collect();
+    let targets: HashMap<u64, Tag> = get_tags_by_ids(db, target_ids)
+        .await?
+        .into_iter()
+        .map(|tag| (tag.custom_id, tag))
+        .collect();
+
+    let mut resolved = HashMap::new();
+    for tag in tags {
+        let canonical = match tag.is_alias {
+            Some(target) => targets
+                .get(&target)
+                .cloned()
+                .ok_or(AliasError::UnknownTag(target))?,
+            None => tag.clone(),
+        };
+        resolved.insert(tag.name, canonical);
+    }
+
+    Ok(resolved)
+}
+
+/// Resolves whitespace separated tag names to the ids of their canonical
+/// tags. Fails with `AliasError::UnknownTagName` for the first name that
+/// does not exist.
+pub async fn resolve_tag_ids<C: Connection>(
+    db: &Surreal<C>,
+    names: &str,
+) -> anyhow::Result<Vec<u64>> {
+    let names: Vec<String> = names.split_whitespace().map(str::to_lowercase).collect();
+    let found = resolve_tags_by_names(db, names.clone()).await?;
+
+    names
+        .into_iter()
+        .map(|name| match found.get(&name) {
+            Some(tag) => Ok(tag.custom_id),
+            None => Err(AliasError::UnknownTagName(name).into()),
+        })
+        .collect()
+}
+
+/// Makes `$alias` an alias of `$target`. Its posts get `$alias_implied`
+/// instead, see `alias_implied_tags`. Runs inside a transaction.
+pub const CREATE_ALIAS: &str = r#"
+    UPDATE post
+        SET tags = array::sort(array::union(array::complement(tags, [$alias]), $alias_implied))
+        WHERE tags CONTAINS $alias;
+    UPDATE tag
+        SET implications = array::union(array::complement(implications, [$alias]), [$target])
+        WHERE implications CONTAINS $alias AND custom_id NOT IN $alias_implied;
+    UPDATE tag
+        SET implications = array::complement(implications, [$alias])
+        WHERE implications CONTAINS $alias AND custom_id IN $alias_implied;
+    UPDATE tag SET is_alias = $target WHERE is_alias = $alias;
+    UPDATE tag
+        SET is_alias = $target, use_count = 0, implications = []
+        WHERE custom_id = $alias;
+"#;
+
+/// The tags posts of `alias_id` get instead when it becomes an alias of
+/// `target_id`. Tags the target implies keep no implication on the alias at
+/// all, as pointing them at the target would be a cycle.
+pub async fn alias_implied_tags<C: Connection>(
+    db: &Surreal<C>,
+    alias_id: u64,
+    target_id: u64,
+) -> anyhow::Result<Vec<u64>> {
+    let mut implied = with_implied_tags(db, &[target_id]).await?;
+    implied.retain(|id| *id != alias_id);
+    Ok(implied)
+}
+
+/// Turns `alias_id` into an alias of `target_id`, or of the tag `target_id`
+/// is an alias of, on behalf of `user_id`. Posts, implications and aliases
+/// using the old tag move over to the target.
+pub async fn create_alias<C: Connection>(
+    db: &Surreal<C>,
+    alias_id: u64,
+    target_id: u64,
+    user_id: u64,
+) -> anyhow::Result<()> {
+    let alias = get_tag_by_id(db, alias_id)
+        .await?
+        .ok_or(AliasError::UnknownTag(alias_id))?;
+    let target = get_tag_by_id(db, target_id)
+        .await?
+        .ok_or(AliasError::UnknownTag(target_id))?;
+    let target = canonical_tag(db, target).await?;
+
+    if target.custom_id == alias.custom_id {
+        return Err(AliasError::SelfAlias.into());
+    }
+
+    let implied = alias_implied_tags(db, alias.custom_id, target.custom_id).await?;
+    let post_entries = retag_history_entries(db, &alias, true, &implied, user_id).await?;
+
+    let entry = TagHistoryEntry {
+        new_name: target.name,
+        target_id: Some(target.custom_id),
+        ..history_entry(db, &alias, TagAction::Alias, user_id).await?
+    };
+
+    db.query(format!(
+        "BEGIN TRANSACTION; {} {} {} COMMIT TRANSACTION;",
+        CREATE_ALIAS, ADD_HISTORY_ENTRY, ADD_POST_HISTORY_ENTRIES
+    ))
+    .bind(("alias", alias.custom_id))
+    .bind(("target", target.custom_id))
+    .bind(("alias_implied", implied))
+    .bind(("entry", entry))
+    .bind(("post_entries", post_entries))
+    .await?
+    .check()?;
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use http::header::{COOKIE, SET_COOKIE};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use leptos::{use_context, ServerFnError};
+use leptos_axum::ResponseOptions;
+
+use crate::models::permission::{Permission, Role};
+use crate::models::user::User;
+use crate::server_only::config::{permissions, session_days};
+use crate::server_only::db::get_db_connection;
+use crate::server_only::user::get_session_user;
+
+/// Name of the cookie holding the session token.
+pub const SESSION_COOKIE: &str = "session";
+
+/// The session token among the cookies in `headers`, if there is one.
+pub fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, value)| *name == SESSION_COOKIE && !value.is_empty())
+        .map(|(_, value)| value.to_string())
+}
+
+/// The `Set-Cookie` value that stores `token`. It is never readable by
+/// scripts and only sent over HTTPS (or to localhost).
+pub fn session_cookie(token: &str) -> String {
+    format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+        SESSION_COOKIE,
+        token,
+        session_days() * 24 * 60 * 60
+    )
+}
+
+/// The `Set-Cookie` value that removes the session cookie.
+pub fn expired_session_cookie() -> String {
+    format!(
+        "{}=; Path=/; Max-Age=0; HttpOnly; Secure; SameSite=Lax",
+        SESSION_COOKIE
+    )
+}
+
+/// Extracts the user the request is logged in as, `None` for anonymous
+/// requests. Server functions get it through `current_user`.
+pub struct CurrentUser(pub Option<User>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(token) = session_token(&parts.headers) else {
+            return Ok(CurrentUser(None));
+        };
+
+        let internal = |e: String| (StatusCode::INTERNAL_SERVER_ERROR, e);
+        let db = get_db_connection()
+            .await
+            .map_err(|e| internal(e.to_string()))?;
+        match get_session_user(&db, &token).await {
+            Ok(user) => Ok(CurrentUser(user)),
+            Err(e) => Err(internal(e.to_string())),
+        }
+    }
+}
+
+/// The user the current request is logged in as.
+pub async fn current_user() -> Result<Option<User>, ServerFnError> {
+    let CurrentUser(user) = leptos_axum::extract().await?;
+    Ok(user)
+}
+
+/// Checks that the current request may do `permission` and returns the id
+/// of its user, 0 for nobody. Otherwise the response becomes a 403 and the
+/// error carries the `PermissionError` message.
+pub async fn authorize(permission: Permission) -> Result<u64, ServerFnError> {
+    let user = current_user().await?;
+    let role = user.as_ref().map_or(Role::Anonymous, |user| user.role);
+    let matrix = permissions().map_err(|e| {
+        ServerFnError::<server_fn::error::NoCustomError>::ServerError(e.to_string())
+    })?;
+
+    if let Err(e) = matrix.check(role, permission) {
+        if let Some(response) = use_context::<ResponseOptions>() {
+            response.set_status(StatusCode::FORBIDDEN);
+        }
+        return Err(ServerFnError::ServerError(e.to_string()));
+    }
+
+    Ok(user.map_or(0, |user| user.custom_id))
+}
+
+/// Adds `cookie` as a `Set-Cookie` header to the response.
+pub fn set_cookie(cookie: &str) -> Result<(), ServerFnError> {
+    let value = HeaderValue::from_str(cookie).map_err(|e| {
+        ServerFnError::<server_fn::error::NoCustomError>::ServerError(e.to_string())
+    })?;
+    if let Some(response) = use_context::<ResponseOptions>() {
+        response.append_header(SET_COOKIE, value);
+    }
+    Ok(())
+}
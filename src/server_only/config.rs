@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::models::permission::{PermissionConfigError, PermissionMatrix};
+
+/// Reads `key` from the environment, falling back to `default` when it is
+/// unset or does not parse.
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Largest image (width * height) accepted on upload. `MAX_IMAGE_PIXELS`.
+pub fn max_image_pixels() -> u64 {
+    env_or("MAX_IMAGE_PIXELS", 100_000_000)
+}
+
+/// Longest side of the small previews shown in post grids. `THUMBNAIL_SIZE`.
+pub fn thumbnail_size() -> u32 {
+    env_or("THUMBNAIL_SIZE", 180)
+}
+
+/// Longest side of the medium sized sample shown on post pages. `SAMPLE_SIZE`.
+pub fn sample_size() -> u32 {
+    env_or("SAMPLE_SIZE", 850)
+}
+
+/// Directory holding uploaded originals and their previews. `UPLOADS_DIR`.
+pub fn uploads_dir() -> PathBuf {
+    env_or("UPLOADS_DIR", PathBuf::from("./uploads"))
+}
+
+/// Directory uploads are written to while they arrive, before they are moved
+/// into `uploads_dir`. It must not be inside `uploads_dir`, which is served
+/// publicly, and should be on the same filesystem so the move is a rename.
+/// `UPLOAD_TEMP_DIR`.
+pub fn upload_temp_dir() -> PathBuf {
+    env_or("UPLOAD_TEMP_DIR", PathBuf::from("./uploads-partial"))
+}
+
+/// Largest file accepted on upload, in bytes. `MAX_UPLOAD_SIZE`.
+pub fn max_upload_size() -> u64 {
+    env_or("MAX_UPLOAD_SIZE", 100 * 1024 * 1024)
+}
+
+/// The ffmpeg binary used to grab poster frames of videos. `FFMPEG_PATH`.
+pub fn ffmpeg_path() -> PathBuf {
+    env_or("FFMPEG_PATH", PathBuf::from("ffmpeg"))
+}
+
+/// Absolute URL the site is reachable under, used for OpenGraph links.
+/// `PUBLIC_URL`, e.g. `https://booru.example.com`.
+pub fn public_url() -> String {
+    env_or("PUBLIC_URL", String::new())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// How many days a login lasts. `SESSION_DAYS`.
+pub fn session_days() -> u64 {
+    env_or("SESSION_DAYS", 30)
+}
+
+/// The least role each permission needs: the defaults, changed by the
+/// `permission=role` pairs in `PERMISSIONS`, e.g.
+/// `upload_post=contributor,delete_tag=janitor`.
+pub fn permissions() -> Result<PermissionMatrix, PermissionConfigError> {
+    PermissionMatrix::default().with_overrides(&std::env::var("PERMISSIONS").unwrap_or_default())
+}
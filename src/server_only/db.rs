@@ -1,6 +1,7 @@
+use anyhow::anyhow;
 use leptos::{ServerFnError, ServerFnErrorErr};
 use surrealdb::engine::remote::ws::Ws;
-use surrealdb::{engine::remote::ws::Client, opt::auth::Root, Surreal};
+use surrealdb::{engine::remote::ws::Client, opt::auth::Root, Connection, Surreal};
 
 pub async fn get_db_connection() -> Result<Surreal<Client>, ServerFnErrorErr> {
     let surreal_url = std::env::var("SURREAL_URL").unwrap_or("127.0.0.1:8000".to_string());
@@ -25,3 +26,33 @@ pub async fn get_db_connection() -> Result<Surreal<Client>, ServerFnErrorErr> {
 
     Ok(db)
 }
+
+/// Bumps the counter stored in the `counter` table and returns the new value.
+pub async fn get_next_id<C: Connection>(db: &Surreal<C>, counter: &str) -> anyhow::Result<u64> {
+    #[derive(serde::Deserialize)]
+    struct IdCounter {
+        last_id: i64,
+    }
+
+    let result: Option<IdCounter> = db
+        .query("UPDATE type::table($counter) SET last_id += 1 RETURN last_id")
+        .bind(("counter", counter.to_string()))
+        .await?
+        .take(0)?;
+
+    match result {
+        Some(counter) => Ok(counter.last_id as u64),
+        None => {
+            // If no record exists, create one starting from 1
+            let created: Option<IdCounter> = db
+                .query("CREATE type::table($counter) SET last_id = 1 RETURN last_id")
+                .bind(("counter", counter.to_string()))
+                .await?
+                .take(0)?;
+            match created {
+                Some(counter) => Ok(counter.last_id as u64),
+                None => Err(anyhow!("Failed to initialize {}", counter)),
+            }
+        }
+    }
+}
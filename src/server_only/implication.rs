@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+
+use surrealdb::{Connection, Surreal};
+use thiserror::Error;
+
+use crate::models::tag::TagAction;
+use crate::server_only::tag::{
+    get_tag_by_id, get_tags_by_ids, history_entry, retag_history_entries, ADD_HISTORY_ENTRY,
+    ADD_POST_HISTORY_ENTRIES,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub enum ImplicationError {
+    #[error("there is no tag #{0}")]
+    UnknownTag(u64),
+    #[error("tag #{0} would end up implying itself")]
+    Cycle(u64),
+    #[error("tag #{0} is an alias, aliases cannot imply other tags")]
+    Alias(u64),
+}
+
+/// Returns `tag_ids` together with every tag they imply, directly or through
+/// other implications, sorted and without duplicates.
+pub async fn with_implied_tags<C: Connection>(
+    db: &Surreal<C>,
+    tag_ids: &[u64],
+) -> anyhow::Result<Vec<u64>> {
+    let mut closure: BTreeSet<u64> = tag_ids.iter().copied().collect();
+    let mut pending: Vec<u64> = closure.iter().copied().collect();
+
+    // Breadth first, one query per level of the implication graph.
+    while !pending.is_empty() {
+        pending = get_tags_by_ids(db, pending)
+            .await?
+            .into_iter()
+            .flat_map(|tag| tag.implications)
+            .filter(|implied| closure.insert(*implied))
+            .collect();
+    }
+
+    Ok(closure.into_iter().collect())
+}
+
+/// Replaces the implications of `$tag_id` with `$implications`.
+pub const SET_IMPLICATIONS: &str =
+    "UPDATE tag SET implications = $implications WHERE custom_id = $tag_id RETURN VALUE custom_id;";
+
+/// Adds `$implied_tags`, everything `$tag_id` implies, to its posts.
+pub const BACK_APPLY_IMPLICATIONS: &str =
+    "UPDATE post SET tags = array::sort(array::union(tags, $implied_tags)) WHERE tags CONTAINS $tag_id;";
+
+/// Replaces the implications of `tag_id` on behalf of `user_id`. Fails with
+/// an `ImplicationError` if a target does not exist, the tag is an alias or
+/// it would imply itself. With `back_apply`, posts that already have the tag get the implied
+/// tags too.
+pub async fn set_implications<C: Connection>(
+    db: &Surreal<C>,
+    tag_id: u64,
+    implications: Vec<u64>,
+    back_apply: bool,
+    user_id: u64,
+) -> anyhow::Result<()> {
+    let mut implications: Vec<u64> = implications;
+    implications.sort();
+    implications.dedup();
+
+    let tag = get_tag_by_id(db, tag_id)
+        .await?
+        .ok_or(ImplicationError::UnknownTag(tag_id))?;
+    if tag.is_alias.is_some() && !implications.is_empty() {
+        return Err(ImplicationError::Alias(tag_id).into());
+    }
+
+    let found = get_tags_by_ids(db, implications.clone()).await?;
+    if let Some(missing) = implications
+        .iter()
+        .find(|id| !found.iter().any(|tag| tag.custom_id == **id))
+    {
+        return Err(ImplicationError::UnknownTag(*missing).into());
+    }
+
+    let mut implied_tags = with_implied_tags(db, &implications).await?;
+    if implied_tags.contains(&tag_id) {
+        return Err(ImplicationError::Cycle(tag_id).into());
+    }
+    implied_tags.push(tag_id);
+    implied_tags.sort();
+
+    let mut statements = vec![SET_IMPLICATIONS];
+    let mut post_entries = vec![];
+    if back_apply {
+        statements.push(BACK_APPLY_IMPLICATIONS);
+        statements.push(ADD_POST_HISTORY_ENTRIES);
+        post_entries = retag_history_entries(db, &tag, false, &implied_tags, user_id).await?;
+    }
+    statements.push(ADD_HISTORY_ENTRY);
+
+    db.query(format!(
+        "BEGIN TRANSACTION; {} COMMIT TRANSACTION;",
+        statements.join("\n")
+    ))
+    .bind(("implications", implications))
+    .bind(("tag_id", tag_id))
+    .bind(("implied_tags", implied_tags))
+    .bind((
+        "entry",
+        history_entry(db, &tag, TagAction::Implications, user_id).await?,
+    ))
+    .bind(("post_entries", post_entries))
+    .await?
+    .check()?;
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+/// How many leading bytes of a file the header parsers below look at. JPEG
+/// frame headers can sit behind large EXIF segments, hence the generous size.
+pub const HEADER_LEN: usize = 1024 * 1024;
+
+/// Detects the type of an uploaded file from its leading bytes. Returns `None`
+/// for anything that is not one of the formats we accept.
+pub fn sniff_mime(header: &[u8]) -> Option<mime::Mime> {
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(mime::IMAGE_PNG);
+    }
+
+    if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(mime::IMAGE_JPEG);
+    }
+
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return Some(mime::IMAGE_GIF);
+    }
+
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+        return Some("image/webp".parse().unwrap());
+    }
+
+    if is_avif(header) {
+        return Some("image/avif".parse().unwrap());
+    }
+
+    if is_mp4(header) {
+        return Some("video/mp4".parse().unwrap());
+    }
+
+    // WebM is Matroska with a "webm" DocType in the leading EBML header.
+    if header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3])
+        && header[..header.len().min(64)]
+            .windows(4)
+            .any(|window| window == b"webm")
+    {
+        return Some("video/webm".parse().unwrap());
+    }
+
+    None
+}
+
+/// Reads `(width, height)` out of the file header without decoding any pixel
+/// data. Returns `None` if the type is unknown or the header is malformed.
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let dimensions = match sniff_mime(data)?.essence_str() {
+        "image/png" => png_dimensions(data),
+        "image/jpeg" => jpeg_dimensions(data),
+        "image/gif" => gif_dimensions(data),
+        "image/webp" => webp_dimensions(data),
+        "image/avif" => avif_dimensions(data),
+        _ => None,
+    }?;
+
+    match dimensions {
+        (0, _) | (_, 0) => None,
+        dimensions => Some(dimensions),
+    }
+}
+
+/// AVIF files are ISO-BMFF containers whose leading `ftyp` box lists `avif`
+/// or `avis` as either the major or one of the compatible brands.
+fn is_avif(header: &[u8]) -> bool {
+    if header.len() < 12 || &header[4..8] != b"ftyp" {
+        return false;
+    }
+
+    let box_size = read_u32_be(header, 0).unwrap_or_default() as usize;
+    let box_end = box_size.min(header.len());
+    if box_end < 12 {
+        return false;
+    }
+
+    let is_avif_brand = |brand: &[u8]| brand == b"avif" || brand == b"avis";
+
+    // Major brand, then (after the 4 byte minor version) the compatible brands.
+    is_avif_brand(&header[8..12])
+        || header
+            .get(16..box_end)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .any(is_avif_brand)
+}
+
+/// MP4 shares the ISO-BMFF `ftyp` box with AVIF, so this has to run after
+/// `is_avif`. Only brands of plain MP4 video files are accepted.
+fn is_mp4(header: &[u8]) -> bool {
+    const MP4_BRANDS: [&[u8]; 10] = [
+        b"isom", b"iso2", b"iso4", b"iso5", b"iso6", b"mp41", b"mp42", b"avc1", b"dash", b"M4V ",
+    ];
+
+    header.len() >= 12 && &header[4..8] == b"ftyp" && MP4_BRANDS.contains(&&header[8..12])
+}
+
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    // The IHDR chunk always comes first, right after the 8 byte signature.
+    if data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+
+    Some((read_u32_be(data, 16)?, read_u32_be(data, 20)?))
+}
+
+fn gif_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    Some((read_u16_le(data, 6)? as u32, read_u16_le(data, 8)? as u32))
+}
+
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+
+    loop {
+        // Markers may be padded with any number of 0xFF fill bytes.
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        while *data.get(pos)? == 0xFF {
+            pos += 1;
+        }
+        let marker = *data.get(pos)?;
+        pos += 1;
+
+        match marker {
+            // Standalone markers without a length field.
+            0x01 | 0xD0..=0xD7 => continue,
+            // Start of scan or end of image before any frame header.
+            0xD9 | 0xDA => return None,
+            // SOF0..SOF15, except DHT, JPG and DAC which share the range.
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = read_u16_be(data, pos + 3)? as u32;
+                let width = read_u16_be(data, pos + 5)? as u32;
+                return Some((width, height));
+            }
+            _ => {
+                let length = read_u16_be(data, pos)? as usize;
+                pos += length;
+            }
+        }
+    }
+}
+
+fn webp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    match data.get(12..16)? {
+        b"VP8X" => Some((read_u24_le(data, 24)? + 1, read_u24_le(data, 27)? + 1)),
+        b"VP8L" => {
+            if *data.get(20)? != 0x2F {
+                return None;
+            }
+            let bits = read_u32_le(data, 21)?;
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8 " => {
+            if data.get(23..26)? != [0x9D, 0x01, 0x2A] {
+                return None;
+            }
+            Some((
+                (read_u16_le(data, 26)? & 0x3FFF) as u32,
+                (read_u16_le(data, 28)? & 0x3FFF) as u32,
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn avif_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    // Rather than walking meta/iprp/ipco, look for the first image spatial
+    // extents property, which belongs to the primary item in practice.
+    let tag = data.windows(4).position(|window| window == b"ispe")?;
+    Some((read_u32_be(data, tag + 8)?, read_u32_be(data, tag + 12)?))
+}
+
+fn read_u16_be(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u16_le(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u24_le(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+fn read_u32_be(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u32_le(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Counts the frames of an image without decoding them: GIF image
+/// descriptors, the APNG `acTL` chunk or animated WebP `ANMF` chunks. Other
+/// image types always have a single frame.
+pub fn frame_count<R: Read + Seek>(mime_type: &mime::Mime, reader: R) -> Option<u32> {
+    match mime_type.essence_str() {
+        "image/gif" => gif_frame_count(reader),
+        "image/png" => png_frame_count(reader),
+        "image/webp" => webp_frame_count(reader),
+        _ => Some(1),
+    }
+}
+
+fn gif_frame_count<R: Read + Seek>(reader: R) -> Option<u32> {
+    let mut reader = BufReader::new(reader);
+    let mut header = [0u8; 13];
+    reader.read_exact(&mut header).ok()?;
+
+    // Skip the global colour table, if there is one.
+    if header[10] & 0x80 != 0 {
+        let table_len = 3 * (1 << ((header[10] & 0x07) + 1));
+        reader.seek_relative(table_len).ok()?;
+    }
+
+    // Many GIFs lack the trailer or are cut off, and viewers show the frames
+    // they have, so the end of the file ends the count as well.
+    let mut frames = 0;
+    while let Some(block) = read_u8(&mut reader) {
+        let complete = match block {
+            // Extension: label, then data sub-blocks.
+            0x21 => read_u8(&mut reader).and_then(|_| skip_gif_sub_blocks(&mut reader)),
+            // Image descriptor: one frame.
+            0x2C => {
+                frames += 1;
+                skip_gif_image(&mut reader)
+            }
+            0x3B => break,
+            _ => return None,
+        };
+        if complete.is_none() {
+            break;
+        }
+    }
+
+    (frames > 0).then_some(frames)
+}
+
+/// Skips the rest of an image after its `0x2C` separator.
+fn skip_gif_image<R: Read + Seek>(reader: &mut BufReader<R>) -> Option<()> {
+    let mut descriptor = [0u8; 9];
+    reader.read_exact(&mut descriptor).ok()?;
+    if descriptor[8] & 0x80 != 0 {
+        let table_len = 3 * (1 << ((descriptor[8] & 0x07) + 1));
+        reader.seek_relative(table_len).ok()?;
+    }
+    read_u8(reader)?; // LZW minimum code size
+    skip_gif_sub_blocks(reader)
+}
+
+fn skip_gif_sub_blocks<R: Read + Seek>(reader: &mut BufReader<R>) -> Option<()> {
+    loop {
+        match read_u8(reader)? {
+            0 => return Some(()),
+            len => reader.seek_relative(len as i64).ok()?,
+        }
+    }
+}
+
+fn png_frame_count<R: Read + Seek>(mut reader: R) -> Option<u32> {
+    reader.seek(SeekFrom::Start(8)).ok()?;
+
+    // acTL has to come before the first IDAT, so stop looking there.
+    loop {
+        let mut chunk = [0u8; 8];
+        reader.read_exact(&mut chunk).ok()?;
+        let length = read_u32_be(&chunk, 0)?;
+        match &chunk[4..8] {
+            b"acTL" => {
+                let mut frames = [0u8; 4];
+                reader.read_exact(&mut frames).ok()?;
+                return Some(u32::from_be_bytes(frames));
+            }
+            b"IDAT" | b"IEND" => return Some(1),
+            // Chunk data plus its CRC.
+            _ => reader.seek(SeekFrom::Current(length as i64 + 4)).ok()?,
+        };
+    }
+}
+
+fn webp_frame_count<R: Read + Seek>(mut reader: R) -> Option<u32> {
+    reader.seek(SeekFrom::Start(12)).ok()?;
+
+    let mut frames = 0;
+    loop {
+        let mut chunk = [0u8; 8];
+        if reader.read_exact(&mut chunk).is_err() {
+            // Still images have no ANMF chunks at all.
+            return Some(frames.max(1));
+        }
+        if &chunk[0..4] == b"ANMF" {
+            frames += 1;
+        }
+        // Chunks are padded to an even length.
+        let length = read_u32_le(&chunk, 4)? as i64;
+        reader.seek(SeekFrom::Current(length + (length & 1))).ok()?;
+    }
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Option<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte).ok()?;
+    Some(byte[0])
+}
@@ -1,2 +1,15 @@
+pub mod alias;
+pub mod auth;
+pub mod config;
 pub mod db;
+pub mod implication;
+pub mod media;
+pub mod post;
+pub mod search;
 pub mod tag;
+pub mod tag_edit;
+pub mod thumbnail;
+pub mod upload;
+pub mod user;
+pub mod video;
+pub mod wiki;
@@ -0,0 +1,220 @@
+use anyhow::anyhow;
+use surrealdb::{sql::parse, Connection, Surreal};
+
+use crate::models::post::{hex_hash, Post};
+use crate::models::tag::{Tag, TagAction};
+use crate::server_only::db::get_next_id;
+use crate::server_only::implication::with_implied_tags;
+use crate::server_only::search::{compile_search, parse_post_search, CompiledSearch};
+use crate::server_only::tag::{get_tags_by_ids, post_history_entries, ADD_POST_HISTORY_ENTRIES};
+
+pub async fn define_post_table<T: Connection>(db: &Surreal<T>) -> anyhow::Result<()> {
+    let schema = r#"
+        DEFINE TABLE post SCHEMAFULL;
+
+        DEFINE FIELD custom_id ON TABLE post TYPE number;
+        DEFINE FIELD image_height ON TABLE post TYPE number;
+        DEFINE FIELD image_width ON TABLE post TYPE number;
+        DEFINE FIELD mime_type ON TABLE post TYPE string;
+        DEFINE FIELD post_type ON TABLE post TYPE string;
+        DEFINE FIELD safety ON TABLE post TYPE string;
+        DEFINE FIELD sha256_hash ON TABLE post TYPE string;
+        DEFINE FIELD md5_hash ON TABLE post TYPE string;
+        DEFINE FIELD uploader_id ON TABLE post TYPE number;
+        DEFINE FIELD tags ON TABLE post TYPE array<number>;
+        DEFINE FIELD duration ON TABLE post TYPE option<number>;
+        DEFINE FIELD has_audio ON TABLE post TYPE bool DEFAULT false;
+        DEFINE FIELD frame_count ON TABLE post TYPE option<number>;
+        DEFINE FIELD animated ON TABLE post TYPE bool DEFAULT false;
+        DEFINE FIELD file_size ON TABLE post TYPE number DEFAULT 0;
+        DEFINE FIELD uploaded_at ON TABLE post TYPE number DEFAULT 0;
+        DEFINE FIELD score ON TABLE post TYPE number DEFAULT 0;
+
+        DEFINE INDEX custom_id ON TABLE post FIELDS custom_id UNIQUE;
+        DEFINE INDEX sha256_unique ON TABLE post FIELDS sha256_hash UNIQUE;
+        DEFINE INDEX animated ON TABLE post FIELDS animated;
+
+        -- Keeps `use_count` of tags in step with the posts using them. Events
+        -- run in the transaction of the write that triggered them.
+        DEFINE EVENT tag_use_count ON TABLE post WHEN $before.tags != $after.tags THEN {
+            LET $old = $before.tags ?? [];
+            LET $new = $after.tags ?? [];
+            UPDATE tag SET use_count -= 1 WHERE custom_id IN array::complement($old, $new);
+            UPDATE tag SET use_count += 1 WHERE custom_id IN array::complement($new, $old);
+        };
+        "#;
+
+    db.query(parse(schema)?).await?;
+
+    Ok(())
+}
+
+/// The most posts a page can have, whatever the client asks for.
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Lists posts newest first. `search` is a query in the post search language,
+/// see `models::search`; a malformed query fails with a `SearchError`.
+pub async fn get_paginated_posts<C: Connection>(
+    db: &Surreal<C>,
+    page: u32,
+    per_page: u32,
+    search: Option<String>,
+) -> Result<Vec<Post>, anyhow::Error> {
+    // Both come from the client, and pages count from 1.
+    let per_page = per_page.min(MAX_PER_PAGE);
+    let offset = page.saturating_sub(1).saturating_mul(per_page);
+    let mut query = "SELECT * FROM post".to_string();
+
+    let compiled = match search {
+        Some(search) => compile_search(db, &parse_post_search(db, &search).await?).await?,
+        None => CompiledSearch::default(),
+    };
+    if let Some(condition) = &compiled.condition {
+        query.push_str(&format!(" WHERE {}", condition));
+    }
+
+    query.push_str(&format!(
+        " ORDER BY {} LIMIT $limit START $offset",
+        compiled.order.order_by()
+    ));
+
+    let mut request = db
+        .query(&query)
+        .bind(("limit", per_page))
+        .bind(("offset", offset));
+    for (name, value) in compiled.params {
+        request = request.bind((name, value));
+    }
+
+    let posts: Vec<Post> = request.await?.take(0)?;
+
+    Ok(posts)
+}
+
+pub async fn get_post_by_id<C: Connection>(
+    db: &Surreal<C>,
+    custom_id: u64,
+) -> Result<Option<Post>, anyhow::Error> {
+    let result: Option<Post> = db
+        .query("SELECT * FROM post WHERE custom_id = $custom_id")
+        .bind(("custom_id", custom_id))
+        .await?
+        .take(0)?;
+
+    Ok(result)
+}
+
+pub async fn get_post_by_hash<C: Connection>(
+    db: &Surreal<C>,
+    sha256_hash: &[u8; 32],
+) -> Result<Option<Post>, anyhow::Error> {
+    let result: Option<Post> = db
+        .query("SELECT * FROM post WHERE sha256_hash = $hash")
+        .bind(("hash", hex_hash::encode(sha256_hash)))
+        .await?
+        .take(0)?;
+
+    Ok(result)
+}
+
+/// Stores `post` with its tags and every tag they imply. The history records
+/// each tag as added by the uploader.
+pub async fn add_new_post<C: Connection>(
+    db: &Surreal<C>,
+    post: &Post,
+) -> Result<u64, anyhow::Error> {
+    define_post_table(db).await?;
+
+    let post = Post {
+        custom_id: get_next_id(db, "post_id_counter").await?,
+        tags: with_implied_tags(db, &post.tags).await?,
+        ..post.clone()
+    };
+    let tags = get_tags_by_ids(db, post.tags.clone()).await?;
+    let entries =
+        post_history_entries(db, post.custom_id, &tags, TagAction::Tag, post.uploader_id).await?;
+
+    let created: Option<u64> = db
+        .query(format!(
+            r#"
+            BEGIN TRANSACTION;
+            CREATE post CONTENT $post RETURN VALUE custom_id;
+            {}
+            COMMIT TRANSACTION;
+            "#,
+            ADD_POST_HISTORY_ENTRIES
+        ))
+        .bind(("post", post))
+        .bind(("post_entries", entries))
+        .await?
+        .take(0)?;
+
+    match created {
+        Some(custom_id) => Ok(custom_id),
+        None => Err(anyhow!("failed to create post")),
+    }
+}
+
+/// Removes the post `custom_id` together with the history of its tags, to
+/// undo `add_new_post` when the uploaded file could not be stored.
+pub async fn delete_post<C: Connection>(db: &Surreal<C>, custom_id: u64) -> anyhow::Result<()> {
+    db.query(
+        r#"
+        BEGIN TRANSACTION;
+        DELETE post WHERE custom_id = $custom_id;
+        DELETE tag_history WHERE post_id = $custom_id;
+        COMMIT TRANSACTION;
+        "#,
+    )
+    .bind(("custom_id", custom_id))
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+/// Replaces the tags of a post on behalf of `user_id`, adding every tag they
+/// imply. Each tag the post gains or loses gets a history entry. Returns the
+/// tags the post ends up with, or `None` if there is no such post.
+pub async fn set_post_tags<C: Connection>(
+    db: &Surreal<C>,
+    custom_id: u64,
+    tags: Vec<u64>,
+    user_id: u64,
+) -> Result<Option<Vec<u64>>, anyhow::Error> {
+    let tags = with_implied_tags(db, &tags).await?;
+    let Some(post) = get_post_by_id(db, custom_id).await? else {
+        return Ok(None);
+    };
+
+    let changed: Vec<u64> = tags
+        .iter()
+        .filter(|id| !post.tags.contains(id))
+        .chain(post.tags.iter().filter(|id| !tags.contains(id)))
+        .copied()
+        .collect();
+    let (gained, lost): (Vec<Tag>, Vec<Tag>) = get_tags_by_ids(db, changed)
+        .await?
+        .into_iter()
+        .partition(|tag| tags.contains(&tag.custom_id));
+    let mut entries = post_history_entries(db, custom_id, &gained, TagAction::Tag, user_id).await?;
+    entries.extend(post_history_entries(db, custom_id, &lost, TagAction::Untag, user_id).await?);
+
+    let updated: Option<Vec<u64>> = db
+        .query(format!(
+            r#"
+            BEGIN TRANSACTION;
+            UPDATE post SET tags = $tags WHERE custom_id = $custom_id RETURN VALUE tags;
+            {}
+            COMMIT TRANSACTION;
+            "#,
+            ADD_POST_HISTORY_ENTRIES
+        ))
+        .bind(("tags", tags))
+        .bind(("custom_id", custom_id))
+        .bind(("post_entries", entries))
+        .await?
+        .take(0)?;
+
+    Ok(updated)
+}
@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use surrealdb::{Connection, Surreal};
+
+use crate::models::metatag::{Filter, Order, Range};
+use crate::models::search::{
+    parse_search_with_tags, unusual_terms, Clause, PostSearch, SearchError, SearchErrorKind, Term,
+};
+use crate::models::user::ANONYMOUS;
+use crate::server_only::alias::resolve_tags_by_names;
+use crate::server_only::tag::get_tags_by_names;
+use crate::server_only::user::get_user_by_name;
+
+/// A value bound to one of the `$p0..$pn` parameters of a compiled search.
+#[derive(Clone, PartialEq, Debug, Serialize)]
+#[serde(untagged)]
+pub enum SearchParam {
+    Number(u64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+/// The `WHERE` condition and order of a search over the `post` table. User
+/// input only ever ends up in `params`, never in `condition` itself.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CompiledSearch {
+    pub condition: Option<String>,
+    pub params: Vec<(String, SearchParam)>,
+    pub order: Order,
+}
+
+impl CompiledSearch {
+    fn bind(&mut self, value: SearchParam) -> String {
+        let name = format!("p{}", self.params.len());
+        self.params.push((name.clone(), value));
+        format!("${}", name)
+    }
+}
+
+/// Parses `query`, treating terms that name an existing tag as that tag even
+/// where the syntax alone would make them a metatag or an error.
+pub async fn parse_post_search<C: Connection>(
+    db: &Surreal<C>,
+    query: &str,
+) -> anyhow::Result<PostSearch> {
+    let terms = unusual_terms(query);
+    let existing_tags: HashSet<String> = if terms.is_empty() {
+        HashSet::new()
+    } else {
+        get_tags_by_names(db, terms)
+            .await?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect()
+    };
+
+    Ok(parse_search_with_tags(query, &existing_tags)?)
+}
+
+/// Resolves the tag names in `search` and compiles it into a condition over
+/// the tag ids stored on posts. Unknown tags and users are reported as a
+/// `SearchError` inside the returned error.
+pub async fn compile_search<C: Connection>(
+    db: &Surreal<C>,
+    search: &PostSearch,
+) -> anyhow::Result<CompiledSearch> {
+    let mut names: Vec<String> = search
+        .clauses()
+        .filter_map(|clause| match &clause.term {
+            Term::Tag(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+
+    // Aliases match the posts of the tag they stand for.
+    let tag_ids: HashMap<String, u64> = if names.is_empty() {
+        HashMap::new()
+    } else {
+        resolve_tags_by_names(db, names)
+            .await?
+            .into_iter()
+            .map(|(name, tag)| (name, tag.custom_id))
+            .collect()
+    };
+
+    // Report the leftmost unknown tag rather than the first one per group.
+    let mut clauses: Vec<&Clause> = search.clauses().collect();
+    clauses.sort_by_key(|clause| clause.start);
+    for clause in clauses {
+        if let Term::Tag(name) = &clause.term {
+            if !tag_ids.contains_key(name) {
+                return Err(SearchError::new(
+                    SearchErrorKind::UnknownTag(name.clone()),
+                    clause.start,
+                    clause.end,
+                )
+                .into());
+            }
+        }
+    }
+
+    let mut user_ids = HashMap::from([(ANONYMOUS.to_string(), 0)]);
+    for clause in search.clauses() {
+        if let Term::Metatag(Filter::Uploader(name)) = &clause.term {
+            if let Some(user) = get_user_by_name(db, name).await? {
+                user_ids.insert(name.clone(), user.custom_id);
+            }
+        }
+    }
+
+    let mut compiled = CompiledSearch {
+        order: search.order,
+        ..CompiledSearch::default()
+    };
+    let mut conditions = vec![];
+
+    for clause in &search.all {
+        conditions.push(clause_condition(
+            clause,
+            &tag_ids,
+            &user_ids,
+            &mut compiled,
+        )?);
+    }
+
+    if !search.any.is_empty() {
+        let alternatives = search
+            .any
+            .iter()
+            .map(|clause| clause_condition(clause, &tag_ids, &user_ids, &mut compiled))
+            .collect::<Result<Vec<String>, SearchError>>()?;
+        conditions.push(format!("({})", alternatives.join(" OR ")));
+    }
+
+    if !conditions.is_empty() {
+        compiled.condition = Some(conditions.join(" AND "));
+    }
+
+    Ok(compiled)
+}
+
+fn clause_condition(
+    clause: &Clause,
+    tag_ids: &HashMap<String, u64>,
+    user_ids: &HashMap<String, u64>,
+    compiled: &mut CompiledSearch,
+) -> Result<String, SearchError> {
+    let condition = match &clause.term {
+        Term::Tag(name) => {
+            let param = compiled.bind(SearchParam::Number(tag_ids[name]));
+            format!("tags CONTAINS {}", param)
+        }
+        Term::Metatag(Filter::Range { field, range }) => range_condition(field, range, compiled),
+        Term::Metatag(Filter::Equals { field, value }) => {
+            format!(
+                "{} = {}",
+                field,
+                compiled.bind(SearchParam::Text(value.clone()))
+            )
+        }
+        Term::Metatag(Filter::Flag { field, value }) => {
+            format!("{} = {}", field, compiled.bind(SearchParam::Bool(*value)))
+        }
+        Term::Metatag(Filter::Uploader(name)) => {
+            // Posts uploaded without an account belong to `anonymous`.
+            let Some(&uploader_id) = user_ids.get(name) else {
+                return Err(SearchError::new(
+                    SearchErrorKind::UnknownUser(name.clone()),
+                    clause.start,
+                    clause.end,
+                ));
+            };
+            format!(
+                "uploader_id = {}",
+                compiled.bind(SearchParam::Number(uploader_id))
+            )
+        }
+        // The parser takes these out of the clause list.
+        Term::Metatag(Filter::Order(_)) => unreachable!("order is not a filter"),
+    };
+
+    Ok(match clause.negated {
+        false => condition,
+        true => format!("!({})", condition),
+    })
+}
+
+fn range_condition(field: &str, range: &Range, compiled: &mut CompiledSearch) -> String {
+    let mut bounds = vec![];
+
+    if let Some(min) = range.min {
+        let operator = if min.inclusive { ">=" } else { ">" };
+        let param = compiled.bind(SearchParam::Float(min.value));
+        bounds.push(format!("{} {} {}", field, operator, param));
+    }
+    if let Some(max) = range.max {
+        let operator = if max.inclusive { "<=" } else { "<" };
+        let param = compiled.bind(SearchParam::Float(max.value));
+        bounds.push(format!("{} {} {}", field, operator, param));
+    }
+
+    format!("({})", bounds.join(" AND "))
+}
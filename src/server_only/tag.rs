@@ -1,17 +1,28 @@
-use anyhow::{anyhow, Ok};
-use regex::Regex;
+use anyhow::Ok;
 use surrealdb::{sql::parse, Connection, Surreal};
 
-use crate::models::tag::Tag;
+use crate::models::post::Post;
+use crate::models::tag::{
+    normalize_tag_name, ListedTag, Tag, TagAction, TagCategory, TagChangeError, TagDeleteError,
+    TagHistoryEntry,
+};
+use crate::server_only::alias::{canonical_tag, resolve_tag_by_name, AliasError};
+use crate::server_only::db::get_next_id;
+use crate::server_only::implication::with_implied_tags;
 
-fn is_snake_case(s: &str) -> bool {
-    let re = Regex::new(r"^[a-z0-9(){}:'_-]+([a-z0-9(){}:'_-]+)*$").unwrap();
-    re.is_match(s)
-}
+/// Builds the `WHERE` clause for a tag name search where `*` matches
+/// anything. The search text is only ever bound as the `$p0..$pn` parameters
+/// returned alongside the clause, so it cannot change the query itself.
+pub fn build_search_query(search: String) -> (String, Vec<(String, String)>) {
+    let mut params: Vec<(String, String)> = Vec::new();
+    let mut bind = |value: &str| {
+        let name = format!("p{}", params.len());
+        params.push((name.clone(), value.to_string()));
+        format!("${}", name)
+    };
 
-pub fn build_search_query(search: String) -> String {
     if search.is_empty() {
-        return String::new();
+        return (String::new(), vec![]);
     }
 
     let parts: Vec<&str> = search.split('*').collect();
@@ -19,45 +30,36 @@ pub fn build_search_query(search: String) -> String {
 
     if parts_len == 1 {
         // No wildcards, exact match
-        return format!("WHERE name = '{}'", escape_string(search));
+        let clause = format!("WHERE name = {}", bind(&search));
+        return (clause, params);
     }
 
     let mut conditions = Vec::new();
 
     if !parts[0].is_empty() {
-        conditions.push(format!(
-            "string::starts_with(name, '{}')",
-            escape_string(parts[0].into())
-        ));
+        conditions.push(format!("string::starts_with(name, {})", bind(parts[0])));
     }
 
     if !parts[parts_len - 1].is_empty() {
         conditions.push(format!(
-            "string::ends_with(name, '{}')",
-            escape_string(parts[parts_len - 1].into())
+            "string::ends_with(name, {})",
+            bind(parts[parts_len - 1])
         ));
     }
 
     for &part in &parts[1..parts_len - 1] {
         if !part.is_empty() {
-            conditions.push(format!(
-                "string::contains(name, '{}')",
-                escape_string(part.into())
-            ));
+            conditions.push(format!("string::contains(name, {})", bind(part)));
         }
     }
 
     if conditions.is_empty() {
-        String::new()
+        (String::new(), vec![])
     } else {
-        format!("WHERE {}", conditions.join(" AND "))
+        (format!("WHERE {}", conditions.join(" AND ")), params)
     }
 }
 
-fn escape_string(s: String) -> String {
-    s.replace('\'', "''")
-}
-
 pub async fn get_paginated_tags<C: surrealdb::Connection>(
     db: &Surreal<C>,
     page: u32,
@@ -66,70 +68,115 @@ pub async fn get_paginated_tags<C: surrealdb::Connection>(
 ) -> Result<Vec<Tag>, anyhow::Error> {
     let offset = (page - 1) * per_page;
     let mut query = "SELECT * FROM tag".to_string();
+    let mut params = vec![];
 
     if let Some(search_term) = search {
-        let where_clause = build_search_query(search_term);
+        // SurrealDB strings cannot hold NUL, and no tag name contains one.
+        if search_term.contains('\0') {
+            return Ok(vec![]);
+        }
+
+        let (where_clause, search_params) = build_search_query(search_term);
         if !where_clause.is_empty() {
             query.push_str(&format!(" {}", where_clause));
+            params = search_params;
         }
     }
 
     query.push_str(" ORDER BY use_count DESC LIMIT $limit START $offset");
 
-    let tags: Vec<Tag> = db
+    let mut request = db
         .query(&query)
         .bind(("limit", per_page))
-        .bind(("offset", offset))
-        .await?
-        .take(0)?;
+        .bind(("offset", offset));
+    for param in params {
+        request = request.bind(param);
+    }
+
+    let tags: Vec<Tag> = request.await?.take(0)?;
 
     Ok(tags)
 }
 
-async fn get_next_id<C: surrealdb::Connection>(db: &Surreal<C>) -> Result<u64, anyhow::Error> {
-    #[derive(serde::Deserialize)]
-    struct IdCounter {
-        last_id: i64,
+/// The `limit` tags whose name starts with `prefix`, most used first. Aliases
+/// rank by the use count of the tag they stand for.
+pub async fn autocomplete_tags<C: Connection>(
+    db: &Surreal<C>,
+    prefix: String,
+    limit: u32,
+) -> anyhow::Result<Vec<ListedTag>> {
+    let prefix = prefix.trim().to_lowercase();
+    // A `*` would turn the prefix into a wildcard search.
+    if prefix.is_empty() || prefix.contains(['*', '\0']) {
+        return Ok(vec![]);
     }
 
-    let result: Option<IdCounter> = db
-        .query("UPDATE id_counter SET last_id += 1 RETURN last_id")
-        .await?
-        .take(0)?;
+    let (where_clause, params) = build_search_query(format!("{}*", prefix));
+    let query = format!(
+        r#"
+        SELECT *, IF is_alias != NONE THEN
+            (SELECT VALUE use_count FROM tag WHERE custom_id = $parent.is_alias)[0]
+        ELSE use_count END AS rank
+        FROM tag {} ORDER BY rank DESC, name ASC LIMIT $limit
+        "#,
+        where_clause
+    );
 
-    match result {
-        Some(counter) => Ok(counter.last_id as u64),
-        None => {
-            // If no record exists, create one starting from 1
-            let created: Option<IdCounter> = db
-                .query("CREATE id_counter SET last_id = 1 RETURN last_id")
-                .await?
-                .take(0)?;
-            match created {
-                Some(counter) => Ok(counter.last_id as u64),
-                None => Err(anyhow!("Failed to initialize id_counter")),
-            }
-        }
+    let mut request = db.query(&query).bind(("limit", limit));
+    for param in params {
+        request = request.bind(param);
     }
+
+    let tags: Vec<Tag> = request.await?.take(0)?;
+
+    with_alias_targets(db, tags).await
 }
 
 pub async fn define_tag_table<T: Connection>(db: &surrealdb::Surreal<T>) -> anyhow::Result<()> {
-    let schema = r#"
-        DEFINE TABLE tag SCHEMAFULL;
+    let categories = TagCategory::ALL
+        .iter()
+        .map(|category| (*category as u8).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let schema = format!(
+        r#"
+        -- Runs on every start and before every new tag, so each definition
+        -- either leaves an existing one alone or, where it changed since the
+        -- table was first created, OVERWRITEs it.
+        DEFINE TABLE IF NOT EXISTS tag SCHEMAFULL;
         
-        DEFINE FIELD custom_id ON TABLE tag TYPE number;
-        DEFINE FIELD name ON TABLE tag TYPE string;
-        DEFINE FIELD description ON TABLE tag TYPE string;
-        DEFINE FIELD is_alias ON TABLE tag TYPE option<number>;
-        DEFINE FIELD category ON TABLE tag TYPE number;
-        DEFINE FIELD implications ON TABLE tag TYPE array;
-        DEFINE FIELD use_count ON TABLE tag TYPE number;
+        DEFINE FIELD IF NOT EXISTS custom_id ON TABLE tag TYPE number;
+        DEFINE FIELD IF NOT EXISTS name ON TABLE tag TYPE string;
+        DEFINE FIELD IF NOT EXISTS description ON TABLE tag TYPE string;
+        DEFINE FIELD IF NOT EXISTS is_alias ON TABLE tag TYPE option<number>;
+        DEFINE FIELD OVERWRITE category ON TABLE tag TYPE number ASSERT $value IN [{categories}];
+        DEFINE FIELD IF NOT EXISTS implications ON TABLE tag TYPE array;
+        DEFINE FIELD IF NOT EXISTS use_count ON TABLE tag TYPE number;
         
-        DEFINE INDEX custom_id ON TABLE tag FIELDS custom_id UNIQUE;
-        DEFINE INDEX name_unique ON TABLE tag FIELDS name UNIQUE;
-        "#;
+        DEFINE INDEX IF NOT EXISTS custom_id ON TABLE tag FIELDS custom_id UNIQUE;
+        DEFINE INDEX IF NOT EXISTS name_unique ON TABLE tag FIELDS name UNIQUE;
+
+        DEFINE TABLE IF NOT EXISTS tag_history SCHEMAFULL;
+
+        DEFINE FIELD IF NOT EXISTS custom_id ON TABLE tag_history TYPE number;
+        DEFINE FIELD IF NOT EXISTS tag_id ON TABLE tag_history TYPE number;
+        DEFINE FIELD OVERWRITE action ON TABLE tag_history TYPE string ASSERT $value IN [
+            "Rename", "Merge", "Create", "Edit", "Alias", "Implications", "Delete", "Tag", "Untag"
+        ];
+        DEFINE FIELD IF NOT EXISTS old_name ON TABLE tag_history TYPE string;
+        DEFINE FIELD IF NOT EXISTS new_name ON TABLE tag_history TYPE string;
+        DEFINE FIELD IF NOT EXISTS target_id ON TABLE tag_history TYPE option<number>;
+        DEFINE FIELD IF NOT EXISTS post_id ON TABLE tag_history TYPE option<number>;
+        DEFINE FIELD IF NOT EXISTS changed_at ON TABLE tag_history TYPE number;
+        DEFINE FIELD IF NOT EXISTS user_id ON TABLE tag_history TYPE number DEFAULT 0;
+
+        DEFINE INDEX IF NOT EXISTS history_id ON TABLE tag_history FIELDS custom_id UNIQUE;
+        DEFINE INDEX IF NOT EXISTS tag_id ON TABLE tag_history FIELDS tag_id;
+        "#
+    );
 
-    db.query(parse(schema)?).await?;
+    db.query(parse(&schema)?).await?.check()?;
 
     Ok(())
 }
@@ -160,26 +207,421 @@ pub async fn get_tag_by_id<C: surrealdb::Connection>(
     Ok(result)
 }
 
+pub async fn get_tags_by_ids<C: surrealdb::Connection>(
+    db: &Surreal<C>,
+    custom_ids: Vec<u64>,
+) -> Result<Vec<Tag>, anyhow::Error> {
+    let tags: Vec<Tag> = db
+        .query("SELECT * FROM tag WHERE custom_id IN $custom_ids ORDER BY name")
+        .bind(("custom_ids", custom_ids))
+        .await?
+        .take(0)?;
+
+    Ok(tags)
+}
+
+pub async fn get_tags_by_names<C: surrealdb::Connection>(
+    db: &Surreal<C>,
+    names: Vec<String>,
+) -> Result<Vec<Tag>, anyhow::Error> {
+    let tags: Vec<Tag> = db
+        .query("SELECT * FROM tag WHERE name IN $names ORDER BY name")
+        .bind(("names", names))
+        .await?
+        .take(0)?;
+
+    Ok(tags)
+}
+
+/// Pairs each tag with the name of the tag it is an alias of.
+pub async fn with_alias_targets<C: surrealdb::Connection>(
+    db: &Surreal<C>,
+    tags: Vec<Tag>,
+) -> Result<Vec<ListedTag>, anyhow::Error> {
+    let target_ids: Vec<u64> = tags.iter().filter_map(|tag| tag.is_alias).collect();
+    let targets = if target_ids.is_empty() {
+        vec![]
+    } else {
+        get_tags_by_ids(db, target_ids).await?
+    };
+
+    Ok(tags
+        .into_iter()
+        .map(|tag| ListedTag {
+            alias_of: tag.is_alias.and_then(|target| {
+                targets
+                    .iter()
+                    .find(|candidate| candidate.custom_id == target)
+                    .map(|candidate| candidate.name.clone())
+            }),
+            tag,
+        })
+        .collect())
+}
+
+/// Creates `tag` on behalf of `user_id` and returns its id.
 pub async fn add_new_tag<C: surrealdb::Connection>(
     db: &Surreal<C>,
     tag: &Tag,
+    user_id: u64,
 ) -> Result<u64, anyhow::Error> {
-    if !is_snake_case(&tag.name) {
-        return Err(anyhow!("Tag name({}) must be in snake_case", &tag.name));
-    }
+    let name = normalize_tag_name(&tag.name)?;
 
     define_tag_table(db).await?;
 
-    let created: Option<Tag> = db
-        .create("tag")
-        .content(Tag {
-            custom_id: get_next_id(db).await?,
-            ..tag.clone()
-        })
-        .await?;
+    // Aliases always point at canonical tags.
+    let is_alias = match tag.is_alias {
+        Some(target) => {
+            let target = get_tag_by_id(db, target)
+                .await?
+                .ok_or(AliasError::UnknownTag(target))?;
+            Some(canonical_tag(db, target).await?.custom_id)
+        }
+        None => None,
+    };
+
+    let tag = Tag {
+        custom_id: get_next_id(db, "id_counter").await?,
+        name,
+        is_alias,
+        ..tag.clone()
+    };
+    let entry = history_entry(db, &tag, TagAction::Create, user_id).await?;
+
+    db.query(format!(
+        "BEGIN TRANSACTION; CREATE tag CONTENT $tag; {} COMMIT TRANSACTION;",
+        ADD_HISTORY_ENTRY
+    ))
+    .bind(("tag", tag.clone()))
+    .bind(("entry", entry))
+    .await?
+    .check()?;
+
+    Ok(tag.custom_id)
+}
+
+/// Rebuilds the `use_count` of every tag from the posts, for when the
+/// counts drifted, e.g. after an import or a crash.
+pub async fn recount_tag_uses<C: Connection>(db: &Surreal<C>) -> anyhow::Result<()> {
+    db.query(
+        r#"
+        UPDATE tag SET use_count = array::len(
+            (SELECT VALUE custom_id FROM post WHERE tags CONTAINS $parent.custom_id)
+        );
+        "#,
+    )
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+/// The current unix time in seconds.
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Stores `$entry` in the tag history. Runs inside a transaction.
+pub const ADD_HISTORY_ENTRY: &str = "CREATE tag_history CONTENT $entry;";
 
-    match created {
-        Some(created_tag) => Ok(created_tag.custom_id),
-        None => Err(anyhow!("failed to create tag")),
+/// A history entry for `action` on `tag` by `user_id`, with the name of the
+/// tag as it is now. Entries naming a target or post fill those in.
+pub async fn history_entry<C: Connection>(
+    db: &Surreal<C>,
+    tag: &Tag,
+    action: TagAction,
+    user_id: u64,
+) -> anyhow::Result<TagHistoryEntry> {
+    Ok(TagHistoryEntry {
+        custom_id: get_next_id(db, "tag_history_counter").await?,
+        tag_id: tag.custom_id,
+        action,
+        old_name: tag.name.clone(),
+        new_name: tag.name.clone(),
+        target_id: None,
+        post_id: None,
+        changed_at: now(),
+        user_id,
+    })
+}
+
+/// `Tag` or `Untag` entries for each of `tags` being added to or removed
+/// from the post `post_id` by `user_id`.
+pub async fn post_history_entries<C: Connection>(
+    db: &Surreal<C>,
+    post_id: u64,
+    tags: &[Tag],
+    action: TagAction,
+    user_id: u64,
+) -> anyhow::Result<Vec<TagHistoryEntry>> {
+    let mut entries = Vec::with_capacity(tags.len());
+    for tag in tags {
+        entries.push(TagHistoryEntry {
+            post_id: Some(post_id),
+            ..history_entry(db, tag, action, user_id).await?
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Stores the `$post_entries` from `post_history_entries` or
+/// `retag_history_entries`. Runs inside a transaction.
+pub const ADD_POST_HISTORY_ENTRIES: &str = "INSERT INTO tag_history $post_entries;";
+
+/// Entries for every post tagged `tag` losing it, if `untag` is set, and
+/// gaining whichever of `added` it does not have yet, by `user_id`. For
+/// changes that retag all posts of a tag at once, like merges.
+pub async fn retag_history_entries<C: Connection>(
+    db: &Surreal<C>,
+    tag: &Tag,
+    untag: bool,
+    added: &[u64],
+    user_id: u64,
+) -> anyhow::Result<Vec<TagHistoryEntry>> {
+    let added = get_tags_by_ids(db, added.to_vec()).await?;
+    let posts: Vec<Post> = db
+        .query("SELECT * FROM post WHERE tags CONTAINS $tag_id")
+        .bind(("tag_id", tag.custom_id))
+        .await?
+        .take(0)?;
+
+    let mut entries = vec![];
+    for post in posts {
+        if untag {
+            let lost = std::slice::from_ref(tag);
+            entries.extend(
+                post_history_entries(db, post.custom_id, lost, TagAction::Untag, user_id).await?,
+            );
+        }
+        let gained: Vec<Tag> = added
+            .iter()
+            .filter(|added| !post.tags.contains(&added.custom_id))
+            .cloned()
+            .collect();
+        entries.extend(
+            post_history_entries(db, post.custom_id, &gained, TagAction::Tag, user_id).await?,
+        );
     }
+
+    Ok(entries)
+}
+
+/// Renames the tag called `name` to `new_name`, which is normalized first,
+/// on behalf of the user `user_id`. Failures are `TagChangeError`s.
+pub async fn rename_tag<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+    new_name: String,
+    user_id: u64,
+) -> anyhow::Result<()> {
+    let new_name = normalize_tag_name(&new_name).map_err(TagChangeError::from)?;
+    let tag = get_tag_by_name(db, name.clone())
+        .await?
+        .ok_or(TagChangeError::UnknownTag(name))?;
+    if new_name == tag.name {
+        return Ok(());
+    }
+    if get_tag_by_name(db, new_name.clone()).await?.is_some() {
+        return Err(TagChangeError::NameTaken(new_name).into());
+    }
+
+    db.query(format!(
+        r#"
+        BEGIN TRANSACTION;
+        UPDATE tag SET name = $new_name WHERE custom_id = $custom_id;
+        {}
+        COMMIT TRANSACTION;
+        "#,
+        ADD_HISTORY_ENTRY
+    ))
+    .bind(("new_name", new_name.clone()))
+    .bind(("custom_id", tag.custom_id))
+    .bind((
+        "entry",
+        TagHistoryEntry {
+            new_name,
+            ..history_entry(db, &tag, TagAction::Rename, user_id).await?
+        },
+    ))
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+/// Merges the tag called `name` into `target`, or the tag `target` is an
+/// alias of: posts move over, the implications of both are combined and the
+/// merged tag becomes an alias of the target. The history records the merge
+/// as made by `user_id`. Failures are `TagChangeError`s.
+pub async fn merge_tags<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+    target: String,
+    user_id: u64,
+) -> anyhow::Result<()> {
+    let tag = get_tag_by_name(db, name.clone())
+        .await?
+        .ok_or(TagChangeError::UnknownTag(name.clone()))?;
+    if tag.is_alias.is_some() {
+        return Err(TagChangeError::MergeAlias(name).into());
+    }
+    let target = resolve_tag_by_name(db, target.clone())
+        .await?
+        .ok_or(TagChangeError::UnknownTag(target))?;
+    if target.custom_id == tag.custom_id {
+        return Err(TagChangeError::SelfMerge.into());
+    }
+
+    // Whatever implied the merged tag implies the target afterwards, so the
+    // combined implications must not lead back to either of them.
+    let mut implications: Vec<u64> = tag
+        .implications
+        .iter()
+        .chain(&target.implications)
+        .copied()
+        .filter(|id| *id != tag.custom_id && *id != target.custom_id)
+        .collect();
+    implications.sort();
+    implications.dedup();
+    let closure = with_implied_tags(db, &implications).await?;
+    if closure.contains(&tag.custom_id) || closure.contains(&target.custom_id) {
+        return Err(TagChangeError::Cycle(target.name).into());
+    }
+
+    let mut implied = closure;
+    implied.push(target.custom_id);
+    implied.sort();
+
+    let mut recount = implied.clone();
+    recount.push(tag.custom_id);
+
+    let post_entries = retag_history_entries(db, &tag, true, &implied, user_id).await?;
+
+    db.query(format!(
+        r#"
+        BEGIN TRANSACTION;
+        UPDATE post
+            SET tags = array::sort(array::union(array::complement(tags, [$tag]), $implied))
+            WHERE tags CONTAINS $tag;
+        UPDATE tag
+            SET implications = array::union(array::complement(implications, [$tag]), [$target])
+            WHERE implications CONTAINS $tag;
+        UPDATE tag SET implications = $implications WHERE custom_id = $target;
+        UPDATE tag SET is_alias = $target WHERE is_alias = $tag;
+        UPDATE tag SET is_alias = $target, implications = [] WHERE custom_id = $tag;
+        UPDATE tag SET use_count = array::len(
+            (SELECT VALUE custom_id FROM post WHERE tags CONTAINS $parent.custom_id)
+        ) WHERE custom_id IN $recount;
+        {}
+        {}
+        COMMIT TRANSACTION;
+        "#,
+        ADD_HISTORY_ENTRY, ADD_POST_HISTORY_ENTRIES
+    ))
+    .bind(("tag", tag.custom_id))
+    .bind(("target", target.custom_id))
+    .bind(("implied", implied))
+    .bind(("implications", implications))
+    .bind(("recount", recount))
+    .bind(("post_entries", post_entries))
+    .bind((
+        "entry",
+        TagHistoryEntry {
+            new_name: target.name,
+            target_id: Some(target.custom_id),
+            ..history_entry(db, &tag, TagAction::Merge, user_id).await?
+        },
+    ))
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+/// Deletes the tag called `name`. A tag that posts, aliases or implications
+/// still refer to is only deleted with `force`, which also removes it from
+/// posts and implications and deletes its aliases. The history records the
+/// deletion as made by `user_id`. Failures are `TagDeleteError`s.
+pub async fn delete_tag<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+    force: bool,
+    user_id: u64,
+) -> anyhow::Result<()> {
+    let tag = get_tag_by_name(db, name.clone())
+        .await?
+        .ok_or(TagDeleteError::UnknownTag(name))?;
+
+    if !force {
+        let mut response = db
+            .query("RETURN array::len((SELECT VALUE custom_id FROM post WHERE tags CONTAINS $custom_id))")
+            .query("RETURN array::len((SELECT VALUE custom_id FROM tag WHERE is_alias = $custom_id))")
+            .query("RETURN array::len((SELECT VALUE custom_id FROM tag WHERE implications CONTAINS $custom_id))")
+            .bind(("custom_id", tag.custom_id))
+            .await?;
+        let posts: Option<u64> = response.take(0)?;
+        let aliases: Option<u64> = response.take(1)?;
+        let implied_by: Option<u64> = response.take(2)?;
+        let (posts, aliases, implied_by) = (
+            posts.unwrap_or_default(),
+            aliases.unwrap_or_default(),
+            implied_by.unwrap_or_default(),
+        );
+
+        if posts > 0 || aliases > 0 || implied_by > 0 {
+            return Err(TagDeleteError::InUse {
+                posts,
+                aliases,
+                implied_by,
+            }
+            .into());
+        }
+    }
+
+    let post_entries = retag_history_entries(db, &tag, true, &[], user_id).await?;
+
+    db.query(format!(
+        r#"
+        BEGIN TRANSACTION;
+        UPDATE post SET tags = array::complement(tags, [$custom_id]) WHERE tags CONTAINS $custom_id;
+        UPDATE tag
+            SET implications = array::complement(implications, [$custom_id])
+            WHERE implications CONTAINS $custom_id;
+        DELETE tag WHERE is_alias = $custom_id OR custom_id = $custom_id;
+        {}
+        {}
+        COMMIT TRANSACTION;
+        "#,
+        ADD_HISTORY_ENTRY, ADD_POST_HISTORY_ENTRIES
+    ))
+    .bind(("custom_id", tag.custom_id))
+    .bind((
+        "entry",
+        history_entry(db, &tag, TagAction::Delete, user_id).await?,
+    ))
+    .bind(("post_entries", post_entries))
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+/// Changes to the tag `tag_id` and merges and aliases into it, oldest
+/// first. Adding the tag to posts and removing it is left out, as popular
+/// tags have any number of those.
+pub async fn get_tag_history<C: Connection>(
+    db: &Surreal<C>,
+    tag_id: u64,
+) -> anyhow::Result<Vec<TagHistoryEntry>> {
+    let entries: Vec<TagHistoryEntry> = db
+        .query("SELECT * FROM tag_history WHERE (tag_id = $tag_id OR target_id = $tag_id) AND post_id = NONE ORDER BY custom_id ASC")
+        .bind(("tag_id", tag_id))
+        .await?
+        .take(0)?;
+
+    Ok(entries)
 }
@@ -0,0 +1,176 @@
+use surrealdb::{Connection, Surreal};
+
+use crate::models::tag::{TagAction, TagDetails, TagEdit, TagEditError};
+use crate::models::wiki::check_wiki_body;
+use crate::server_only::alias::{
+    alias_implied_tags, resolve_tag_by_name, resolve_tags_by_names, CREATE_ALIAS,
+};
+use crate::server_only::implication::{
+    with_implied_tags, BACK_APPLY_IMPLICATIONS, SET_IMPLICATIONS,
+};
+use crate::server_only::tag::{
+    get_tag_by_id, get_tag_by_name, get_tag_history, get_tags_by_ids, history_entry,
+    retag_history_entries, ADD_HISTORY_ENTRY, ADD_POST_HISTORY_ENTRIES,
+};
+use crate::server_only::user::get_user_names;
+use crate::server_only::wiki::{latest_wiki_version, new_wiki_version, ADD_WIKI_VERSION};
+
+/// The tag called `name` with the names of its alias target and
+/// implications and its history, `None` if there is no such tag.
+pub async fn get_tag_details<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+) -> anyhow::Result<Option<TagDetails>> {
+    let Some(tag) = get_tag_by_name(db, name).await? else {
+        return Ok(None);
+    };
+
+    let alias_of = match tag.is_alias {
+        Some(target) => get_tag_by_id(db, target).await?.map(|target| target.name),
+        None => None,
+    };
+    let implications = get_tags_by_ids(db, tag.implications.clone())
+        .await?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+
+    let history = get_tag_history(db, tag.custom_id).await?;
+    let users = history.iter().map(|entry| entry.user_id).collect();
+    let user_names = get_user_names(db, users).await?;
+
+    Ok(Some(TagDetails {
+        tag,
+        alias_of,
+        implications,
+        history,
+        user_names,
+    }))
+}
+
+/// Applies `edit` to the tag called `name`. Everything is checked before
+/// anything changes and the changes are made in one transaction; failures
+/// are `TagEditError`s. A changed description is saved as a new version of
+/// the wiki page by `user_id`, other changes get a history entry.
+pub async fn edit_tag<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+    edit: TagEdit,
+    user_id: u64,
+) -> anyhow::Result<()> {
+    let tag = get_tag_by_name(db, name.clone())
+        .await?
+        .ok_or(TagEditError::UnknownTag(name))?;
+    if let Some(description) = &edit.description {
+        check_wiki_body(description).map_err(|_| TagEditError::DescriptionTooLong)?;
+    }
+
+    let alias_of = match edit.alias_of.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(target) => {
+            let target = resolve_tag_by_name(db, target.to_lowercase())
+                .await?
+                .ok_or_else(|| TagEditError::UnknownTag(target.to_string()))?;
+            if target.custom_id == tag.custom_id {
+                return Err(TagEditError::SelfAlias.into());
+            }
+            Some(target.custom_id)
+        }
+    };
+
+    let names: Vec<String> = edit
+        .implications
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    let found = resolve_tags_by_names(db, names.clone()).await?;
+    if let Some(missing) = names.iter().find(|name| !found.contains_key(*name)) {
+        return Err(TagEditError::UnknownTag(missing.clone()).into());
+    }
+    let mut implications: Vec<u64> = found.values().map(|tag| tag.custom_id).collect();
+    implications.sort();
+    implications.dedup();
+
+    if alias_of.is_some() && !implications.is_empty() {
+        return Err(TagEditError::AliasWithImplications.into());
+    }
+    let mut implied_tags = with_implied_tags(db, &implications).await?;
+    if implied_tags.contains(&tag.custom_id) {
+        return Err(TagEditError::Cycle(tag.name).into());
+    }
+    implied_tags.push(tag.custom_id);
+    implied_tags.sort();
+
+    let mut statements = vec!["UPDATE tag SET category = $category WHERE custom_id = $tag_id;"];
+
+    let wiki_version = match edit
+        .description
+        .filter(|description| *description != tag.description)
+    {
+        Some(description) => {
+            let version = latest_wiki_version(db, tag.custom_id).await? + 1;
+            statements.push(ADD_WIKI_VERSION);
+            Some(new_wiki_version(db, &tag, version, description, None, user_id).await?)
+        }
+        None => None,
+    };
+
+    let mut alias_implied = vec![];
+    let mut post_entries = vec![];
+    match (tag.is_alias, alias_of) {
+        (None, Some(target)) => {
+            alias_implied = alias_implied_tags(db, tag.custom_id, target).await?;
+            statements.push(CREATE_ALIAS);
+            statements.push(ADD_POST_HISTORY_ENTRIES);
+            post_entries = retag_history_entries(db, &tag, true, &alias_implied, user_id).await?;
+        }
+        // An alias has no posts or implications, so it only needs to point
+        // somewhere else.
+        (Some(current), target) if target != Some(current) => {
+            statements.push("UPDATE tag SET is_alias = $target WHERE custom_id = $tag_id;")
+        }
+        _ => {}
+    }
+
+    if alias_of.is_none() {
+        statements.push(SET_IMPLICATIONS);
+        if edit.back_apply {
+            statements.push(BACK_APPLY_IMPLICATIONS);
+            statements.push(ADD_POST_HISTORY_ENTRIES);
+            post_entries = retag_history_entries(db, &tag, false, &implied_tags, user_id).await?;
+        }
+    }
+
+    // Description changes are already kept as wiki versions.
+    let mut current_implications = tag.implications.clone();
+    current_implications.sort();
+    let entry = if edit.category != tag.category
+        || alias_of != tag.is_alias
+        || (alias_of.is_none() && implications != current_implications)
+    {
+        statements.push(ADD_HISTORY_ENTRY);
+        Some(history_entry(db, &tag, TagAction::Edit, user_id).await?)
+    } else {
+        None
+    };
+
+    db.query(format!(
+        "BEGIN TRANSACTION; {} COMMIT TRANSACTION;",
+        statements.join("\n")
+    ))
+    .bind(("tag_id", tag.custom_id))
+    .bind(("category", edit.category))
+    .bind(("wiki_version", wiki_version))
+    .bind(("alias", tag.custom_id))
+    .bind(("target", alias_of))
+    .bind(("alias_implied", alias_implied))
+    .bind(("implications", implications))
+    .bind(("implied_tags", implied_tags))
+    .bind(("entry", entry))
+    .bind(("post_entries", post_entries))
+    .await?
+    .check()?;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use leptos::logging;
+use surrealdb::{Connection, Surreal};
+use tokio::sync::mpsc;
+
+use crate::models::post::{Post, PostType};
+use crate::server_only::config::{sample_size, thumbnail_size};
+use crate::server_only::post::get_paginated_posts;
+use crate::server_only::video::extract_poster_frame;
+
+/// Posts waiting for their previews, consumed by the task started in
+/// `start_thumbnail_worker`.
+static THUMBNAIL_QUEUE: OnceLock<mpsc::UnboundedSender<(Post, PathBuf)>> = OnceLock::new();
+
+/// Spawns the background task that renders thumbnails for queued posts. Must
+/// be called once from inside the tokio runtime before anything is queued.
+pub fn start_thumbnail_worker() {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(Post, PathBuf)>();
+
+    if THUMBNAIL_QUEUE.set(sender).is_err() {
+        logging::warn!("thumbnail worker was already started");
+        return;
+    }
+
+    tokio::spawn(async move {
+        while let Some((post, uploads_dir)) = receiver.recv().await {
+            if let Err(e) = generate_thumbnails(&post, &uploads_dir).await {
+                logging::error!(
+                    "failed to generate thumbnails for post #{}: {}",
+                    post.custom_id,
+                    e
+                );
+            }
+        }
+    });
+}
+
+/// Queues thumbnail generation for `post`, whose original lives in `uploads_dir`.
+pub fn queue_thumbnails(post: &Post, uploads_dir: &Path) -> anyhow::Result<()> {
+    THUMBNAIL_QUEUE
+        .get()
+        .ok_or_else(|| anyhow!("thumbnail worker is not running"))?
+        .send((post.clone(), uploads_dir.to_path_buf()))
+        .map_err(|_| anyhow!("thumbnail worker has stopped"))
+}
+
+/// Writes the thumbnail and sample for `post` next to its original file.
+/// Videos are previewed by their first frame. Images already smaller than a
+/// preview size are not scaled up.
+pub async fn generate_thumbnails(post: &Post, uploads_dir: &Path) -> anyhow::Result<()> {
+    let source = uploads_dir.join(post.file_name());
+    let thumbnail = uploads_dir.join(post.thumbnail_file_name());
+    let sample = uploads_dir.join(post.sample_file_name());
+    let (thumbnail_size, sample_size) = (thumbnail_size(), sample_size());
+    let post_type = post.post_type.clone();
+
+    // Decoding and resizing is CPU bound, keep it off the async executor.
+    tokio::task::spawn_blocking(move || {
+        let image = match post_type {
+            PostType::Image => decode_image(&source)?,
+            PostType::Video => extract_poster_frame(&source)?,
+        };
+
+        write_preview(&image, thumbnail_size, &thumbnail)?;
+        write_preview(&image, sample_size, &sample)?;
+
+        Ok(())
+    })
+    .await?
+}
+
+/// Regenerates the previews of every post, for use after the configured sizes
+/// change. Returns how many posts failed.
+pub async fn regenerate_all_thumbnails<C: Connection>(
+    db: &Surreal<C>,
+    uploads_dir: &Path,
+) -> anyhow::Result<usize> {
+    let per_page = 100;
+    let mut page = 1;
+    let mut failed = 0;
+
+    loop {
+        let posts = get_paginated_posts(db, page, per_page, None).await?;
+
+        for post in &posts {
+            if let Err(e) = generate_thumbnails(post, uploads_dir).await {
+                logging::error!(
+                    "failed to generate thumbnails for post #{}: {}",
+                    post.custom_id,
+                    e
+                );
+                failed += 1;
+            }
+        }
+
+        if posts.len() < per_page as usize {
+            return Ok(failed);
+        }
+        page += 1;
+    }
+}
+
+/// Decodes the image at `source`. Formats the `image` crate is built without,
+/// like AVIF, are decoded by ffmpeg instead.
+fn decode_image(source: &Path) -> anyhow::Result<DynamicImage> {
+    match image::open(source) {
+        Err(image::ImageError::Unsupported(_)) => extract_poster_frame(source),
+        result => Ok(result?),
+    }
+}
+
+fn write_preview(image: &DynamicImage, max_size: u32, path: &Path) -> anyhow::Result<()> {
+    let preview = if image.width() > max_size || image.height() > max_size {
+        image.resize(max_size, max_size, FilterType::Lanczos3)
+    } else {
+        image.clone()
+    };
+
+    // JPEG has no alpha channel, so flatten to RGB first.
+    DynamicImage::ImageRgb8(preview.to_rgb8()).save_with_format(path, ImageFormat::Jpeg)?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Receiving uploaded files. The body is written to a temp file chunk by
+//! chunk, so an upload never has to fit in memory, and only moved to its
+//! final name once the post exists.
+
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+use crate::components::file_upload::UploadError;
+use crate::server_only::media::HEADER_LEN;
+
+/// A partially written upload. The file is deleted on drop unless it was
+/// moved to its final name with `persist`.
+pub struct TempFile {
+    pub path: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    pub fn new(dir: &Path) -> TempFile {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        TempFile {
+            path: dir.join(format!(".upload-{}-{}.tmp", std::process::id(), id)),
+            persisted: false,
+        }
+    }
+
+    /// Moves the file to `target`, copying it if the two are on different
+    /// filesystems.
+    pub async fn persist(mut self, target: &Path) -> std::io::Result<()> {
+        if tokio::fs::rename(&self.path, target).await.is_err() {
+            // The copy is in place, dropping `self` removes the original.
+            tokio::fs::copy(&self.path, target).await?;
+            return Ok(());
+        }
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// An upload written to its temp file, with what was learned on the way.
+pub struct ReceivedFile {
+    pub temp_file: TempFile,
+    /// The first `HEADER_LEN` bytes, for sniffing the file type.
+    pub header: Vec<u8>,
+    pub sha256_hash: [u8; 32],
+    pub md5_hash: [u8; 16],
+    pub file_size: u64,
+}
+
+/// Writes `chunks` to a new temp file in `dir` as they arrive. Fails with
+/// `UploadError::TooLarge` as soon as more than `max_size` bytes arrived;
+/// the temp file is removed again whenever this fails.
+pub async fn receive_file<S, E>(
+    chunks: S,
+    dir: &Path,
+    max_size: u64,
+) -> anyhow::Result<ReceivedFile>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: std::fmt::Display,
+{
+    let mut chunks = std::pin::pin!(chunks);
+    tokio::fs::create_dir_all(dir).await?;
+    let temp_file = TempFile::new(dir);
+    let mut file = tokio::fs::File::create(&temp_file.path).await?;
+    let mut hasher = Sha256::new();
+    let mut md5_hasher = Md5::new();
+    let mut header: Vec<u8> = Vec::new();
+    let mut file_size: u64 = 0;
+
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| UploadError::InvalidFile(e.to_string()))?;
+        file_size += chunk.len() as u64;
+        if file_size > max_size {
+            return Err(UploadError::TooLarge(max_size).into());
+        }
+
+        if header.len() < HEADER_LEN {
+            let missing = HEADER_LEN - header.len();
+            header.extend_from_slice(&chunk[..missing.min(chunk.len())]);
+        }
+        hasher.update(&chunk);
+        md5_hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+
+    Ok(ReceivedFile {
+        temp_file,
+        header,
+        sha256_hash: hasher.finalize().into(),
+        md5_hash: md5_hasher.finalize().into(),
+        file_size,
+    })
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surrealdb::{sql::parse, Connection, Surreal};
+
+use crate::models::permission::Role;
+use crate::models::post::hex_hash;
+use crate::models::user::{normalize_user_name, User, UserError, MIN_PASSWORD_LENGTH};
+use crate::server_only::config::session_days;
+use crate::server_only::db::get_next_id;
+use crate::server_only::tag::now;
+
+/// A user as stored, with the Argon2id hash of their password in PHC form.
+#[derive(Clone, Serialize, Deserialize)]
+struct UserRecord {
+    custom_id: u64,
+    name: String,
+    password_hash: String,
+    created_at: u64,
+    #[serde(default)]
+    role: Role,
+}
+
+impl From<UserRecord> for User {
+    fn from(record: UserRecord) -> User {
+        User {
+            custom_id: record.custom_id,
+            name: record.name,
+            created_at: record.created_at,
+            role: record.role,
+        }
+    }
+}
+
+/// A login. Only a hash of the token is stored, so a leaked database does
+/// not hand out sessions.
+#[derive(Serialize, Deserialize)]
+struct Session {
+    token_hash: String,
+    user_id: u64,
+    expires_at: u64,
+}
+
+pub async fn define_user_tables<C: Connection>(db: &Surreal<C>) -> anyhow::Result<()> {
+    let schema = format!(
+        r#"
+        DEFINE TABLE user SCHEMAFULL;
+
+        DEFINE FIELD custom_id ON TABLE user TYPE number;
+        DEFINE FIELD name ON TABLE user TYPE string;
+        DEFINE FIELD password_hash ON TABLE user TYPE string;
+        DEFINE FIELD created_at ON TABLE user TYPE number;
+        DEFINE FIELD role ON TABLE user TYPE number DEFAULT {} ASSERT $value IN {:?};
+
+        DEFINE INDEX user_id ON TABLE user FIELDS custom_id UNIQUE;
+        DEFINE INDEX user_name ON TABLE user FIELDS name UNIQUE;
+
+        DEFINE TABLE session SCHEMAFULL;
+
+        DEFINE FIELD token_hash ON TABLE session TYPE string;
+        DEFINE FIELD user_id ON TABLE session TYPE number;
+        DEFINE FIELD expires_at ON TABLE session TYPE number;
+
+        DEFINE INDEX session_token ON TABLE session FIELDS token_hash UNIQUE;
+        "#,
+        u8::from(Role::default()),
+        Role::ALL.map(u8::from)
+    );
+
+    db.query(parse(&schema)?).await?;
+
+    Ok(())
+}
+
+fn random_bytes<const N: usize>() -> anyhow::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("no randomness: {}", e))?;
+    Ok(bytes)
+}
+
+fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::encode_b64(&random_bytes::<16>()?).map_err(|e| anyhow!(e))?;
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!(e))?;
+    Ok(hash.to_string())
+}
+
+fn hash_token(token: &str) -> String {
+    let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+    hex_hash::encode(&digest)
+}
+
+async fn get_user_record<C: Connection>(
+    db: &Surreal<C>,
+    name: &str,
+) -> anyhow::Result<Option<UserRecord>> {
+    let result: Option<UserRecord> = db
+        .query("SELECT * FROM user WHERE name = $name")
+        .bind(("name", name.to_lowercase()))
+        .await?
+        .take(0)?;
+
+    Ok(result)
+}
+
+/// Creates a user called `name`, which is normalized first. Failures are
+/// `UserError`s.
+pub async fn register_user<C: Connection>(
+    db: &Surreal<C>,
+    name: &str,
+    password: &str,
+) -> anyhow::Result<User> {
+    let name = normalize_user_name(name)?;
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(UserError::PasswordTooShort.into());
+    }
+
+    define_user_tables(db).await?;
+    if get_user_record(db, &name).await?.is_some() {
+        return Err(UserError::NameTaken(name).into());
+    }
+
+    let record = UserRecord {
+        custom_id: get_next_id(db, "user_counter").await?,
+        name,
+        password_hash: hash_password(password)?,
+        created_at: now(),
+        role: Role::default(),
+    };
+    db.query("CREATE user CONTENT $record")
+        .bind(("record", record.clone()))
+        .await?
+        .check()?;
+
+    Ok(record.into())
+}
+
+/// The user called `name` if `password` is theirs, otherwise
+/// `UserError::WrongCredentials`.
+pub async fn authenticate<C: Connection>(
+    db: &Surreal<C>,
+    name: &str,
+    password: &str,
+) -> anyhow::Result<User> {
+    let record = get_user_record(db, name.trim())
+        .await?
+        .ok_or(UserError::WrongCredentials)?;
+
+    let hash = PasswordHash::new(&record.password_hash).map_err(|e| anyhow!(e))?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .map_err(|_| UserError::WrongCredentials)?;
+
+    Ok(record.into())
+}
+
+pub async fn get_user_by_name<C: Connection>(
+    db: &Surreal<C>,
+    name: &str,
+) -> anyhow::Result<Option<User>> {
+    Ok(get_user_record(db, name).await?.map(User::from))
+}
+
+/// Names of the users with the given ids. Unknown ids are left out.
+pub async fn get_user_names<C: Connection>(
+    db: &Surreal<C>,
+    ids: Vec<u64>,
+) -> anyhow::Result<HashMap<u64, String>> {
+    let users: Vec<User> = db
+        .query("SELECT custom_id, name, created_at, role FROM user WHERE custom_id IN $ids")
+        .bind(("ids", ids))
+        .await?
+        .take(0)?;
+
+    Ok(users
+        .into_iter()
+        .map(|user| (user.custom_id, user.name))
+        .collect())
+}
+
+/// Gives the user called `name` a new role on behalf of `granted_by`, or of
+/// whoever runs the server if that is `None`. Users can neither hand out a
+/// role above their own nor change anyone ranked as high as them, except
+/// themselves. Failures are `UserError`s.
+pub async fn set_user_role<C: Connection>(
+    db: &Surreal<C>,
+    name: &str,
+    role: Role,
+    granted_by: Option<&User>,
+) -> anyhow::Result<User> {
+    let name = name.trim().to_lowercase();
+    let current = get_user_by_name(db, &name)
+        .await?
+        .ok_or_else(|| UserError::UnknownUser(name.clone()))?;
+    if let Some(granted_by) = granted_by {
+        let own_role = current.custom_id == granted_by.custom_id;
+        if current.role >= granted_by.role && !own_role {
+            return Err(UserError::OutranksYou(current.name).into());
+        }
+        if role > granted_by.role {
+            return Err(UserError::RoleAboveYours.into());
+        }
+    }
+
+    let user: Option<User> = db
+        .query("UPDATE user SET role = $role WHERE name = $name RETURN custom_id, name, created_at, role")
+        .bind(("role", u8::from(role)))
+        .bind(("name", name.clone()))
+        .await?
+        .take(0)?;
+
+    Ok(user.ok_or(UserError::UnknownUser(name))?)
+}
+
+/// Logs the user `user_id` in and returns the token for their cookie.
+pub async fn create_session<C: Connection>(
+    db: &Surreal<C>,
+    user_id: u64,
+) -> anyhow::Result<String> {
+    define_user_tables(db).await?;
+
+    let token = hex_hash::encode(&random_bytes::<32>()?);
+    let session = Session {
+        token_hash: hash_token(&token),
+        user_id,
+        expires_at: now() + session_days() * 24 * 60 * 60,
+    };
+    db.query("DELETE session WHERE expires_at <= $now; CREATE session CONTENT $new_session")
+        .bind(("now", now()))
+        .bind(("new_session", session))
+        .await?
+        .check()?;
+
+    Ok(token)
+}
+
+/// The user logged in with `token`, if the session exists and has not
+/// expired.
+pub async fn get_session_user<C: Connection>(
+    db: &Surreal<C>,
+    token: &str,
+) -> anyhow::Result<Option<User>> {
+    let user: Option<User> = db
+        .query(
+            r#"
+            SELECT custom_id, name, created_at, role FROM user WHERE custom_id = (
+                SELECT VALUE user_id FROM session
+                WHERE token_hash = $token_hash AND expires_at > $now
+            )[0]
+            "#,
+        )
+        .bind(("token_hash", hash_token(token)))
+        .bind(("now", now()))
+        .await?
+        .take(0)?;
+
+    Ok(user)
+}
+
+/// Logs out the session with `token`.
+pub async fn end_session<C: Connection>(db: &Surreal<C>, token: &str) -> anyhow::Result<()> {
+    db.query("DELETE session WHERE token_hash = $token_hash")
+        .bind(("token_hash", hash_token(token)))
+        .await?
+        .check()?;
+
+    Ok(())
+}
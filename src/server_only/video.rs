@@ -0,0 +1,350 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use crate::server_only::config::ffmpeg_path;
+
+/// Largest metadata element (`moov` box, WebM `Info`/`Tracks`) read into memory.
+const MAX_METADATA_LEN: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct VideoMetadata {
+    pub width: u32,
+    pub height: u32,
+    /// Length in seconds.
+    pub duration: f64,
+    pub has_audio: bool,
+}
+
+/// Reads dimensions, duration and audio presence from a WebM or MP4 container
+/// without decoding any frames. Returns `None` for other or malformed files.
+pub fn video_metadata<R: Read + Seek>(mime_type: &mime::Mime, reader: R) -> Option<VideoMetadata> {
+    match mime_type.essence_str() {
+        "video/mp4" => mp4_metadata(reader),
+        "video/webm" => webm_metadata(reader),
+        _ => None,
+    }
+}
+
+/// Decodes the first frame of the video at `source` with ffmpeg, as the
+/// source image for its thumbnails.
+pub fn extract_poster_frame(source: &Path) -> anyhow::Result<image::DynamicImage> {
+    let output = std::process::Command::new(ffmpeg_path())
+        .args(["-v", "error", "-i"])
+        .arg(source)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(image::load_from_memory_with_format(
+        &output.stdout,
+        image::ImageFormat::Png,
+    )?)
+}
+
+fn mp4_metadata<R: Read + Seek>(mut reader: R) -> Option<VideoMetadata> {
+    // The moov box may come after the (huge) mdat box, so walk the top level
+    // boxes by seeking instead of reading everything.
+    let moov = loop {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).ok()?;
+        let size = u32::from_be_bytes(header[0..4].try_into().ok()?) as u64;
+        let (payload_len, is_moov) = match size {
+            0 => return None, // Box runs to the end of the file, no moov after it.
+            1 => {
+                let mut large_size = [0u8; 8];
+                reader.read_exact(&mut large_size).ok()?;
+                (
+                    u64::from_be_bytes(large_size).checked_sub(16)?,
+                    &header[4..8] == b"moov",
+                )
+            }
+            size => (size.checked_sub(8)?, &header[4..8] == b"moov"),
+        };
+
+        if is_moov {
+            if payload_len > MAX_METADATA_LEN {
+                return None;
+            }
+            let mut moov = vec![0u8; payload_len as usize];
+            reader.read_exact(&mut moov).ok()?;
+            break moov;
+        }
+
+        skip_forward(&mut reader, payload_len)?;
+    };
+
+    let mut duration = None;
+    let mut dimensions = None;
+    let mut has_audio = false;
+
+    for (kind, payload) in mp4_boxes(&moov) {
+        match kind {
+            b"mvhd" => {
+                let (timescale, length) = match *payload.first()? {
+                    0 => (read_u32_be(payload, 12)?, read_u32_be(payload, 16)? as u64),
+                    _ => (read_u32_be(payload, 20)?, read_u64_be(payload, 24)?),
+                };
+                if timescale != 0 {
+                    duration = Some(length as f64 / timescale as f64);
+                }
+            }
+            b"trak" => {
+                let children = mp4_boxes(payload);
+                let handler =
+                    children
+                        .iter()
+                        .find(|(kind, _)| *kind == b"mdia")
+                        .and_then(|(_, mdia)| {
+                            mp4_boxes(mdia)
+                                .into_iter()
+                                .find(|(kind, _)| *kind == b"hdlr")
+                                .and_then(|(_, hdlr)| hdlr.get(8..12))
+                        });
+
+                match handler {
+                    Some(b"soun") => has_audio = true,
+                    Some(b"vide") if dimensions.is_none() => {
+                        let tkhd = children.iter().find(|(kind, _)| *kind == b"tkhd")?.1;
+                        // Track width and height are 16.16 fixed point numbers.
+                        let offset = if *tkhd.first()? == 0 { 76 } else { 88 };
+                        dimensions = Some((
+                            read_u32_be(tkhd, offset)? >> 16,
+                            read_u32_be(tkhd, offset + 4)? >> 16,
+                        ));
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let (width, height) = dimensions?;
+    Some(VideoMetadata {
+        width,
+        height,
+        duration: duration?,
+        has_audio,
+    })
+}
+
+/// Skips `len` bytes. Sizes come from the file, so the target is computed
+/// without wrapping around: a huge size must not seek back to an earlier
+/// element and walk the same elements forever.
+fn skip_forward<R: Seek>(reader: &mut R, len: u64) -> Option<()> {
+    let start = reader.stream_position().ok()?;
+    let target = start.checked_add(len)?;
+    let position = reader.seek(SeekFrom::Start(target)).ok()?;
+    (position == target).then_some(())
+}
+
+/// Splits an in-memory run of ISO-BMFF boxes into `(type, payload)` pairs.
+fn mp4_boxes(mut data: &[u8]) -> Vec<(&[u8; 4], &[u8])> {
+    let mut boxes = vec![];
+
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let kind: &[u8; 4] = data[4..8].try_into().unwrap();
+        let (header_len, size) = match size {
+            0 => (8, data.len()),
+            1 => match read_u64_be(data, 8) {
+                Some(size) => (16, size as usize),
+                None => break,
+            },
+            size => (8, size),
+        };
+
+        if size < header_len || size > data.len() {
+            break;
+        }
+        boxes.push((kind, &data[header_len..size]));
+        data = &data[size..];
+    }
+
+    boxes
+}
+
+const EBML_HEADER: u64 = 0x1A45DFA3;
+const EBML_DOC_TYPE: u64 = 0x4282;
+const SEGMENT: u64 = 0x18538067;
+const SEGMENT_INFO: u64 = 0x1549A966;
+const TIMECODE_SCALE: u64 = 0x2AD7B1;
+const DURATION: u64 = 0x4489;
+const TRACKS: u64 = 0x1654AE6B;
+const TRACK_ENTRY: u64 = 0xAE;
+const TRACK_TYPE: u64 = 0x83;
+const TRACK_VIDEO: u64 = 0xE0;
+const PIXEL_WIDTH: u64 = 0xB0;
+const PIXEL_HEIGHT: u64 = 0xBA;
+
+fn webm_metadata<R: Read + Seek>(mut reader: R) -> Option<VideoMetadata> {
+    let (id, size) = read_ebml_element_header(&mut reader)?;
+    if id != EBML_HEADER {
+        return None;
+    }
+    let header = read_ebml_payload(&mut reader, size?)?;
+    let doc_type = ebml_children(&header)
+        .into_iter()
+        .find(|(id, _)| *id == EBML_DOC_TYPE)?
+        .1;
+    if doc_type != b"webm" {
+        return None;
+    }
+
+    let (id, _segment_size) = read_ebml_element_header(&mut reader)?;
+    if id != SEGMENT {
+        return None;
+    }
+
+    let mut info = None;
+    let mut tracks = None;
+
+    while info.is_none() || tracks.is_none() {
+        let (id, size) = read_ebml_element_header(&mut reader)?;
+        // Only clusters of live streams have an unknown size, and those come
+        // after the metadata we are looking for.
+        let size = size?;
+        match id {
+            SEGMENT_INFO => info = Some(read_ebml_payload(&mut reader, size)?),
+            TRACKS => tracks = Some(read_ebml_payload(&mut reader, size)?),
+            _ => skip_forward(&mut reader, size)?,
+        }
+    }
+
+    let info = ebml_children(info.as_deref()?);
+    let timecode_scale = info
+        .iter()
+        .find(|(id, _)| *id == TIMECODE_SCALE)
+        .map(|(_, value)| read_ebml_uint(value))
+        .unwrap_or(1_000_000);
+    let duration = match info.iter().find(|(id, _)| *id == DURATION)?.1 {
+        value if value.len() == 4 => f32::from_be_bytes(value.try_into().ok()?) as f64,
+        value if value.len() == 8 => f64::from_be_bytes(value.try_into().ok()?),
+        _ => return None,
+    };
+
+    let mut dimensions = None;
+    let mut has_audio = false;
+
+    for (id, entry) in ebml_children(tracks.as_deref()?) {
+        if id != TRACK_ENTRY {
+            continue;
+        }
+        let entry = ebml_children(entry);
+        let track_type = entry
+            .iter()
+            .find(|(id, _)| *id == TRACK_TYPE)
+            .map(|(_, value)| read_ebml_uint(value));
+
+        match track_type {
+            Some(1) if dimensions.is_none() => {
+                let video = ebml_children(entry.iter().find(|(id, _)| *id == TRACK_VIDEO)?.1);
+                let field = |wanted: u64| {
+                    video
+                        .iter()
+                        .find(|(id, _)| *id == wanted)
+                        .map(|(_, value)| read_ebml_uint(value) as u32)
+                };
+                dimensions = Some((field(PIXEL_WIDTH)?, field(PIXEL_HEIGHT)?));
+            }
+            Some(2) => has_audio = true,
+            _ => (),
+        }
+    }
+
+    let (width, height) = dimensions?;
+    Some(VideoMetadata {
+        width,
+        height,
+        // Durations are stored in timecode units, which are nanoseconds * scale.
+        duration: duration * timecode_scale as f64 / 1_000_000_000.0,
+        has_audio,
+    })
+}
+
+/// Reads an element ID and its size. The size is `None` for the reserved
+/// "unknown size" value.
+fn read_ebml_element_header<R: Read>(reader: &mut R) -> Option<(u64, Option<u64>)> {
+    let (id, _) = read_ebml_vint(reader, false)?;
+    let (size, length) = read_ebml_vint(reader, true)?;
+    let unknown = (1u64 << (7 * length)) - 1;
+    Some((id, (size != unknown).then_some(size)))
+}
+
+/// Reads a variable length integer. IDs keep their length marker bit, sizes
+/// do not. Returns the value and its length in bytes.
+fn read_ebml_vint<R: Read>(reader: &mut R, strip_marker: bool) -> Option<(u64, u32)> {
+    let mut first = [0u8; 1];
+    reader.read_exact(&mut first).ok()?;
+    let length = first[0].leading_zeros() + 1;
+    if length > 8 {
+        return None;
+    }
+
+    let mut value = if strip_marker {
+        (first[0] as u64) & ((1 << (8 - length)) - 1)
+    } else {
+        first[0] as u64
+    };
+    for _ in 1..length {
+        reader.read_exact(&mut first).ok()?;
+        value = (value << 8) | first[0] as u64;
+    }
+
+    Some((value, length))
+}
+
+fn read_ebml_payload<R: Read>(reader: &mut R, size: u64) -> Option<Vec<u8>> {
+    if size > MAX_METADATA_LEN {
+        return None;
+    }
+    let mut payload = vec![0u8; size as usize];
+    reader.read_exact(&mut payload).ok()?;
+    Some(payload)
+}
+
+/// Splits an in-memory EBML master element into `(id, payload)` pairs.
+fn ebml_children(mut data: &[u8]) -> Vec<(u64, &[u8])> {
+    let mut children = vec![];
+
+    while !data.is_empty() {
+        let mut cursor = std::io::Cursor::new(data);
+        let Some((id, Some(size))) = read_ebml_element_header(&mut cursor) else {
+            break;
+        };
+        let start = cursor.position() as usize;
+        let Some(end) = start
+            .checked_add(size as usize)
+            .filter(|end| *end <= data.len())
+        else {
+            break;
+        };
+        children.push((id, &data[start..end]));
+        data = &data[end..];
+    }
+
+    children
+}
+
+fn read_ebml_uint(value: &[u8]) -> u64 {
+    value
+        .iter()
+        .take(8)
+        .fold(0, |acc, byte| (acc << 8) | *byte as u64)
+}
+
+fn read_u32_be(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u64_be(data: &[u8], pos: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(pos..pos + 8)?.try_into().ok()?))
+}
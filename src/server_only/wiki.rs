@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+
+use ammonia::{Builder, UrlRelative};
+use surrealdb::{sql::parse, Connection, Surreal};
+
+use crate::models::post::Post;
+use crate::models::tag::{Tag, TagCategory};
+use crate::models::wiki::{
+    check_wiki_body, example_posts, parse_markup, render_markup, tag_links, WikiError, WikiPage,
+    WikiVersion, MISSING_TAG_CLASS,
+};
+use crate::server_only::alias::resolve_tags_by_names;
+use crate::server_only::db::get_next_id;
+use crate::server_only::tag::{get_tag_by_name, now};
+use crate::server_only::user::get_user_names;
+
+pub async fn define_wiki_table<C: Connection>(db: &Surreal<C>) -> anyhow::Result<()> {
+    let schema = r#"
+        DEFINE TABLE wiki_version SCHEMAFULL;
+
+        DEFINE FIELD custom_id ON TABLE wiki_version TYPE number;
+        DEFINE FIELD tag_id ON TABLE wiki_version TYPE number;
+        DEFINE FIELD version ON TABLE wiki_version TYPE number;
+        DEFINE FIELD body ON TABLE wiki_version TYPE string;
+        DEFINE FIELD created_at ON TABLE wiki_version TYPE number;
+        DEFINE FIELD reverted_from ON TABLE wiki_version TYPE option<number>;
+        DEFINE FIELD author_id ON TABLE wiki_version TYPE number DEFAULT 0;
+
+        DEFINE INDEX wiki_version_id ON TABLE wiki_version FIELDS custom_id UNIQUE;
+        DEFINE INDEX tag_version ON TABLE wiki_version FIELDS tag_id, version UNIQUE;
+        "#;
+
+    db.query(parse(schema)?).await?;
+
+    Ok(())
+}
+
+/// Every version of the wiki page of the tag `tag_id`, newest first.
+pub async fn get_wiki_versions<C: Connection>(
+    db: &Surreal<C>,
+    tag_id: u64,
+) -> anyhow::Result<Vec<WikiVersion>> {
+    let versions: Vec<WikiVersion> = db
+        .query("SELECT * FROM wiki_version WHERE tag_id = $tag_id ORDER BY version DESC")
+        .bind(("tag_id", tag_id))
+        .await?
+        .take(0)?;
+
+    Ok(versions)
+}
+
+/// Number of the latest version of the page of the tag `tag_id`, 0 if it
+/// was never saved.
+pub async fn latest_wiki_version<C: Connection>(
+    db: &Surreal<C>,
+    tag_id: u64,
+) -> anyhow::Result<u32> {
+    Ok(get_wiki_versions(db, tag_id)
+        .await?
+        .first()
+        .map_or(0, |version| version.version))
+}
+
+/// Strips everything from `html` the wiki markup cannot produce, in case
+/// the renderer lets something through.
+pub fn sanitize_wiki_html(html: &str) -> String {
+    let mut link_classes: HashSet<&str> = TagCategory::ALL
+        .iter()
+        .map(|category| category.colour())
+        .collect();
+    link_classes.insert(MISSING_TAG_CLASS);
+
+    Builder::empty()
+        .tags(HashSet::from(["p", "br", "strong", "em", "a", "img"]))
+        .tag_attributes(HashMap::from([
+            ("a", HashSet::from(["href"])),
+            ("img", HashSet::from(["src", "alt"])),
+        ]))
+        .allowed_classes(HashMap::from([("a", link_classes)]))
+        .url_schemes(HashSet::from(["http", "https"]))
+        .url_relative(UrlRelative::PassThrough)
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .clean(html)
+        .to_string()
+}
+
+/// Renders the wiki markup `body` to sanitized HTML, looking up the tags
+/// and posts it refers to.
+pub async fn render_wiki<C: Connection>(db: &Surreal<C>, body: &str) -> anyhow::Result<String> {
+    let paragraphs = parse_markup(body);
+
+    let tags = resolve_tags_by_names(db, tag_links(&paragraphs)).await?;
+    let posts: Vec<Post> = db
+        .query("SELECT * FROM post WHERE custom_id IN $ids")
+        .bind(("ids", example_posts(&paragraphs)))
+        .await?
+        .take(0)?;
+    let posts = posts
+        .into_iter()
+        .map(|post| (post.custom_id, post))
+        .collect();
+
+    Ok(sanitize_wiki_html(&render_markup(
+        &paragraphs,
+        &tags,
+        &posts,
+    )))
+}
+
+/// The wiki page of the tag called `name`, `None` if there is no such tag.
+/// Tags with a description but no saved versions show the description.
+pub async fn get_wiki_page<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+) -> anyhow::Result<Option<WikiPage>> {
+    let Some(tag) = get_tag_by_name(db, name).await? else {
+        return Ok(None);
+    };
+
+    let versions = get_wiki_versions(db, tag.custom_id).await?;
+    let body = match versions.first() {
+        Some(latest) => latest.body.clone(),
+        None => tag.description.clone(),
+    };
+    let html = render_wiki(db, &body).await?;
+    let authors = versions.iter().map(|version| version.author_id).collect();
+    let user_names = get_user_names(db, authors).await?;
+
+    Ok(Some(WikiPage {
+        tag,
+        body,
+        html,
+        versions,
+        user_names,
+    }))
+}
+
+/// Stores `$wiki_version` and makes its body the description of its tag.
+/// Runs inside a transaction.
+pub const ADD_WIKI_VERSION: &str = r#"
+    CREATE wiki_version CONTENT $wiki_version;
+    UPDATE tag SET description = $wiki_version.body WHERE custom_id = $wiki_version.tag_id;
+"#;
+
+/// `version` of the wiki page of `tag`, ready for `ADD_WIKI_VERSION`.
+pub async fn new_wiki_version<C: Connection>(
+    db: &Surreal<C>,
+    tag: &Tag,
+    version: u32,
+    body: String,
+    reverted_from: Option<u32>,
+    author_id: u64,
+) -> anyhow::Result<WikiVersion> {
+    define_wiki_table(db).await?;
+
+    Ok(WikiVersion {
+        custom_id: get_next_id(db, "wiki_version_counter").await?,
+        tag_id: tag.custom_id,
+        version,
+        body,
+        created_at: now(),
+        reverted_from,
+        author_id,
+    })
+}
+
+/// Saves `body` as the next version of the page of `tag` and makes it the
+/// description of the tag. Returns the new version number.
+async fn add_wiki_version<C: Connection>(
+    db: &Surreal<C>,
+    tag: &Tag,
+    version: u32,
+    body: String,
+    reverted_from: Option<u32>,
+    author_id: u64,
+) -> anyhow::Result<u32> {
+    let wiki_version = new_wiki_version(db, tag, version, body, reverted_from, author_id).await?;
+
+    db.query(format!(
+        "BEGIN TRANSACTION; {} COMMIT TRANSACTION;",
+        ADD_WIKI_VERSION
+    ))
+    .bind(("wiki_version", wiki_version))
+    .await?
+    .check()?;
+
+    Ok(version)
+}
+
+/// Saves `body` as a new version of the page of the tag called `name`.
+/// `base_version` is the version the edit started from; if the page has
+/// changed since, nothing is saved. Saving an unchanged body keeps the
+/// current version. Bodies over `MAX_WIKI_BYTES` or `MAX_WIKI_LINES` are
+/// refused. The version is credited to `author_id`. Failures are
+/// `WikiError`s.
+pub async fn save_wiki_page<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+    body: String,
+    base_version: u32,
+    author_id: u64,
+) -> anyhow::Result<u32> {
+    check_wiki_body(&body)?;
+    let tag = get_tag_by_name(db, name.clone())
+        .await?
+        .ok_or(WikiError::UnknownTag(name))?;
+
+    let versions = get_wiki_versions(db, tag.custom_id).await?;
+    let latest = versions.first().map_or(0, |version| version.version);
+    if base_version != latest {
+        return Err(WikiError::Outdated(latest).into());
+    }
+    if versions.first().is_some_and(|version| version.body == body) {
+        return Ok(latest);
+    }
+
+    add_wiki_version(db, &tag, latest + 1, body, None, author_id).await
+}
+
+/// Restores `version` of the page of the tag called `name` by saving its
+/// body as a new version by `author_id`. Failures are `WikiError`s.
+pub async fn revert_wiki_page<C: Connection>(
+    db: &Surreal<C>,
+    name: String,
+    version: u32,
+    author_id: u64,
+) -> anyhow::Result<u32> {
+    let tag = get_tag_by_name(db, name.clone())
+        .await?
+        .ok_or(WikiError::UnknownTag(name))?;
+
+    let versions = get_wiki_versions(db, tag.custom_id).await?;
+    let latest = versions.first().map_or(0, |version| version.version);
+    let restored = versions
+        .into_iter()
+        .find(|existing| existing.version == version)
+        .ok_or(WikiError::UnknownVersion(version))?;
+
+    add_wiki_version(
+        db,
+        &tag,
+        latest + 1,
+        restored.body,
+        Some(version),
+        author_id,
+    )
+    .await
+}
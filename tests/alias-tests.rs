@@ -0,0 +1,168 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use maerbooru::models::tag::Tag;
+    use maerbooru::server_only::alias::{
+        create_alias, resolve_tag_by_name, resolve_tags_by_names, AliasError,
+    };
+    use maerbooru::server_only::implication::{set_implications, ImplicationError};
+    use maerbooru::server_only::post::{add_new_post, get_paginated_posts, get_post_by_id};
+    use maerbooru::server_only::tag::{add_new_tag, get_tag_by_id, get_tag_by_name};
+
+    use crate::common::{new_alias, new_db, new_tag, test_post};
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn names_resolve_to_canonical_tag() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let kitty = new_alias(&db, "kitty", cat).await;
+
+        let found = get_tag_by_name(&db, "kitty".into()).await.unwrap().unwrap();
+        assert_eq!(found.custom_id, kitty);
+
+        let resolved = resolve_tag_by_name(&db, "kitty".into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.custom_id, cat);
+
+        let resolved = resolve_tags_by_names(&db, vec!["kitty".into(), "cat".into(), "dog".into()])
+            .await
+            .unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved["kitty"].custom_id, cat);
+        assert_eq!(resolved["cat"].custom_id, cat);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn new_aliases_point_at_canonical_tags() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let kitty = new_alias(&db, "kitty", cat).await;
+        let kitten = new_alias(&db, "kitten", kitty).await;
+
+        let kitten = get_tag_by_id(&db, kitten).await.unwrap().unwrap();
+        assert_eq!(kitten.is_alias, Some(cat));
+
+        let tag = Tag {
+            name: String::from("ghost"),
+            is_alias: Some(404),
+            ..Tag::default()
+        };
+        let error = add_new_tag(&db, &tag, 0).await.unwrap_err();
+        assert_eq!(
+            error.downcast::<AliasError>().unwrap(),
+            AliasError::UnknownTag(404)
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn aliasing_migrates_usages() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let animal = new_tag(&db, "animal").await;
+        let kitty = new_tag(&db, "kitty").await;
+        let kitty_alias = new_alias(&db, "kitty_cat", kitty).await;
+        let cute = new_tag(&db, "cute").await;
+        let red = new_tag(&db, "red").await;
+
+        set_implications(&db, cat, vec![animal], false, 0)
+            .await
+            .unwrap();
+        set_implications(&db, cute, vec![kitty], false, 0)
+            .await
+            .unwrap();
+        let post = add_new_post(&db, &test_post(0, vec![kitty, red]))
+            .await
+            .unwrap();
+        add_new_post(&db, &test_post(1, vec![kitty, cat]))
+            .await
+            .unwrap();
+
+        create_alias(&db, kitty, cat, 0).await.unwrap();
+
+        let post = get_post_by_id(&db, post).await.unwrap().unwrap();
+        let mut expected = vec![cat, animal, red];
+        expected.sort();
+        assert_eq!(post.tags, expected);
+
+        let kitty = get_tag_by_id(&db, kitty).await.unwrap().unwrap();
+        assert_eq!((kitty.is_alias, kitty.use_count), (Some(cat), 0));
+
+        // The post that had both only counts once.
+        let cat_tag = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        assert_eq!(cat_tag.use_count, 2);
+
+        let kitty_alias = get_tag_by_id(&db, kitty_alias).await.unwrap().unwrap();
+        assert_eq!(kitty_alias.is_alias, Some(cat));
+
+        let cute = get_tag_by_id(&db, cute).await.unwrap().unwrap();
+        assert_eq!(cute.implications, vec![cat]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn alias_of_itself() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let kitty = new_alias(&db, "kitty", cat).await;
+
+        for target in [cat, kitty] {
+            let error = create_alias(&db, cat, target, 0).await.unwrap_err();
+            assert_eq!(
+                error.downcast::<AliasError>().unwrap(),
+                AliasError::SelfAlias
+            );
+        }
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn aliases_cannot_imply() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let animal = new_tag(&db, "animal").await;
+        let kitty = new_alias(&db, "kitty", cat).await;
+
+        let error = set_implications(&db, kitty, vec![animal], false, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<ImplicationError>().unwrap(),
+            ImplicationError::Alias(kitty)
+        );
+
+        let kitty = get_tag_by_id(&db, kitty).await.unwrap().unwrap();
+        assert!(kitty.implications.is_empty());
+        let cat = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        assert!(cat.implications.is_empty());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_by_alias() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        new_alias(&db, "kitty", cat).await;
+
+        let post = add_new_post(&db, &test_post(0, vec![cat])).await.unwrap();
+
+        let found: Vec<u64> = get_paginated_posts(&db, 1, 10, Some("kitty".into()))
+            .await
+            .unwrap()
+            .iter()
+            .map(|post| post.custom_id)
+            .collect();
+        assert_eq!(found, vec![post]);
+
+        let found = get_paginated_posts(&db, 1, 10, Some("-kitty".into()))
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+//! Fixtures shared by the integration tests. Every test file is its own
+//! crate and pulls this in with `mod common;`, so not all of them use every
+//! helper.
+#![allow(dead_code)]
+
+use surrealdb::engine::local::{Db, Mem};
+use surrealdb::Surreal;
+
+use maerbooru::models::post::{Post, PostType, Safety};
+use maerbooru::models::tag::Tag;
+use maerbooru::server_only::tag::add_new_tag;
+
+/// An 800×600 PNG post with the given tags. `hash_byte` fills the hashes,
+/// so posts in one database need different ones.
+pub fn test_post(hash_byte: u8, tags: Vec<u64>) -> Post {
+    Post {
+        custom_id: 0,
+        image_height: 600,
+        image_width: 800,
+        mime_type: mime::IMAGE_PNG,
+        post_type: PostType::Image,
+        safety: Safety::Safe,
+        sha256_hash: [hash_byte; 32],
+        md5_hash: [0; 16],
+        uploader_id: 0,
+        tags,
+        duration: None,
+        has_audio: false,
+        frame_count: Some(1),
+        animated: false,
+        file_size: 1234,
+        uploaded_at: 1_700_000_000,
+        score: 0,
+    }
+}
+
+/// An empty in-memory database.
+pub async fn new_db() -> Surreal<Db> {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    db
+}
+
+/// Creates a general tag called `name` and returns its id.
+pub async fn new_tag(db: &Surreal<Db>, name: &str) -> u64 {
+    let tag = Tag {
+        name: String::from(name),
+        ..Tag::default()
+    };
+    add_new_tag(db, &tag, 0).await.unwrap()
+}
+
+/// Creates `name` as an alias of the tag `target` and returns its id.
+pub async fn new_alias(db: &Surreal<Db>, name: &str, target: u64) -> u64 {
+    let tag = Tag {
+        name: String::from(name),
+        is_alias: Some(target),
+        ..Tag::default()
+    };
+    add_new_tag(db, &tag, 0).await.unwrap()
+}
@@ -0,0 +1,19 @@
+use maerbooru::models::date::{civil_from_days, format_timestamp};
+
+#[test]
+fn unix_epoch() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+}
+
+#[test]
+fn leap_day() {
+    // 2024-02-29 12:34:56 UTC
+    assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34 UTC");
+}
+
+#[test]
+fn end_of_year() {
+    // 1999-12-31 23:59:59 UTC
+    assert_eq!(format_timestamp(946_684_799), "1999-12-31 23:59 UTC");
+}
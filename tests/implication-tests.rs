@@ -0,0 +1,170 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use surrealdb::engine::local::Db;
+    use surrealdb::Surreal;
+
+    use maerbooru::server_only::implication::{
+        set_implications, with_implied_tags, ImplicationError,
+    };
+    use maerbooru::server_only::post::{add_new_post, get_post_by_id, set_post_tags};
+
+    use crate::common::{new_db, new_tag, test_post};
+
+    /// `cat_ears` ⇒ `animal_ears` ⇒ `ears`, plus an unrelated `red`.
+    async fn ears(db: &Surreal<Db>) -> [u64; 4] {
+        let cat_ears = new_tag(db, "cat_ears").await;
+        let animal_ears = new_tag(db, "animal_ears").await;
+        let ears = new_tag(db, "ears").await;
+        let red = new_tag(db, "red").await;
+
+        set_implications(db, cat_ears, vec![animal_ears], false, 0)
+            .await
+            .unwrap();
+        set_implications(db, animal_ears, vec![ears], false, 0)
+            .await
+            .unwrap();
+
+        [cat_ears, animal_ears, ears, red]
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn transitive_closure() {
+        let db = new_db().await;
+        let [cat_ears, animal_ears, ears, red] = ears(&db).await;
+
+        let mut expected = vec![cat_ears, animal_ears, ears, red];
+        expected.sort();
+        assert_eq!(
+            with_implied_tags(&db, &[red, cat_ears]).await.unwrap(),
+            expected
+        );
+        assert_eq!(with_implied_tags(&db, &[ears]).await.unwrap(), vec![ears]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn diamond_without_duplicates() {
+        let db = new_db().await;
+        let top = new_tag(&db, "top").await;
+        let left = new_tag(&db, "left").await;
+        let right = new_tag(&db, "right").await;
+        let bottom = new_tag(&db, "bottom").await;
+
+        set_implications(&db, top, vec![left, right, left], false, 0)
+            .await
+            .unwrap();
+        set_implications(&db, left, vec![bottom], false, 0)
+            .await
+            .unwrap();
+        set_implications(&db, right, vec![bottom], false, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            with_implied_tags(&db, &[top]).await.unwrap(),
+            vec![top, left, right, bottom]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn tagging_adds_implied_tags() {
+        let db = new_db().await;
+        let [cat_ears, animal_ears, ears, red] = ears(&db).await;
+
+        let created = add_new_post(&db, &test_post(0, vec![cat_ears]))
+            .await
+            .unwrap();
+        let post = get_post_by_id(&db, created).await.unwrap().unwrap();
+        assert_eq!(post.tags, vec![cat_ears, animal_ears, ears]);
+
+        let tags = set_post_tags(&db, created, vec![red, animal_ears], 0)
+            .await
+            .unwrap();
+        assert_eq!(tags, Some(vec![animal_ears, ears, red]));
+
+        assert_eq!(set_post_tags(&db, 999, vec![red], 0).await.unwrap(), None);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn cycles_are_rejected() {
+        let db = new_db().await;
+        let [cat_ears, animal_ears, ears, _] = ears(&db).await;
+
+        for (tag, implications) in [
+            (ears, vec![cat_ears]),
+            (animal_ears, vec![cat_ears]),
+            (cat_ears, vec![cat_ears]),
+        ] {
+            let error = set_implications(&db, tag, implications, false, 0)
+                .await
+                .unwrap_err();
+            assert_eq!(
+                error.downcast::<ImplicationError>().unwrap(),
+                ImplicationError::Cycle(tag)
+            );
+        }
+
+        // The failed edits left the graph alone.
+        assert_eq!(with_implied_tags(&db, &[ears]).await.unwrap(), vec![ears]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn unknown_tags_are_rejected() {
+        let db = new_db().await;
+        let [cat_ears, ..] = ears(&db).await;
+
+        let error = set_implications(&db, cat_ears, vec![404], false, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<ImplicationError>().unwrap(),
+            ImplicationError::UnknownTag(404)
+        );
+
+        let error = set_implications(&db, 404, vec![cat_ears], false, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<ImplicationError>().unwrap(),
+            ImplicationError::UnknownTag(404)
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn back_apply_to_existing_posts() {
+        let db = new_db().await;
+        let [cat_ears, animal_ears, ears, red] = ears(&db).await;
+        let ribbon = new_tag(&db, "ribbon").await;
+
+        let tagged = add_new_post(&db, &test_post(0, vec![red])).await.unwrap();
+        let untagged = add_new_post(&db, &test_post(1, vec![cat_ears]))
+            .await
+            .unwrap();
+
+        // Without back-applying, only later tagging picks it up.
+        set_implications(&db, red, vec![ribbon], false, 0)
+            .await
+            .unwrap();
+        let post = get_post_by_id(&db, tagged).await.unwrap().unwrap();
+        assert_eq!(post.tags, vec![red]);
+
+        set_implications(&db, red, vec![ribbon, cat_ears], true, 0)
+            .await
+            .unwrap();
+        let post = get_post_by_id(&db, tagged).await.unwrap().unwrap();
+        let mut expected = vec![cat_ears, animal_ears, ears, red, ribbon];
+        expected.sort();
+        assert_eq!(post.tags, expected);
+
+        let post = get_post_by_id(&db, untagged).await.unwrap().unwrap();
+        assert_eq!(post.tags, vec![cat_ears, animal_ears, ears]);
+    }
+}
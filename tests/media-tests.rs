@@ -0,0 +1,289 @@
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use std::io::Cursor;
+
+    use maerbooru::server_only::media::{frame_count, image_dimensions, sniff_mime};
+
+    // Just enough of each format for the header parsers: signature plus the
+    // chunk/box that carries the image size (120x80 in all of them).
+    const PNG: &[u8] = &[
+        0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, // signature
+        0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D', b'R', // IHDR chunk
+        0x00, 0x00, 0x00, 0x78, 0x00, 0x00, 0x00, 0x50, // width, height
+        0x08, 0x06, 0x00, 0x00, 0x00, // depth, colour type, compression, filter, interlace
+        0x00, 0x00, 0x00, 0x00, // crc
+    ];
+
+    const JPEG: &[u8] = &[
+        0xFF, 0xD8, // SOI
+        0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0x00, 0x01, 0x01, 0x00, 0x00, 0x01, 0x00,
+        0x01, 0x00, 0x00, // APP0
+        0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x50, 0x00, 0x78, 0x03, 0x01, 0x22, 0x00, 0x02, 0x11,
+        0x01, 0x03, 0x11, 0x01, // SOF0, height then width
+        0xFF, 0xD9, // EOI
+    ];
+
+    const WEBP: &[u8] = &[
+        b'R', b'I', b'F', b'F', 0x16, 0x00, 0x00, 0x00, b'W', b'E', b'B', b'P', // RIFF header
+        b'V', b'P', b'8', b'X', 0x0A, 0x00, 0x00, 0x00, // VP8X chunk
+        0x00, 0x00, 0x00, 0x00, // flags
+        0x77, 0x00, 0x00, 0x4F, 0x00, 0x00, // width - 1, height - 1
+    ];
+
+    const AVIF: &[u8] = &[
+        0x00, 0x00, 0x00, 0x1C, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f', // ftyp
+        0x00, 0x00, 0x00, 0x00, b'a', b'v', b'i', b'f', b'm', b'i', b'f', b'1', b'm', b'i', b'a',
+        b'f', // minor version, compatible brands
+        0x00, 0x00, 0x00, 0x14, b'i', b's', b'p', b'e', 0x00, 0x00, 0x00, 0x00, // ispe
+        0x00, 0x00, 0x00, 0x78, 0x00, 0x00, 0x00, 0x50, // width, height
+    ];
+
+    const GIF: &[u8] = &[
+        b'G', b'I', b'F', b'8', b'9', b'a', // signature
+        0x78, 0x00, 0x50, 0x00, // width, height
+        0x00, 0x00, 0x00, // flags, background, aspect ratio
+        0x3B, // trailer
+    ];
+
+    const WEBP_LOSSLESS: &[u8] = &[
+        b'R', b'I', b'F', b'F', 0x0E, 0x00, 0x00, 0x00, b'W', b'E', b'B', b'P', // RIFF header
+        b'V', b'P', b'8', b'L', 0x05, 0x00, 0x00, 0x00, // VP8L chunk
+        0x2F, 0x77, 0xC0, 0x13, 0x00, // signature, packed width - 1 and height - 1
+    ];
+
+    const WEBP_LOSSY: &[u8] = &[
+        b'R', b'I', b'F', b'F', 0x12, 0x00, 0x00, 0x00, b'W', b'E', b'B', b'P', // RIFF header
+        b'V', b'P', b'8', b' ', 0x0A, 0x00, 0x00, 0x00, // VP8 chunk
+        0x00, 0x00, 0x00, 0x9D, 0x01, 0x2A, // frame tag, start code
+        0x78, 0x00, 0x50, 0x00, // width, height
+    ];
+
+    const ELF: &[u8] = &[
+        0x7F, b'E', b'L', b'F', 0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ];
+
+    const MP4: &[u8] = &[
+        0x00, 0x00, 0x00, 0x18, b'f', b't', b'y', b'p', b'i', b's', b'o', b'm', 0x00, 0x00, 0x02,
+        0x00, b'i', b's', b'o', b'm', b'm', b'p', b'4', b'1',
+    ];
+
+    const WEBM: &[u8] = &[
+        0x1A, 0x45, 0xDF, 0xA3, 0x9F, // EBML header
+        0x42, 0x86, 0x81, 0x01, 0x42, 0xF7, 0x81, 0x01, 0x42, 0xF2, 0x81, 0x04, 0x42, 0xF3, 0x81,
+        0x08, // versions and limits
+        0x42, 0x82, 0x84, b'w', b'e', b'b', b'm', // DocType
+        0x42, 0x87, 0x81, 0x04, 0x42, 0x85, 0x81, 0x02, // DocType versions
+    ];
+
+    const HEIC: &[u8] = &[
+        0x00, 0x00, 0x00, 0x18, b'f', b't', b'y', b'p', b'h', b'e', b'i', b'c', 0x00, 0x00, 0x00,
+        0x00, b'm', b'i', b'f', b'1', b'h', b'e', b'i', b'c',
+    ];
+
+    #[test]
+    fn sniff_png() {
+        assert_eq!(sniff_mime(PNG), Some(mime::IMAGE_PNG));
+    }
+
+    #[test]
+    fn sniff_jpeg() {
+        assert_eq!(sniff_mime(JPEG), Some(mime::IMAGE_JPEG));
+    }
+
+    #[test]
+    fn sniff_webp() {
+        assert_eq!(sniff_mime(WEBP), Some("image/webp".parse().unwrap()));
+    }
+
+    #[test]
+    fn sniff_avif() {
+        assert_eq!(sniff_mime(AVIF), Some("image/avif".parse().unwrap()));
+    }
+
+    #[test]
+    fn sniff_avif_compatible_brand() {
+        let mut avif = AVIF.to_vec();
+        avif[8..12].copy_from_slice(b"mif1");
+        assert_eq!(sniff_mime(&avif), Some("image/avif".parse().unwrap()));
+    }
+
+    #[test]
+    fn sniff_gif() {
+        assert_eq!(sniff_mime(GIF), Some(mime::IMAGE_GIF));
+    }
+
+    #[test]
+    fn sniff_mp4() {
+        assert_eq!(sniff_mime(MP4), Some("video/mp4".parse().unwrap()));
+    }
+
+    #[test]
+    fn sniff_webm() {
+        assert_eq!(sniff_mime(WEBM), Some("video/webm".parse().unwrap()));
+    }
+
+    #[test]
+    fn reject_unknown_content() {
+        assert_eq!(sniff_mime(ELF), None);
+        assert_eq!(sniff_mime(HEIC), None);
+        assert_eq!(sniff_mime(b"just some text"), None);
+        assert_eq!(sniff_mime(&[]), None);
+    }
+
+    #[test]
+    fn reject_truncated_headers() {
+        assert_eq!(sniff_mime(&PNG[..4]), None);
+        assert_eq!(sniff_mime(&WEBP[..10]), None);
+        assert_eq!(sniff_mime(&AVIF[..8]), None);
+        assert_eq!(sniff_mime(&WEBM[..4]), None);
+    }
+
+    #[test]
+    fn dimensions_of_each_format() {
+        for fixture in [PNG, JPEG, GIF, WEBP, WEBP_LOSSLESS, WEBP_LOSSY, AVIF] {
+            assert_eq!(image_dimensions(fixture), Some((120, 80)));
+        }
+    }
+
+    #[test]
+    fn jpeg_dimensions_after_other_segments() {
+        // An EXIF segment and some fill bytes in front of the frame header.
+        let mut jpeg = vec![
+            0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x08, b'E', b'x', b'i', b'f', 0, 0,
+        ];
+        jpeg.extend_from_slice(&[0xFF, 0xFF]);
+        jpeg.extend_from_slice(&JPEG[2..]);
+        assert_eq!(image_dimensions(&jpeg), Some((120, 80)));
+    }
+
+    #[test]
+    fn no_dimensions_for_broken_headers() {
+        assert_eq!(image_dimensions(&PNG[..20]), None);
+        assert_eq!(image_dimensions(&JPEG[..22]), None);
+        assert_eq!(image_dimensions(&WEBP[..26]), None);
+        assert_eq!(image_dimensions(&AVIF[..40]), None);
+        assert_eq!(image_dimensions(ELF), None);
+    }
+
+    #[test]
+    fn no_dimensions_for_empty_images() {
+        let mut png = PNG.to_vec();
+        png[16..20].copy_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(image_dimensions(&png), None);
+    }
+
+    /// Encodes a GIF with one solid frame per colour.
+    fn animated_gif(colours: &[[u8; 4]]) -> Vec<u8> {
+        let mut gif = vec![];
+        {
+            let mut encoder = image::codecs::gif::GifEncoder::new(&mut gif);
+            let frames = colours.iter().map(|colour| {
+                image::Frame::new(image::RgbaImage::from_pixel(4, 4, image::Rgba(*colour)))
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+        gif
+    }
+
+    fn png_chunk(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut chunk = (payload.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(payload);
+        chunk.extend_from_slice(&[0; 4]); // crc, not checked
+        chunk
+    }
+
+    fn riff_chunk(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut chunk = kind.to_vec();
+        chunk.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    #[test]
+    fn gif_frames() {
+        let still = animated_gif(&[[255, 0, 0, 255]]);
+        let animated = animated_gif(&[[255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255]]);
+
+        assert_eq!(frame_count(&mime::IMAGE_GIF, Cursor::new(still)), Some(1));
+        assert_eq!(
+            frame_count(&mime::IMAGE_GIF, Cursor::new(animated)),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn gif_without_trailer() {
+        let animated = animated_gif(&[[255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255]]);
+        assert_eq!(animated.last(), Some(&0x3B));
+
+        let without_trailer = animated[..animated.len() - 1].to_vec();
+        assert_eq!(
+            frame_count(&mime::IMAGE_GIF, Cursor::new(without_trailer)),
+            Some(3)
+        );
+
+        // Cut off in the middle of the last frame.
+        let truncated = animated[..animated.len() - 4].to_vec();
+        assert_eq!(
+            frame_count(&mime::IMAGE_GIF, Cursor::new(truncated)),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn gif_without_frames() {
+        assert_eq!(frame_count(&mime::IMAGE_GIF, Cursor::new(GIF)), None);
+    }
+
+    #[test]
+    fn apng_frames() {
+        let apng = [
+            PNG,
+            &png_chunk(b"acTL", &[0, 0, 0, 3, 0, 0, 0, 0]),
+            &png_chunk(b"IDAT", &[0; 8]),
+            &png_chunk(b"IEND", &[]),
+        ]
+        .concat();
+
+        assert_eq!(frame_count(&mime::IMAGE_PNG, Cursor::new(apng)), Some(3));
+    }
+
+    #[test]
+    fn png_frames() {
+        let png = [PNG, &png_chunk(b"IDAT", &[0; 8]), &png_chunk(b"IEND", &[])].concat();
+
+        assert_eq!(frame_count(&mime::IMAGE_PNG, Cursor::new(png)), Some(1));
+    }
+
+    #[test]
+    fn webp_frames() {
+        let webp_mime: mime::Mime = "image/webp".parse().unwrap();
+        let frame = riff_chunk(b"ANMF", &[0; 17]);
+        let chunks = [
+            riff_chunk(b"VP8X", &[0x02, 0, 0, 0, 0x77, 0, 0, 0x4F, 0, 0]),
+            riff_chunk(b"ANIM", &[0; 6]),
+            frame.clone(),
+            frame,
+        ]
+        .concat();
+        let mut animated = b"RIFF".to_vec();
+        animated.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+        animated.extend_from_slice(b"WEBP");
+        animated.extend_from_slice(&chunks);
+
+        assert_eq!(frame_count(&webp_mime, Cursor::new(animated)), Some(2));
+        assert_eq!(frame_count(&webp_mime, Cursor::new(WEBP)), Some(1));
+    }
+
+    #[test]
+    fn still_formats_have_one_frame() {
+        assert_eq!(frame_count(&mime::IMAGE_JPEG, Cursor::new(JPEG)), Some(1));
+        let avif_mime: mime::Mime = "image/avif".parse().unwrap();
+        assert_eq!(frame_count(&avif_mime, Cursor::new(AVIF)), Some(1));
+    }
+}
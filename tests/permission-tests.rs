@@ -0,0 +1,97 @@
+use leptos::ServerFnError;
+use maerbooru::error_template::{server_error_message, AppError};
+use maerbooru::models::permission::{
+    Permission, PermissionConfigError, PermissionError, PermissionMatrix, Role, UnknownRole,
+    FORBIDDEN_PREFIX,
+};
+
+#[test]
+fn roles() {
+    assert!(Role::Anonymous < Role::Member && Role::Moderator < Role::Admin);
+    assert_eq!(Role::default(), Role::Member);
+    for role in Role::ALL {
+        assert_eq!(role.name().parse(), Ok(role));
+        assert_eq!(Role::try_from(u8::from(role)), Ok(role));
+    }
+    assert_eq!(" Janitor ".parse(), Ok(Role::Janitor));
+    assert_eq!("boss".parse::<Role>(), Err(UnknownRole("boss".into())));
+    assert!(Role::try_from(6).is_err());
+}
+
+#[test]
+fn default_matrix() {
+    let matrix = PermissionMatrix::default();
+
+    for permission in Permission::ALL {
+        assert!(matrix.check(Role::Anonymous, permission).is_err());
+        assert_eq!(matrix.check(Role::Admin, permission), Ok(()));
+    }
+    assert_eq!(matrix.check(Role::Member, Permission::UploadPost), Ok(()));
+    assert_eq!(matrix.check(Role::Member, Permission::EditWiki), Ok(()));
+    assert!(matrix.check(Role::Member, Permission::EditTag).is_err());
+    assert_eq!(matrix.check(Role::Janitor, Permission::MergeTags), Ok(()));
+    assert_eq!(
+        matrix.check(Role::Janitor, Permission::DeleteTag),
+        Err(PermissionError {
+            permission: Permission::DeleteTag,
+            required: Role::Moderator,
+        })
+    );
+    assert!(matrix
+        .check(Role::Moderator, Permission::ManageUsers)
+        .is_err());
+}
+
+#[test]
+fn overrides() {
+    let matrix = PermissionMatrix::default()
+        .with_overrides(" upload_post = anonymous, delete_tag=janitor,")
+        .unwrap();
+    assert_eq!(matrix.required(Permission::UploadPost), Role::Anonymous);
+    assert_eq!(matrix.required(Permission::DeleteTag), Role::Janitor);
+    assert_eq!(matrix.required(Permission::RenameTag), Role::Janitor);
+
+    assert_eq!(
+        PermissionMatrix::default().with_overrides(""),
+        Ok(PermissionMatrix::default())
+    );
+    assert_eq!(
+        PermissionMatrix::default().with_overrides("upload_post"),
+        Err(PermissionConfigError::Malformed("upload_post".into()))
+    );
+    assert_eq!(
+        PermissionMatrix::default().with_overrides("fly=admin"),
+        Err(PermissionConfigError::UnknownPermission("fly".into()))
+    );
+    assert_eq!(
+        PermissionMatrix::default().with_overrides("upload_post=boss"),
+        Err(PermissionConfigError::UnknownRole(UnknownRole(
+            "boss".into()
+        )))
+    );
+}
+
+#[test]
+fn forbidden_messages() {
+    let error = PermissionError {
+        permission: Permission::DeleteTag,
+        required: Role::Moderator,
+    };
+    assert_eq!(
+        error.to_string(),
+        "Forbidden: you need to be moderator or higher to delete tags"
+    );
+    assert!(error.to_string().starts_with(FORBIDDEN_PREFIX));
+
+    let forbidden: ServerFnError = ServerFnError::ServerError(error.to_string());
+    let app_error = AppError::from_server_error(&forbidden).unwrap();
+    assert_eq!(app_error.status_code(), http::StatusCode::FORBIDDEN);
+    assert_eq!(server_error_message(&forbidden), error.to_string());
+
+    let other: ServerFnError = ServerFnError::ServerError("database down".into());
+    assert!(AppError::from_server_error(&other).is_none());
+    assert_eq!(
+        server_error_message(&other),
+        "Error: error running server function: database down"
+    );
+}
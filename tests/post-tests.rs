@@ -0,0 +1,174 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use maerbooru::models::post::Post;
+    use maerbooru::server_only::post::add_new_post;
+    use maerbooru::server_only::post::define_post_table;
+    use maerbooru::server_only::post::delete_post;
+    use maerbooru::server_only::post::get_paginated_posts;
+    use maerbooru::server_only::post::get_post_by_hash;
+    use maerbooru::server_only::post::get_post_by_id;
+    use maerbooru::server_only::post::MAX_PER_PAGE;
+
+    use maerbooru::server_only::tag::{get_tag_by_id, get_tag_history};
+
+    use crate::common::{new_db, new_tag, test_post};
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn create_and_find_post_by_id() {
+        let db = new_db().await;
+
+        define_post_table(&db).await.unwrap();
+
+        let post = test_post(0xAB, vec![]);
+        let new_post_id = add_new_post(&db, &post).await.unwrap();
+
+        let found_post = get_post_by_id(&db, new_post_id)
+            .await
+            .unwrap()
+            .expect("post should exist by now");
+
+        assert_eq!(
+            Post {
+                custom_id: found_post.custom_id,
+                ..post
+            },
+            found_post
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn create_and_find_post_by_hash() {
+        let db = new_db().await;
+
+        define_post_table(&db).await.unwrap();
+
+        let first_id = add_new_post(&db, &test_post(1, vec![])).await.unwrap();
+        let second_id = add_new_post(&db, &test_post(2, vec![])).await.unwrap();
+        assert_ne!(first_id, second_id);
+
+        let found_post = get_post_by_hash(&db, &[2; 32])
+            .await
+            .unwrap()
+            .expect("post should exist by now");
+        assert_eq!(found_post.custom_id, second_id);
+
+        assert!(get_post_by_hash(&db, &[3; 32]).await.unwrap().is_none());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn same_hash_twice() {
+        let db = new_db().await;
+
+        define_post_table(&db).await.unwrap();
+
+        add_new_post(&db, &test_post(7, vec![])).await.unwrap();
+
+        if (add_new_post(&db, &test_post(7, vec![])).await).is_ok() {
+            panic!("adding the same file twice should have failed")
+        }
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn list_posts_newest_first() {
+        let db = new_db().await;
+
+        define_post_table(&db).await.unwrap();
+
+        let mut ids = vec![];
+        for hash_byte in 0..5 {
+            ids.push(
+                add_new_post(&db, &test_post(hash_byte, vec![]))
+                    .await
+                    .unwrap(),
+            );
+        }
+        ids.reverse();
+
+        let first_page: Vec<u64> = get_paginated_posts(&db, 1, 2, None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|post| post.custom_id)
+            .collect();
+        assert_eq!(first_page, ids[0..2]);
+
+        let last_page: Vec<u64> = get_paginated_posts(&db, 3, 2, None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|post| post.custom_id)
+            .collect();
+        assert_eq!(last_page, ids[4..5]);
+
+        assert!(get_paginated_posts(&db, 4, 2, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn page_zero_and_oversized_pages() {
+        let db = new_db().await;
+
+        let mut ids = vec![];
+        for hash_byte in 0..=MAX_PER_PAGE as u8 {
+            ids.push(
+                add_new_post(&db, &test_post(hash_byte, vec![]))
+                    .await
+                    .unwrap(),
+            );
+        }
+        ids.reverse();
+
+        let first_page: Vec<u64> = get_paginated_posts(&db, 0, 2, None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|post| post.custom_id)
+            .collect();
+        assert_eq!(first_page, ids[0..2]);
+
+        let everything = get_paginated_posts(&db, 1, u32::MAX, None).await.unwrap();
+        assert_eq!(everything.len(), MAX_PER_PAGE as usize);
+
+        let past_the_end = get_paginated_posts(&db, u32::MAX, u32::MAX, None)
+            .await
+            .unwrap();
+        assert!(past_the_end.is_empty());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn deleting_a_new_post_undoes_it() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let kept = add_new_post(&db, &test_post(0, vec![cat])).await.unwrap();
+        let post = add_new_post(&db, &test_post(1, vec![cat])).await.unwrap();
+        assert_eq!(get_tag_by_id(&db, cat).await.unwrap().unwrap().use_count, 2);
+
+        delete_post(&db, post).await.unwrap();
+
+        assert!(get_post_by_id(&db, post).await.unwrap().is_none());
+        assert!(get_post_by_id(&db, kept).await.unwrap().is_some());
+        assert_eq!(get_tag_by_id(&db, cat).await.unwrap().unwrap().use_count, 1);
+        let post_ids: Vec<Option<u64>> = db
+            .query("SELECT VALUE post_id FROM tag_history WHERE post_id != NONE")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(post_ids, vec![Some(kept)]);
+        assert_eq!(get_tag_history(&db, cat).await.unwrap().len(), 1);
+
+        // The same file can be uploaded again.
+        add_new_post(&db, &test_post(1, vec![cat])).await.unwrap();
+    }
+}
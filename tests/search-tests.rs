@@ -0,0 +1,777 @@
+use std::collections::HashSet;
+
+use maerbooru::models::metatag::{Bound, Filter, Order, Range};
+use maerbooru::models::search::{
+    parse_search, parse_search_with_tags, unusual_terms, Clause, SearchError, SearchErrorKind, Term,
+};
+
+fn clause(term: Term, negated: bool, start: usize, end: usize) -> Clause {
+    Clause {
+        term,
+        negated,
+        start,
+        end,
+    }
+}
+
+fn rating(safety: &str) -> Term {
+    Term::Metatag(Filter::Equals {
+        field: "safety",
+        value: safety.into(),
+    })
+}
+
+/// The filter of a query consisting of a single metatag.
+fn filter(query: &str) -> Filter {
+    match parse_search(query).unwrap().all.remove(0).term {
+        Term::Metatag(filter) => filter,
+        term => panic!("expected a metatag, got {:?}", term),
+    }
+}
+
+fn range(field: &'static str, min: Option<(f64, bool)>, max: Option<(f64, bool)>) -> Filter {
+    let bound = |(value, inclusive)| Bound { value, inclusive };
+    Filter::Range {
+        field,
+        range: Range {
+            min: min.map(bound),
+            max: max.map(bound),
+        },
+    }
+}
+
+#[test]
+fn empty_query() {
+    let search = parse_search("   ").unwrap();
+    assert!(search.all.is_empty());
+    assert!(search.any.is_empty());
+}
+
+#[test]
+fn conjunction_negation_and_or_group() {
+    let search = parse_search("cat_ears -dog ~red ~blue rating:safe").unwrap();
+
+    assert_eq!(
+        search.all,
+        vec![
+            clause(Term::Tag("cat_ears".into()), false, 0, 8),
+            clause(Term::Tag("dog".into()), true, 9, 13),
+            clause(rating("Safe"), false, 25, 36),
+        ]
+    );
+    assert_eq!(
+        search.any,
+        vec![
+            clause(Term::Tag("red".into()), false, 14, 18),
+            clause(Term::Tag("blue".into()), false, 19, 24),
+        ]
+    );
+}
+
+#[test]
+fn terms_are_lowercased() {
+    let search = parse_search("Cat_Ears RATING:Unsafe -ANIMATED:YES").unwrap();
+
+    let terms: Vec<(Term, bool)> = search
+        .all
+        .into_iter()
+        .map(|clause| (clause.term, clause.negated))
+        .collect();
+    assert_eq!(
+        terms,
+        vec![
+            (Term::Tag("cat_ears".into()), false),
+            (rating("Unsafe"), false),
+            (
+                Term::Metatag(Filter::Flag {
+                    field: "animated",
+                    value: true
+                }),
+                true
+            ),
+        ]
+    );
+}
+
+#[test]
+fn tag_names_with_qualifiers() {
+    let search = parse_search("lain_(serial_experiments_lain) see-through").unwrap();
+
+    assert_eq!(
+        search.all[0].term,
+        Term::Tag("lain_(serial_experiments_lain)".into())
+    );
+    assert_eq!(search.all[1].term, Term::Tag("see-through".into()));
+}
+
+#[test]
+fn lone_operator() {
+    assert_eq!(
+        parse_search("cat -"),
+        Err(SearchError::new(SearchErrorKind::MissingTerm, 4, 5))
+    );
+    assert_eq!(
+        parse_search("~"),
+        Err(SearchError::new(SearchErrorKind::MissingTerm, 0, 1))
+    );
+}
+
+#[test]
+fn combined_operators() {
+    assert_eq!(
+        parse_search("cat ~-dog"),
+        Err(SearchError::new(SearchErrorKind::CombinedOperators, 4, 6))
+    );
+    assert_eq!(
+        parse_search("--dog"),
+        Err(SearchError::new(SearchErrorKind::CombinedOperators, 0, 2))
+    );
+}
+
+#[test]
+fn invalid_character() {
+    assert_eq!(
+        parse_search("cat d\"og"),
+        Err(SearchError::new(
+            SearchErrorKind::InvalidCharacter('"'),
+            5,
+            6
+        ))
+    );
+    assert_eq!(
+        parse_search("ça"),
+        Err(SearchError::new(
+            SearchErrorKind::InvalidCharacter('ç'),
+            0,
+            2
+        ))
+    );
+}
+
+#[test]
+fn wildcard() {
+    assert_eq!(
+        parse_search("cat_*"),
+        Err(SearchError::new(SearchErrorKind::Wildcard, 4, 5))
+    );
+}
+
+#[test]
+fn invalid_rating() {
+    assert_eq!(
+        parse_search("cat -rating:nope"),
+        Err(SearchError::new(
+            SearchErrorKind::InvalidValue {
+                metatag: "rating".into(),
+                value: "nope".into(),
+                expected: "`s`, `q` or `e`".into(),
+            },
+            12,
+            16
+        ))
+    );
+}
+
+#[test]
+fn positions_after_unicode_whitespace() {
+    // U+3000 is three bytes long.
+    assert_eq!(
+        parse_search("cat\u{3000}d*g"),
+        Err(SearchError::new(SearchErrorKind::Wildcard, 7, 8))
+    );
+}
+
+#[test]
+fn too_many_terms() {
+    let query = vec!["tag"; 33].join(" ");
+
+    assert_eq!(
+        parse_search(&query),
+        Err(SearchError::new(SearchErrorKind::TooManyTerms, 128, 131))
+    );
+}
+
+#[test]
+fn error_display_has_position() {
+    let error = parse_search("cat_*").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "wildcards are not supported in post search (at 4..5)"
+    );
+}
+
+#[test]
+fn rating_short_forms() {
+    assert_eq!(
+        parse_search("rating:s").unwrap().all[0].term,
+        rating("Safe")
+    );
+    assert_eq!(
+        parse_search("rating:q").unwrap().all[0].term,
+        rating("Sketchy")
+    );
+    assert_eq!(
+        parse_search("rating:e").unwrap().all[0].term,
+        rating("Unsafe")
+    );
+}
+
+#[test]
+fn numeric_comparisons() {
+    assert_eq!(
+        filter("width:1000"),
+        range("image_width", Some((1000.0, true)), Some((1000.0, true)))
+    );
+    assert_eq!(
+        filter("width:>=1000"),
+        range("image_width", Some((1000.0, true)), None)
+    );
+    assert_eq!(
+        filter("height:>1000"),
+        range("image_height", Some((1000.0, false)), None)
+    );
+    assert_eq!(
+        filter("height:<=50"),
+        range("image_height", None, Some((50.0, true)))
+    );
+    assert_eq!(
+        filter("score:<-5"),
+        range("score", None, Some((-5.0, false)))
+    );
+    assert_eq!(
+        filter("score:-5..10"),
+        range("score", Some((-5.0, true)), Some((10.0, true)))
+    );
+    assert_eq!(
+        filter("score:10.."),
+        range("score", Some((10.0, true)), None)
+    );
+}
+
+#[test]
+fn scaled_values() {
+    assert_eq!(
+        filter("mpixels:>2.5"),
+        range(
+            "image_width * image_height",
+            Some((2_500_000.0, false)),
+            None
+        )
+    );
+    assert_eq!(
+        filter("filesize:500kb..2mb"),
+        range(
+            "file_size",
+            Some((512_000.0, true)),
+            Some((2_097_152.0, true))
+        )
+    );
+    assert_eq!(
+        filter("filesize:<100"),
+        range("file_size", None, Some((100.0, false)))
+    );
+}
+
+#[test]
+fn dates_cover_whole_periods() {
+    // 2026-01-01 and 2026-07-01 00:00 UTC.
+    assert_eq!(
+        filter("date:2026-01..2026-06"),
+        range(
+            "uploaded_at",
+            Some((1_767_225_600.0, true)),
+            Some((1_782_864_000.0, false))
+        )
+    );
+    // 2024-02-29 and 2024-03-01 00:00 UTC.
+    assert_eq!(
+        filter("date:2024-02-29"),
+        range(
+            "uploaded_at",
+            Some((1_709_164_800.0, true)),
+            Some((1_709_251_200.0, false))
+        )
+    );
+    // After the whole of 2025, so from 2026-01-01.
+    assert_eq!(
+        filter("date:>2025"),
+        range("uploaded_at", Some((1_767_225_600.0, true)), None)
+    );
+    assert_eq!(
+        filter("date:2025-12"),
+        range(
+            "uploaded_at",
+            Some((1_764_547_200.0, true)),
+            Some((1_767_225_600.0, false))
+        )
+    );
+}
+
+#[test]
+fn equality_metatags() {
+    assert_eq!(
+        filter("type:video"),
+        Filter::Equals {
+            field: "post_type",
+            value: "Video".into()
+        }
+    );
+    assert_eq!(
+        filter("md5:d41d8cd98f00b204e9800998ecf8427e"),
+        Filter::Equals {
+            field: "md5_hash",
+            value: "D41D8CD98F00B204E9800998ECF8427E".into()
+        }
+    );
+    assert_eq!(
+        filter("uploader:Anonymous"),
+        Filter::Uploader("anonymous".into())
+    );
+}
+
+#[test]
+fn invalid_metatag_values() {
+    for (query, start, end) in [
+        ("width:wide", 6, 10),
+        ("width:..", 6, 8),
+        ("mpixels:>nan", 8, 12),
+        ("filesize:5tb", 9, 12),
+        ("date:2026-13", 5, 12),
+        ("date:2025-02-29", 5, 15),
+        ("date:26-01", 5, 10),
+        ("type:audio", 5, 10),
+        ("animated:maybe", 9, 14),
+        ("md5:1234", 4, 8),
+        ("sha256:d41d8cd98f00b204e9800998ecf8427e", 7, 39),
+        ("order:newest", 6, 12),
+    ] {
+        let error = parse_search(query).unwrap_err();
+        assert!(
+            matches!(error.kind, SearchErrorKind::InvalidValue { .. }),
+            "{}: {:?}",
+            query,
+            error
+        );
+        assert_eq!((error.start, error.end), (start, end), "{}", query);
+    }
+}
+
+#[test]
+fn invalid_value_message() {
+    let error = parse_search("width:wide").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`wide` is not a valid value for `width:`, expected a number or range like `>=1000` or `100..200` (at 6..10)"
+    );
+}
+
+#[test]
+fn unknown_metatag() {
+    assert_eq!(
+        parse_search("cat colour:red"),
+        Err(SearchError::new(
+            SearchErrorKind::UnknownMetatag("colour".into()),
+            4,
+            10
+        ))
+    );
+}
+
+#[test]
+fn existing_tags_come_before_metatags() {
+    let query = "-this_is_(not-so-wrong:uwu) rating:s ~a.b";
+    assert_eq!(
+        unusual_terms(query),
+        vec!["this_is_(not-so-wrong:uwu)", "rating:s", "a.b"]
+    );
+
+    let existing_tags: HashSet<String> = ["this_is_(not-so-wrong:uwu)", "a.b"]
+        .map(String::from)
+        .into();
+    let search = parse_search_with_tags(query, &existing_tags).unwrap();
+    assert_eq!(
+        search.all,
+        vec![
+            clause(Term::Tag("this_is_(not-so-wrong:uwu)".into()), true, 0, 27),
+            clause(rating("Safe"), false, 28, 36),
+        ]
+    );
+    assert_eq!(
+        search.any,
+        vec![clause(Term::Tag("a.b".into()), false, 37, 41)]
+    );
+}
+
+#[test]
+fn order() {
+    let search = parse_search("cat order:score").unwrap();
+    assert_eq!(search.order, Order::ScoreDesc);
+    assert_eq!(search.all.len(), 1);
+
+    assert_eq!(parse_search("cat").unwrap().order, Order::IdDesc);
+    assert_eq!(
+        parse_search("-order:random"),
+        Err(SearchError::new(SearchErrorKind::OrderNotFilter, 0, 13))
+    );
+    assert_eq!(
+        parse_search("order:id_asc order:random"),
+        Err(SearchError::new(SearchErrorKind::DuplicateOrder, 13, 25))
+    );
+}
+
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use surrealdb::engine::local::Db;
+    use surrealdb::Surreal;
+
+    use maerbooru::models::post::{Post, PostType, Safety};
+    use maerbooru::models::search::{SearchError, SearchErrorKind};
+    use maerbooru::server_only::post::{add_new_post, get_paginated_posts};
+    use maerbooru::server_only::tag::{get_tag_by_id, rename_tag};
+    use maerbooru::server_only::user::register_user;
+
+    use crate::common::{self, new_db, new_tag};
+
+    fn test_post(hash_byte: u8, tags: Vec<u64>, safety: Safety, animated: bool) -> Post {
+        Post {
+            safety,
+            frame_count: Some(if animated { 10 } else { 1 }),
+            animated,
+            ..common::test_post(hash_byte, tags)
+        }
+    }
+
+    /// Tags `cat_ears`, `dog`, `red` and `blue`, and one post per tag set
+    /// below. Returns the post ids in creation order.
+    async fn fixture() -> (Surreal<Db>, Vec<u64>) {
+        let db = new_db().await;
+
+        let mut tag_ids = vec![];
+        for name in ["cat_ears", "dog", "red", "blue"] {
+            tag_ids.push(new_tag(&db, name).await);
+        }
+        let [cat_ears, dog, red, blue] = tag_ids[..] else {
+            unreachable!()
+        };
+
+        let posts = [
+            test_post(0, vec![cat_ears, red], Safety::Safe, false),
+            test_post(1, vec![cat_ears, blue], Safety::Unsafe, true),
+            test_post(2, vec![cat_ears, dog, red], Safety::Safe, false),
+            test_post(3, vec![dog, blue], Safety::Sketchy, false),
+            test_post(4, vec![cat_ears], Safety::Safe, true),
+        ];
+        let mut post_ids = vec![];
+        for (i, post) in posts.into_iter().enumerate() {
+            // Widths 500..=900, one upload per day from 2026-01-01, scores -2..=2.
+            let post = Post {
+                image_width: 500 + 100 * i as u32,
+                uploaded_at: 1_767_225_600 + 86_400 * i as u64,
+                score: i as i64 - 2,
+                md5_hash: [i as u8; 16],
+                file_size: 1024 * 1024 * i as u64,
+                post_type: if i == 3 {
+                    PostType::Video
+                } else {
+                    PostType::Image
+                },
+                ..post
+            };
+            post_ids.push(add_new_post(&db, &post).await.unwrap());
+        }
+
+        (db, post_ids)
+    }
+
+    /// Ids of the posts found, in the order of the results.
+    async fn search_ordered(db: &Surreal<Db>, query: &str) -> Result<Vec<u64>, anyhow::Error> {
+        Ok(get_paginated_posts(db, 1, 100, Some(query.into()))
+            .await?
+            .iter()
+            .map(|post| post.custom_id)
+            .collect())
+    }
+
+    async fn search(db: &Surreal<Db>, query: &str) -> Result<Vec<u64>, anyhow::Error> {
+        let mut ids = search_ordered(db, query).await?;
+        ids.sort();
+        Ok(ids)
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_by_tags() {
+        let (db, ids) = fixture().await;
+
+        assert_eq!(search(&db, "").await.unwrap(), ids);
+        assert_eq!(
+            search(&db, "cat_ears").await.unwrap(),
+            vec![ids[0], ids[1], ids[2], ids[4]]
+        );
+        assert_eq!(
+            search(&db, "cat_ears red").await.unwrap(),
+            vec![ids[0], ids[2]]
+        );
+        assert_eq!(
+            search(&db, "cat_ears -dog red").await.unwrap(),
+            vec![ids[0]]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_or_group() {
+        let (db, ids) = fixture().await;
+
+        assert_eq!(
+            search(&db, "~red ~blue").await.unwrap(),
+            vec![ids[0], ids[1], ids[2], ids[3]]
+        );
+        assert_eq!(
+            search(&db, "cat_ears -dog ~red ~blue").await.unwrap(),
+            vec![ids[0], ids[1]]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_rating_and_animated() {
+        let (db, ids) = fixture().await;
+
+        assert_eq!(
+            search(&db, "cat_ears ~red ~blue rating:safe")
+                .await
+                .unwrap(),
+            vec![ids[0], ids[2]]
+        );
+        assert_eq!(
+            search(&db, "-rating:safe").await.unwrap(),
+            vec![ids[1], ids[3]]
+        );
+        assert_eq!(
+            search(&db, "animated:yes").await.unwrap(),
+            vec![ids[1], ids[4]]
+        );
+        assert_eq!(
+            search(&db, "cat_ears animated:no").await.unwrap(),
+            vec![ids[0], ids[2]]
+        );
+        assert_eq!(
+            search(&db, "cat_ears -animated:yes").await.unwrap(),
+            vec![ids[0], ids[2]]
+        );
+
+        // `animated` on its own is an ordinary tag.
+        let animated = new_tag(&db, "animated").await;
+        let tagged = add_new_post(&db, &test_post(9, vec![animated], Safety::Safe, false))
+            .await
+            .unwrap();
+        assert_eq!(search(&db, "animated").await.unwrap(), vec![tagged]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn unknown_tag_is_positioned() {
+        let (db, _) = fixture().await;
+
+        let error = search(&db, "~red cat_ears ~nope -missing")
+            .await
+            .unwrap_err()
+            .downcast::<SearchError>()
+            .unwrap();
+
+        assert_eq!(
+            error,
+            SearchError::new(SearchErrorKind::UnknownTag("nope".into()), 14, 19)
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn tags_named_before_validation() {
+        let (db, ids) = fixture().await;
+
+        // Names the tag name grammar no longer accepts, as older tags have.
+        db.query("CREATE tag CONTENT { custom_id: 100, name: 'this_is_(not-so-wrong:uwu)', description: '', category: 0, implications: [], use_count: 0 }")
+            .query("CREATE tag CONTENT { custom_id: 101, name: 'rating:e', description: '', category: 0, implications: [], use_count: 0 }")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let legacy = add_new_post(&db, &test_post(9, vec![100, 101], Safety::Safe, false))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            search(&db, "this_is_(not-so-wrong:uwu)").await.unwrap(),
+            vec![legacy]
+        );
+        assert_eq!(search(&db, "rating:e").await.unwrap(), vec![legacy]);
+        assert_eq!(
+            search(&db, "cat_ears -this_is_(not-so-wrong:uwu)")
+                .await
+                .unwrap(),
+            vec![ids[0], ids[1], ids[2], ids[4]]
+        );
+        assert_eq!(search(&db, "rating:q").await.unwrap(), vec![ids[3]]);
+
+        rename_tag(
+            &db,
+            "this_is_(not-so-wrong:uwu)".into(),
+            "this_is_(not-so-wrong)".into(),
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            get_tag_by_id(&db, 100).await.unwrap().unwrap().name,
+            "this_is_(not-so-wrong)"
+        );
+        assert_eq!(
+            search(&db, "this_is_(not-so-wrong)").await.unwrap(),
+            vec![legacy]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn parse_error_reaches_caller() {
+        let (db, _) = fixture().await;
+
+        let error = search(&db, "cat_ears -")
+            .await
+            .unwrap_err()
+            .downcast::<SearchError>()
+            .unwrap();
+
+        assert_eq!(error.kind, SearchErrorKind::MissingTerm);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_numeric_metatags() {
+        let (db, ids) = fixture().await;
+
+        assert_eq!(search(&db, "width:700").await.unwrap(), vec![ids[2]]);
+        assert_eq!(
+            search(&db, "width:>700").await.unwrap(),
+            vec![ids[3], ids[4]]
+        );
+        assert_eq!(
+            search(&db, "-width:600..800").await.unwrap(),
+            vec![ids[0], ids[4]]
+        );
+        assert_eq!(
+            search(&db, "mpixels:>=0.42").await.unwrap(),
+            vec![ids[2], ids[3], ids[4]]
+        );
+        assert_eq!(
+            search(&db, "filesize:1mb..2mb").await.unwrap(),
+            vec![ids[1], ids[2]]
+        );
+        assert_eq!(search(&db, "score:<0").await.unwrap(), vec![ids[0], ids[1]]);
+        assert_eq!(
+            search(&db, "~score:-2 ~score:2").await.unwrap(),
+            vec![ids[0], ids[4]]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_dates() {
+        let (db, ids) = fixture().await;
+
+        assert_eq!(search(&db, "date:2026-01-02").await.unwrap(), vec![ids[1]]);
+        assert_eq!(
+            search(&db, "date:2026-01-02..2026-01-03").await.unwrap(),
+            vec![ids[1], ids[2]]
+        );
+        assert_eq!(
+            search(&db, "date:>2026-01-03").await.unwrap(),
+            vec![ids[3], ids[4]]
+        );
+        assert_eq!(search(&db, "date:2026-01").await.unwrap(), ids);
+        assert!(search(&db, "date:2025").await.unwrap().is_empty());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_equality_metatags() {
+        let (db, ids) = fixture().await;
+
+        assert_eq!(search(&db, "type:video").await.unwrap(), vec![ids[3]]);
+        assert_eq!(
+            search(&db, "md5:02020202020202020202020202020202")
+                .await
+                .unwrap(),
+            vec![ids[2]]
+        );
+        assert_eq!(
+            search(&db, &format!("sha256:{}", "01".repeat(32)))
+                .await
+                .unwrap(),
+            vec![ids[1]]
+        );
+        assert_eq!(search(&db, "uploader:anonymous").await.unwrap(), ids);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_by_uploader() {
+        let (db, ids) = fixture().await;
+        let user = register_user(&db, "maeru", "correct horse").await.unwrap();
+        let post = Post {
+            uploader_id: user.custom_id,
+            ..test_post(9, vec![], Safety::Safe, false)
+        };
+        let uploaded = add_new_post(&db, &post).await.unwrap();
+
+        assert_eq!(search(&db, "uploader:Maeru").await.unwrap(), vec![uploaded]);
+        assert_eq!(search(&db, "uploader:anonymous").await.unwrap(), ids);
+        assert_eq!(
+            search(&db, "-uploader:anonymous").await.unwrap(),
+            vec![uploaded]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn unknown_uploader() {
+        let (db, _) = fixture().await;
+
+        let error = search(&db, "cat_ears uploader:nobody")
+            .await
+            .unwrap_err()
+            .downcast::<SearchError>()
+            .unwrap();
+
+        assert_eq!(
+            error,
+            SearchError::new(SearchErrorKind::UnknownUser("nobody".into()), 9, 24)
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_order() {
+        let (db, ids) = fixture().await;
+
+        let newest_first: Vec<u64> = ids.iter().rev().copied().collect();
+        assert_eq!(search_ordered(&db, "").await.unwrap(), newest_first);
+        assert_eq!(search_ordered(&db, "order:id_asc").await.unwrap(), ids);
+        assert_eq!(
+            search_ordered(&db, "order:score").await.unwrap(),
+            newest_first
+        );
+        assert_eq!(
+            search_ordered(&db, "cat_ears order:score_asc")
+                .await
+                .unwrap(),
+            vec![ids[0], ids[1], ids[2], ids[4]]
+        );
+        assert_eq!(search(&db, "order:random").await.unwrap(), ids);
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use maerbooru::models::tag::TagDeleteError;
+    use maerbooru::server_only::implication::set_implications;
+    use maerbooru::server_only::post::{add_new_post, get_post_by_id};
+    use maerbooru::server_only::tag::{delete_tag, get_tag_by_id};
+
+    use crate::common::{new_alias, new_db, new_tag, test_post};
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn unused_tags_are_deleted() {
+        let db = new_db().await;
+        let typo = new_tag(&db, "cta").await;
+
+        delete_tag(&db, "cta".into(), false, 0).await.unwrap();
+        assert!(get_tag_by_id(&db, typo).await.unwrap().is_none());
+
+        let error = delete_tag(&db, "cta".into(), false, 0).await.unwrap_err();
+        assert_eq!(
+            error.downcast::<TagDeleteError>().unwrap(),
+            TagDeleteError::UnknownTag("cta".into())
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn used_tags_need_force() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let kitty = new_alias(&db, "kitty", cat).await;
+        let cat_ears = new_tag(&db, "cat_ears").await;
+        let red = new_tag(&db, "red").await;
+        set_implications(&db, cat_ears, vec![cat], false, 0)
+            .await
+            .unwrap();
+
+        let first = add_new_post(&db, &test_post(0, vec![cat_ears, red]))
+            .await
+            .unwrap();
+        let second = add_new_post(&db, &test_post(1, vec![cat])).await.unwrap();
+
+        let error = delete_tag(&db, "cat".into(), false, 0).await.unwrap_err();
+        assert_eq!(
+            error.downcast::<TagDeleteError>().unwrap(),
+            TagDeleteError::InUse {
+                posts: 2,
+                aliases: 1,
+                implied_by: 1,
+            }
+        );
+        assert!(get_tag_by_id(&db, cat).await.unwrap().is_some());
+
+        delete_tag(&db, "cat".into(), true, 0).await.unwrap();
+
+        assert!(get_tag_by_id(&db, cat).await.unwrap().is_none());
+        assert!(get_tag_by_id(&db, kitty).await.unwrap().is_none());
+
+        let cat_ears = get_tag_by_id(&db, cat_ears).await.unwrap().unwrap();
+        assert!(cat_ears.implications.is_empty());
+        assert_eq!(cat_ears.use_count, 1);
+
+        let mut expected = vec![cat_ears.custom_id, red];
+        expected.sort();
+        let first = get_post_by_id(&db, first).await.unwrap().unwrap();
+        assert_eq!(first.tags, expected);
+        let second = get_post_by_id(&db, second).await.unwrap().unwrap();
+        assert!(second.tags.is_empty());
+    }
+}
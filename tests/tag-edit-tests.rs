@@ -0,0 +1,252 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use surrealdb::engine::local::Db;
+    use surrealdb::Surreal;
+
+    use maerbooru::models::tag::{TagCategory, TagEdit, TagEditError};
+    use maerbooru::models::wiki::MAX_WIKI_LINES;
+    use maerbooru::server_only::post::{add_new_post, get_post_by_id};
+    use maerbooru::server_only::tag::{get_tag_by_id, get_tag_by_name};
+    use maerbooru::server_only::tag_edit::{edit_tag, get_tag_details};
+    use maerbooru::server_only::wiki::define_wiki_table;
+
+    use crate::common::{new_db, new_tag, test_post};
+
+    async fn edit_error(db: &Surreal<Db>, name: &str, edit: TagEdit) -> TagEditError {
+        edit_tag(db, name.into(), edit, 0)
+            .await
+            .unwrap_err()
+            .downcast::<TagEditError>()
+            .unwrap()
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn edit_description_category_and_implications() {
+        let db = new_db().await;
+        let cat_ears = new_tag(&db, "cat_ears").await;
+        let ears = new_tag(&db, "ears").await;
+        let kitty = new_tag(&db, "kitty").await;
+        let cat = new_tag(&db, "cat").await;
+        edit_tag(
+            &db,
+            "kitty".into(),
+            TagEdit {
+                alias_of: Some("cat".into()),
+                ..TagEdit::default()
+            },
+            0,
+        )
+        .await
+        .unwrap();
+
+        let post = add_new_post(&db, &test_post(0, vec![cat_ears]))
+            .await
+            .unwrap();
+
+        edit_tag(
+            &db,
+            "cat_ears".into(),
+            TagEdit {
+                description: Some("Ears of a cat.".into()),
+                category: TagCategory::Species,
+                alias_of: None,
+                implications: vec!["ears".into(), "KITTY ".into(), "".into()],
+                back_apply: true,
+            },
+            0,
+        )
+        .await
+        .unwrap();
+
+        let tag = get_tag_by_id(&db, cat_ears).await.unwrap().unwrap();
+        assert_eq!(tag.description, "Ears of a cat.");
+        assert_eq!(tag.category, TagCategory::Species);
+        let mut expected = vec![ears, cat];
+        expected.sort();
+        assert_eq!(tag.implications, expected);
+
+        let post = get_post_by_id(&db, post).await.unwrap().unwrap();
+        let mut expected = vec![cat_ears, ears, cat];
+        expected.sort();
+        assert_eq!(post.tags, expected);
+
+        let details = get_tag_details(&db, "cat_ears".into())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut names = details.implications.clone();
+        names.sort();
+        assert_eq!(names, vec!["cat", "ears"]);
+
+        let details = get_tag_details(&db, "kitty".into()).await.unwrap().unwrap();
+        assert_eq!(
+            (details.tag.custom_id, details.alias_of),
+            (kitty, Some("cat".into()))
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn change_and_remove_alias_targets() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let feline = new_tag(&db, "feline").await;
+        let kitty = new_tag(&db, "kitty").await;
+
+        let post = add_new_post(&db, &test_post(0, vec![kitty])).await.unwrap();
+
+        for (target, expected) in [
+            (Some("cat"), Some(cat)),
+            (Some("feline"), Some(feline)),
+            (None, None),
+        ] {
+            edit_tag(
+                &db,
+                "kitty".into(),
+                TagEdit {
+                    alias_of: target.map(String::from),
+                    ..TagEdit::default()
+                },
+                0,
+            )
+            .await
+            .unwrap();
+            let tag = get_tag_by_id(&db, kitty).await.unwrap().unwrap();
+            assert_eq!(tag.is_alias, expected);
+        }
+
+        // Only the first edit made it an alias, which moved the post to `cat`.
+        let post = get_post_by_id(&db, post).await.unwrap().unwrap();
+        assert_eq!(post.tags, vec![cat]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn failures_change_nothing() {
+        let db = new_db().await;
+        let cat_ears = new_tag(&db, "cat_ears").await;
+        new_tag(&db, "ears").await;
+        new_tag(&db, "animal_ears").await;
+        edit_tag(
+            &db,
+            "animal_ears".into(),
+            TagEdit {
+                implications: vec!["cat_ears".into()],
+                ..TagEdit::default()
+            },
+            0,
+        )
+        .await
+        .unwrap();
+
+        let cases = [
+            (
+                "dog",
+                TagEdit::default(),
+                TagEditError::UnknownTag("dog".into()),
+            ),
+            (
+                "cat_ears",
+                TagEdit {
+                    alias_of: Some("nothing".into()),
+                    ..TagEdit::default()
+                },
+                TagEditError::UnknownTag("nothing".into()),
+            ),
+            (
+                "cat_ears",
+                TagEdit {
+                    implications: vec!["ears".into(), "nothing".into()],
+                    ..TagEdit::default()
+                },
+                TagEditError::UnknownTag("nothing".into()),
+            ),
+            (
+                "cat_ears",
+                TagEdit {
+                    alias_of: Some("cat_ears".into()),
+                    ..TagEdit::default()
+                },
+                TagEditError::SelfAlias,
+            ),
+            (
+                "cat_ears",
+                TagEdit {
+                    alias_of: Some("ears".into()),
+                    implications: vec!["ears".into()],
+                    ..TagEdit::default()
+                },
+                TagEditError::AliasWithImplications,
+            ),
+            (
+                "cat_ears",
+                TagEdit {
+                    implications: vec!["animal_ears".into()],
+                    ..TagEdit::default()
+                },
+                TagEditError::Cycle("cat_ears".into()),
+            ),
+            (
+                "cat_ears",
+                TagEdit {
+                    description: Some("line\n".repeat(MAX_WIKI_LINES + 1)),
+                    ..TagEdit::default()
+                },
+                TagEditError::DescriptionTooLong,
+            ),
+        ];
+
+        for (name, edit, expected) in cases {
+            let edit = TagEdit {
+                description: edit.description.or_else(|| Some("changed".into())),
+                category: TagCategory::Meta,
+                ..edit
+            };
+            assert_eq!(edit_error(&db, name, edit).await, expected);
+        }
+
+        let tag = get_tag_by_name(&db, "cat_ears".into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tag.custom_id, cat_ears);
+        assert_eq!(tag.description, "");
+        assert_eq!(tag.category, TagCategory::General);
+        assert_eq!(tag.is_alias, None);
+        assert!(tag.implications.is_empty());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn failed_writes_are_rolled_back() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        new_tag(&db, "animal").await;
+
+        // Takes the id the new wiki version would get, so storing it fails
+        // after the category was already written.
+        define_wiki_table(&db).await.unwrap();
+        db.query("CREATE wiki_version CONTENT { custom_id: 1, tag_id: 999, version: 1, body: '', created_at: 0 }")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let edit = TagEdit {
+            description: Some("A small animal.".into()),
+            category: TagCategory::Species,
+            implications: vec!["animal".into()],
+            ..TagEdit::default()
+        };
+        assert!(edit_tag(&db, "cat".into(), edit, 0).await.is_err());
+
+        let tag = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        assert_eq!(tag.category, TagCategory::General);
+        assert_eq!(tag.description, "");
+        assert!(tag.implications.is_empty());
+    }
+}
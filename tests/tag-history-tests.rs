@@ -0,0 +1,227 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use maerbooru::models::tag::{Tag, TagAction, TagCategory, TagEdit, TagHistoryEntry};
+    use maerbooru::server_only::alias::create_alias;
+    use maerbooru::server_only::implication::set_implications;
+    use maerbooru::server_only::post::{add_new_post, set_post_tags};
+    use maerbooru::server_only::tag::{add_new_tag, delete_tag, get_tag_history, merge_tags};
+    use maerbooru::server_only::tag_edit::edit_tag;
+    use surrealdb::engine::local::Db;
+    use surrealdb::Surreal;
+
+    use maerbooru::models::post::Post;
+
+    use crate::common::{new_db, new_tag, test_post};
+
+    fn changes(history: &[TagHistoryEntry]) -> Vec<(TagAction, u64)> {
+        history
+            .iter()
+            .map(|entry| (entry.action, entry.user_id))
+            .collect()
+    }
+
+    /// The tag, action and post of the post tagging entries made by `user_id`,
+    /// by tag.
+    async fn post_changes(db: &Surreal<Db>, user_id: u64) -> Vec<(u64, TagAction, Option<u64>)> {
+        let entries: Vec<TagHistoryEntry> = db
+            .query("SELECT * FROM tag_history WHERE post_id != NONE AND user_id = $user_id ORDER BY tag_id, post_id")
+            .bind(("user_id", user_id))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        entries
+            .iter()
+            .map(|entry| (entry.tag_id, entry.action, entry.post_id))
+            .collect()
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn tag_changes_record_their_user() {
+        let db = new_db().await;
+        let tag = Tag {
+            name: "cat".into(),
+            ..Tag::default()
+        };
+        let cat = add_new_tag(&db, &tag, 3).await.unwrap();
+        let animal = new_tag(&db, "animal").await;
+        let kitty = new_tag(&db, "kitty").await;
+
+        set_implications(&db, cat, vec![animal], false, 4)
+            .await
+            .unwrap();
+        let edit = TagEdit {
+            category: TagCategory::Species,
+            implications: vec!["animal".into()],
+            ..TagEdit::default()
+        };
+        edit_tag(&db, "cat".into(), edit.clone(), 5).await.unwrap();
+        // Saving the same edit again changes nothing, so it is not recorded.
+        edit_tag(&db, "cat".into(), edit, 5).await.unwrap();
+        create_alias(&db, kitty, cat, 6).await.unwrap();
+
+        let history = get_tag_history(&db, cat).await.unwrap();
+        assert_eq!(
+            changes(&history),
+            [
+                (TagAction::Create, 3),
+                (TagAction::Implications, 4),
+                (TagAction::Edit, 5),
+                (TagAction::Alias, 6),
+            ]
+        );
+        let alias = &history[3];
+        assert_eq!(alias.tag_id, kitty);
+        assert_eq!(alias.target_id, Some(cat));
+        assert_eq!(
+            (alias.old_name.as_str(), alias.new_name.as_str()),
+            ("kitty", "cat")
+        );
+
+        delete_tag(&db, "animal".into(), true, 7).await.unwrap();
+        let history = get_tag_history(&db, animal).await.unwrap();
+        assert_eq!(
+            changes(&history),
+            [(TagAction::Create, 0), (TagAction::Delete, 7)]
+        );
+        assert_eq!(history[1].old_name, "animal");
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn post_tagging_is_recorded_per_tag() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let dog = new_tag(&db, "dog").await;
+        let red = new_tag(&db, "red").await;
+        let post = add_new_post(&db, &test_post(0, vec![cat, red]))
+            .await
+            .unwrap();
+
+        set_post_tags(&db, post, vec![dog, red], 8).await.unwrap();
+
+        assert_eq!(
+            post_changes(&db, 8).await,
+            [
+                (cat, TagAction::Untag, Some(post)),
+                (dog, TagAction::Tag, Some(post)),
+            ]
+        );
+
+        // They stay off the history shown on the tag page.
+        assert_eq!(
+            changes(&get_tag_history(&db, cat).await.unwrap()),
+            [(TagAction::Create, 0)]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn uploads_record_their_tags() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let animal = new_tag(&db, "animal").await;
+        set_implications(&db, cat, vec![animal], false, 0)
+            .await
+            .unwrap();
+
+        let post = Post {
+            uploader_id: 5,
+            ..test_post(0, vec![cat])
+        };
+        let post = add_new_post(&db, &post).await.unwrap();
+
+        assert_eq!(
+            post_changes(&db, 5).await,
+            [
+                (cat, TagAction::Tag, Some(post)),
+                (animal, TagAction::Tag, Some(post)),
+            ]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn merges_record_the_posts_they_move() {
+        let db = new_db().await;
+        let kitty = new_tag(&db, "kitty").await;
+        let cat = new_tag(&db, "cat").await;
+        let animal = new_tag(&db, "animal").await;
+        set_implications(&db, cat, vec![animal], false, 0)
+            .await
+            .unwrap();
+        let moved = add_new_post(&db, &test_post(0, vec![kitty])).await.unwrap();
+        let both = add_new_post(&db, &test_post(1, vec![kitty, cat]))
+            .await
+            .unwrap();
+        add_new_post(&db, &test_post(2, vec![cat])).await.unwrap();
+
+        merge_tags(&db, "kitty".into(), "cat".into(), 9)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            post_changes(&db, 9).await,
+            [
+                (kitty, TagAction::Untag, Some(moved)),
+                (kitty, TagAction::Untag, Some(both)),
+                (cat, TagAction::Tag, Some(moved)),
+                (animal, TagAction::Tag, Some(moved)),
+            ]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn forced_deletes_record_the_posts_they_untag() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        let red = new_tag(&db, "red").await;
+        let first = add_new_post(&db, &test_post(0, vec![cat, red]))
+            .await
+            .unwrap();
+        let second = add_new_post(&db, &test_post(1, vec![cat])).await.unwrap();
+        add_new_post(&db, &test_post(2, vec![red])).await.unwrap();
+
+        delete_tag(&db, "cat".into(), true, 7).await.unwrap();
+
+        assert_eq!(
+            post_changes(&db, 7).await,
+            [
+                (cat, TagAction::Untag, Some(first)),
+                (cat, TagAction::Untag, Some(second)),
+            ]
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn aliasing_and_back_applying_record_their_posts() {
+        let db = new_db().await;
+        let kitty = new_tag(&db, "kitty").await;
+        let cat = new_tag(&db, "cat").await;
+        let animal = new_tag(&db, "animal").await;
+        let post = add_new_post(&db, &test_post(0, vec![kitty])).await.unwrap();
+
+        set_implications(&db, kitty, vec![animal], true, 4)
+            .await
+            .unwrap();
+        assert_eq!(
+            post_changes(&db, 4).await,
+            [(animal, TagAction::Tag, Some(post))]
+        );
+
+        create_alias(&db, kitty, cat, 6).await.unwrap();
+        assert_eq!(
+            post_changes(&db, 6).await,
+            [
+                (kitty, TagAction::Untag, Some(post)),
+                (cat, TagAction::Tag, Some(post)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use maerbooru::components::tag_input::{complete, current_prefix};
+
+#[test]
+fn prefix_of_last_word() {
+    assert_eq!(current_prefix("cat_ears do"), "do");
+    assert_eq!(current_prefix("Cat"), "cat");
+    assert_eq!(current_prefix("cat_ears -do"), "do");
+    assert_eq!(current_prefix("~re"), "re");
+}
+
+#[test]
+fn no_prefix() {
+    assert_eq!(current_prefix(""), "");
+    assert_eq!(current_prefix("cat_ears "), "");
+    assert_eq!(current_prefix("cat rating:s"), "");
+    assert_eq!(current_prefix("-"), "");
+}
+
+#[test]
+fn completion_replaces_last_word() {
+    assert_eq!(complete("cat_ears do", "dog"), "cat_ears dog ");
+    assert_eq!(complete("ca", "cat_ears"), "cat_ears ");
+    assert_eq!(complete("cat -do", "dog"), "cat -dog ");
+    assert_eq!(complete("cat\u{3000}~re", "red"), "cat\u{3000}~red ");
+}
@@ -0,0 +1,184 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use maerbooru::models::tag::{TagAction, TagChangeError, TagHistoryEntry, TagNameError};
+    use maerbooru::server_only::implication::set_implications;
+    use maerbooru::server_only::post::{add_new_post, get_post_by_id};
+    use maerbooru::server_only::tag::{
+        get_tag_by_id, get_tag_by_name, get_tag_history, merge_tags, rename_tag,
+    };
+
+    use crate::common::{new_alias, new_db, new_tag, test_post};
+
+    fn change_error(error: anyhow::Error) -> TagChangeError {
+        error.downcast::<TagChangeError>().unwrap()
+    }
+
+    fn actions(history: &[TagHistoryEntry]) -> Vec<TagAction> {
+        history.iter().map(|entry| entry.action).collect()
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn rename_with_history() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cta").await;
+        new_tag(&db, "dog").await;
+
+        rename_tag(&db, "cta".into(), " Cat".into(), 7)
+            .await
+            .unwrap();
+
+        let tag = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        assert_eq!(tag.name, "cat");
+        assert!(get_tag_by_name(&db, "cta".into()).await.unwrap().is_none());
+
+        let history = get_tag_history(&db, cat).await.unwrap();
+        assert_eq!(actions(&history), [TagAction::Create, TagAction::Rename]);
+        assert_eq!(history[1].user_id, 7);
+        assert_eq!(
+            (history[1].old_name.as_str(), history[1].new_name.as_str()),
+            ("cta", "cat")
+        );
+
+        let error = rename_tag(&db, "cat".into(), "dog".into(), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(change_error(error), TagChangeError::NameTaken("dog".into()));
+
+        let error = rename_tag(&db, "cat".into(), "-cat".into(), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            change_error(error),
+            TagChangeError::InvalidName(TagNameError::LeadingOperator('-'))
+        );
+
+        let error = rename_tag(&db, "cta".into(), "kitty".into(), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            change_error(error),
+            TagChangeError::UnknownTag("cta".into())
+        );
+
+        assert_eq!(get_tag_history(&db, cat).await.unwrap().len(), 2);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn merge_moves_everything_to_the_target() {
+        let db = new_db().await;
+        let kitty = new_tag(&db, "kitty").await;
+        let cat = new_tag(&db, "cat").await;
+        let cute = new_tag(&db, "cute").await;
+        let animal = new_tag(&db, "animal").await;
+        let kitten = new_tag(&db, "kitten").await;
+        let kitty_cat = new_alias(&db, "kitty_cat", kitty).await;
+        let red = new_tag(&db, "red").await;
+
+        set_implications(&db, kitty, vec![cute], false, 0)
+            .await
+            .unwrap();
+        set_implications(&db, cat, vec![animal], false, 0)
+            .await
+            .unwrap();
+        set_implications(&db, kitten, vec![kitty], false, 0)
+            .await
+            .unwrap();
+
+        let only_kitty = add_new_post(&db, &test_post(0, vec![kitty, red]))
+            .await
+            .unwrap();
+        let both = add_new_post(&db, &test_post(1, vec![kitty, cat]))
+            .await
+            .unwrap();
+
+        merge_tags(&db, "kitty".into(), "cat".into(), 0)
+            .await
+            .unwrap();
+
+        let mut expected = vec![cat, cute, animal, red];
+        expected.sort();
+        let post = get_post_by_id(&db, only_kitty).await.unwrap().unwrap();
+        assert_eq!(post.tags, expected);
+
+        let mut expected = vec![cat, cute, animal];
+        expected.sort();
+        let post = get_post_by_id(&db, both).await.unwrap().unwrap();
+        assert_eq!(post.tags, expected);
+
+        let cat_tag = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        let mut expected = vec![cute, animal];
+        expected.sort();
+        assert_eq!(cat_tag.implications, expected);
+        assert_eq!(cat_tag.use_count, 2);
+
+        let kitty_tag = get_tag_by_id(&db, kitty).await.unwrap().unwrap();
+        assert_eq!(kitty_tag.is_alias, Some(cat));
+        assert!(kitty_tag.implications.is_empty());
+        assert_eq!(kitty_tag.use_count, 0);
+
+        let kitten = get_tag_by_id(&db, kitten).await.unwrap().unwrap();
+        assert_eq!(kitten.implications, vec![cat]);
+        let kitty_cat = get_tag_by_id(&db, kitty_cat).await.unwrap().unwrap();
+        assert_eq!(kitty_cat.is_alias, Some(cat));
+
+        for id in [kitty, cat] {
+            let history = get_tag_history(&db, id).await.unwrap();
+            assert_eq!(
+                actions(&history),
+                [TagAction::Create, TagAction::Implications, TagAction::Merge]
+            );
+            assert_eq!(history[2].target_id, Some(cat));
+            assert_eq!(
+                (history[2].old_name.as_str(), history[2].new_name.as_str()),
+                ("kitty", "cat")
+            );
+        }
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn invalid_merges() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        new_alias(&db, "feline", cat).await;
+        let ears = new_tag(&db, "ears").await;
+        let cat_ears = new_tag(&db, "cat_ears").await;
+        set_implications(&db, cat_ears, vec![ears], false, 0)
+            .await
+            .unwrap();
+        set_implications(&db, cat, vec![cat_ears], false, 0)
+            .await
+            .unwrap();
+
+        for (name, target, expected) in [
+            ("cat", "feline", TagChangeError::SelfMerge),
+            (
+                "feline",
+                "ears",
+                TagChangeError::MergeAlias("feline".into()),
+            ),
+            ("dog", "cat", TagChangeError::UnknownTag("dog".into())),
+            ("cat", "dog", TagChangeError::UnknownTag("dog".into())),
+            // `ears` would take over `cat_ears`, which implies `ears`.
+            ("cat", "ears", TagChangeError::Cycle("ears".into())),
+        ] {
+            let error = merge_tags(&db, name.into(), target.into(), 0)
+                .await
+                .unwrap_err();
+            assert_eq!(change_error(error), expected, "{} into {}", name, target);
+        }
+
+        let cat = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        assert_eq!((cat.is_alias, cat.implications), (None, vec![cat_ears]));
+        let history = get_tag_history(&db, cat.custom_id).await.unwrap();
+        assert_eq!(
+            actions(&history),
+            [TagAction::Create, TagAction::Implications]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use maerbooru::models::tag::{normalize_tag_name, TagNameError};
+
+#[test]
+fn plain_names() {
+    for name in ["cat_ears", "see-through", "1girl", "x"] {
+        assert_eq!(normalize_tag_name(name), Ok(name.to_string()));
+    }
+}
+
+#[test]
+fn normalization() {
+    assert_eq!(normalize_tag_name("  Cat Ears\t"), Ok("cat_ears".into()));
+    assert_eq!(normalize_tag_name("BLUE   sky"), Ok("blue_sky".into()));
+}
+
+#[test]
+fn qualifiers() {
+    for name in [
+        "lain_(serial_experiments_lain)",
+        "saber_(fate)_(cosplay)",
+        "mark_(a_(b))",
+    ] {
+        assert_eq!(normalize_tag_name(name), Ok(name.to_string()));
+    }
+    assert_eq!(
+        normalize_tag_name("Lain (Serial Experiments Lain)"),
+        Ok("lain_(serial_experiments_lain)".into())
+    );
+}
+
+#[test]
+fn misplaced_qualifiers() {
+    for name in [
+        "(fate)",
+        "saber(fate)",
+        "saber_(fate)_lily",
+        "saber_(fate)x",
+    ] {
+        assert_eq!(
+            normalize_tag_name(name),
+            Err(TagNameError::MisplacedQualifier),
+            "{}",
+            name
+        );
+    }
+    assert_eq!(
+        normalize_tag_name("saber_()"),
+        Err(TagNameError::EmptyQualifier)
+    );
+}
+
+#[test]
+fn unbalanced_parentheses() {
+    for name in ["saber_(fate", "saber_(fate))", "saber)", "a_(b_(c)"] {
+        assert_eq!(
+            normalize_tag_name(name),
+            Err(TagNameError::UnbalancedParentheses),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn rejected_names() {
+    assert_eq!(normalize_tag_name("   "), Err(TagNameError::Empty));
+    assert_eq!(
+        normalize_tag_name("-dog"),
+        Err(TagNameError::LeadingOperator('-'))
+    );
+    assert_eq!(
+        normalize_tag_name("~dog"),
+        Err(TagNameError::LeadingOperator('~'))
+    );
+    assert_eq!(normalize_tag_name("*"), Err(TagNameError::Wildcard));
+    assert_eq!(normalize_tag_name("cat*"), Err(TagNameError::Wildcard));
+    for c in ['{', ':', '\'', 'ç', '"'] {
+        assert_eq!(
+            normalize_tag_name(&format!("a{}b", c)),
+            Err(TagNameError::InvalidCharacter(c))
+        );
+    }
+}
@@ -3,12 +3,16 @@ pub mod server_only {
     use core::panic;
 
     use maerbooru::server_only::tag::add_new_tag;
+    use maerbooru::server_only::tag::autocomplete_tags;
+    use maerbooru::server_only::tag::build_search_query;
+    use maerbooru::server_only::tag::get_paginated_tags;
     use surrealdb::engine::local::Mem;
 
-    use maerbooru::models::tag::Tag;
+    use maerbooru::models::tag::{Tag, TagCategory, TagNameError, UnknownCategory};
     use maerbooru::server_only::tag::define_tag_table;
     use maerbooru::server_only::tag::get_tag_by_id;
     use maerbooru::server_only::tag::get_tag_by_name;
+    use maerbooru::server_only::tag::get_tags_by_ids;
 
     #[allow(clippy::needless_return)]
     #[tokio::test]
@@ -24,11 +28,11 @@ pub mod server_only {
             description: String::from("what the fuck??"),
             is_alias: None,
             use_count: 0,
-            category: 0,
+            category: TagCategory::General,
             implications: vec![],
         };
 
-        let new_tag_id = add_new_tag(&db, &tag).await.unwrap();
+        let new_tag_id = add_new_tag(&db, &tag, 0).await.unwrap();
 
         let found_tag = get_tag_by_id(&db, new_tag_id)
             .await
@@ -46,7 +50,7 @@ pub mod server_only {
 
     #[allow(clippy::needless_return)]
     #[tokio::test]
-    async fn non_snake_case() {
+    async fn names_are_normalized() {
         let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
         db.use_ns("test").use_db("test").await.unwrap();
 
@@ -54,22 +58,22 @@ pub mod server_only {
 
         let tag = Tag {
             custom_id: 0,
-            name: String::from("wrong name"),
+            name: String::from("  Lain (Serial_Experiments_Lain) "),
             description: String::from("hello"),
             is_alias: None,
             use_count: 0,
-            category: 0,
+            category: TagCategory::Character,
             implications: vec![],
         };
 
-        if (add_new_tag(&db, &tag).await).is_ok() {
-            panic!("adding the tag should have failed")
-        }
+        let id = add_new_tag(&db, &tag, 0).await.unwrap();
+        let found = get_tag_by_id(&db, id).await.unwrap().unwrap();
+        assert_eq!(found.name, "lain_(serial_experiments_lain)");
     }
 
     #[allow(clippy::needless_return)]
     #[tokio::test]
-    async fn tag_mix_case() {
+    async fn invalid_names_are_rejected() {
         let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
         db.use_ns("test").use_db("test").await.unwrap();
 
@@ -77,22 +81,25 @@ pub mod server_only {
 
         let tag = Tag {
             custom_id: 0,
-            name: String::from("this_is_(not-so-wrong:uwu)"),
+            name: String::from("-dog"),
             description: String::from("hello"),
             is_alias: None,
             use_count: 0,
-            category: 0,
+            category: TagCategory::General,
             implications: vec![],
         };
 
-        if (add_new_tag(&db, &tag).await).is_err() {
-            panic!("adding the tag shouldn't have failed!")
-        }
+        let error = add_new_tag(&db, &tag, 0).await.unwrap_err();
+        assert_eq!(
+            error.downcast::<TagNameError>().unwrap(),
+            TagNameError::LeadingOperator('-')
+        );
+        assert!(get_tag_by_name(&db, "-dog".into()).await.unwrap().is_none());
     }
 
     #[allow(clippy::needless_return)]
     #[tokio::test]
-    async fn upper_case() {
+    async fn create_and_find_tag_by_name() {
         let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
         db.use_ns("test").use_db("test").await.unwrap();
 
@@ -100,51 +107,176 @@ pub mod server_only {
 
         let tag = Tag {
             custom_id: 0,
-            name: String::from("UPPERCASEISWRONg"),
-            description: String::from("hello"),
+            name: String::from("test_tag"),
+            description: String::from("what the fuck??"),
             is_alias: None,
             use_count: 0,
-            category: 0,
+            category: TagCategory::General,
             implications: vec![],
         };
 
-        if (add_new_tag(&db, &tag).await).is_ok() {
-            panic!("adding the tag should have failed")
+        let _ = add_new_tag(&db, &tag, 0).await.unwrap();
+
+        let found_tag = get_tag_by_name(&db, "test_tag".into())
+            .await
+            .unwrap()
+            .expect("tag should exist by now");
+
+        assert_eq!(
+            Tag {
+                custom_id: found_tag.custom_id,
+                ..tag
+            },
+            found_tag
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn find_tags_by_ids() {
+        let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        define_tag_table(&db).await.unwrap();
+
+        let mut ids = vec![];
+        for name in ["zebra", "apple", "unused"] {
+            let tag = Tag {
+                name: String::from(name),
+                ..Tag::default()
+            };
+            ids.push(add_new_tag(&db, &tag, 0).await.unwrap());
+        }
+
+        let found_tags = get_tags_by_ids(&db, vec![ids[0], ids[1]]).await.unwrap();
+
+        let names: Vec<&str> = found_tags.iter().map(|tag| tag.name.as_str()).collect();
+        assert_eq!(names, vec!["apple", "zebra"]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn categories_are_stored_and_validated() {
+        let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        define_tag_table(&db).await.unwrap();
+
+        for category in TagCategory::ALL {
+            assert_eq!(TagCategory::try_from(category as u8), Ok(category));
+
+            let tag = Tag {
+                name: category.display_name().to_lowercase(),
+                category,
+                ..Tag::default()
+            };
+            let id = add_new_tag(&db, &tag, 0).await.unwrap();
+            let found = get_tag_by_id(&db, id).await.unwrap().unwrap();
+            assert_eq!(found.category, category);
         }
+
+        assert_eq!(TagCategory::try_from(42), Err(UnknownCategory(42)));
+
+        let result = db
+            .query("CREATE tag CONTENT { custom_id: 999, name: 'bad', description: '', category: 42, implications: [], use_count: 0 }")
+            .await
+            .unwrap()
+            .check();
+        assert!(result.is_err());
     }
 
     #[allow(clippy::needless_return)]
     #[tokio::test]
-    async fn create_and_find_tag_by_name() {
+    async fn category_check_applies_to_an_existing_tag_table() {
         let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
         db.use_ns("test").use_db("test").await.unwrap();
 
+        // The tag table as it was defined before categories were checked.
+        db.query(
+            r#"
+            DEFINE TABLE tag SCHEMAFULL;
+            DEFINE FIELD custom_id ON TABLE tag TYPE number;
+            DEFINE FIELD name ON TABLE tag TYPE string;
+            DEFINE FIELD description ON TABLE tag TYPE string;
+            DEFINE FIELD is_alias ON TABLE tag TYPE option<number>;
+            DEFINE FIELD category ON TABLE tag TYPE number;
+            DEFINE FIELD implications ON TABLE tag TYPE array;
+            DEFINE FIELD use_count ON TABLE tag TYPE number;
+            DEFINE INDEX custom_id ON TABLE tag FIELDS custom_id UNIQUE;
+            DEFINE INDEX name_unique ON TABLE tag FIELDS name UNIQUE;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
         define_tag_table(&db).await.unwrap();
+        // And again, as on every start.
+        define_tag_table(&db).await.unwrap();
+
+        let result = db
+            .query("CREATE tag CONTENT { custom_id: 999, name: 'bad', description: '', category: 42, implications: [], use_count: 0 }")
+            .await
+            .unwrap()
+            .check();
+        assert!(result.is_err());
 
         let tag = Tag {
-            custom_id: 0,
-            name: String::from("test_tag"),
-            description: String::from("what the fuck??"),
-            is_alias: None,
-            use_count: 0,
-            category: 0,
-            implications: vec![],
+            name: String::from("fine"),
+            category: TagCategory::Artist,
+            ..Tag::default()
         };
+        add_new_tag(&db, &tag, 0).await.unwrap();
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn autocomplete_by_prefix() {
+        let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        define_tag_table(&db).await.unwrap();
 
-        let _ = add_new_tag(&db, &tag).await.unwrap();
+        let mut ids = vec![];
+        for (name, use_count) in [("cat", 5), ("cat_ears", 9), ("catgirl", 1), ("dog", 20)] {
+            let tag = Tag {
+                name: String::from(name),
+                use_count,
+                ..Tag::default()
+            };
+            ids.push(add_new_tag(&db, &tag, 0).await.unwrap());
+        }
+        let kitty = Tag {
+            name: String::from("cat_(animal)"),
+            is_alias: Some(ids[0]),
+            ..Tag::default()
+        };
+        add_new_tag(&db, &kitty, 0).await.unwrap();
 
-        let found_tag = get_tag_by_name(&db, "test_tag".into())
+        let found: Vec<(String, Option<String>)> = autocomplete_tags(&db, " CAT".into(), 10)
             .await
             .unwrap()
-            .expect("tag should exist by now");
-
+            .into_iter()
+            .map(|listed| (listed.tag.name, listed.alias_of))
+            .collect();
         assert_eq!(
-            Tag {
-                custom_id: found_tag.custom_id,
-                ..tag
-            },
-            found_tag
+            found,
+            vec![
+                ("cat_ears".into(), None),
+                ("cat".into(), None),
+                ("cat_(animal)".into(), Some("cat".into())),
+                ("catgirl".into(), None),
+            ]
         );
+
+        let found = autocomplete_tags(&db, "cat".into(), 2).await.unwrap();
+        assert_eq!(found.len(), 2);
+
+        for prefix in ["", "  ", "*", "c*t", "\0"] {
+            let found = autocomplete_tags(&db, prefix.into(), 10).await.unwrap();
+            assert!(found.is_empty(), "prefix: {:?}", prefix);
+        }
     }
 
     //#[allow(clippy::needless_return)]
@@ -153,9 +285,192 @@ pub mod server_only {
     //    todo!();
     //} // TODO: add proper testing for paginating.
 
-    //#[allow(clippy::needless_return)]
-    //#[tokio::test]
-    //async fn tag_query_generation() {
-    //    todo!();
-    //} // TODO: add proper testing search query generation
+    #[test]
+    fn tag_query_generation() {
+        assert_eq!(build_search_query(String::new()), (String::new(), vec![]));
+        assert_eq!(
+            build_search_query("lain".into()),
+            (
+                "WHERE name = $p0".into(),
+                vec![("p0".into(), "lain".into())]
+            )
+        );
+        assert_eq!(
+            build_search_query("lain*".into()),
+            (
+                "WHERE string::starts_with(name, $p0)".into(),
+                vec![("p0".into(), "lain".into())]
+            )
+        );
+        assert_eq!(
+            build_search_query("*girls".into()),
+            (
+                "WHERE string::ends_with(name, $p0)".into(),
+                vec![("p0".into(), "girls".into())]
+            )
+        );
+        assert_eq!(
+            build_search_query("a*b*c".into()),
+            (
+                "WHERE string::starts_with(name, $p0) AND string::ends_with(name, $p1) AND string::contains(name, $p2)".into(),
+                vec![
+                    ("p0".into(), "a".into()),
+                    ("p1".into(), "c".into()),
+                    ("p2".into(), "b".into())
+                ]
+            )
+        );
+        assert_eq!(build_search_query("**".into()), (String::new(), vec![]));
+    }
+
+    /// Characters that could end a string or start new syntax in SurrealQL.
+    const NASTY: &[&str] = &[
+        "'",
+        "\"",
+        "`",
+        "\\",
+        "\\'",
+        "\\u0027",
+        ";",
+        "$p0",
+        "$limit",
+        "(",
+        ")",
+        "{",
+        "}",
+        "⟨",
+        "⟩",
+        "/*",
+        "--",
+        "\n",
+        " ",
+        "OR",
+        "true",
+        "DELETE tag",
+        "*",
+        "a",
+        "_",
+        "é",
+        "\u{0}",
+    ];
+
+    /// Deterministic pseudo-random search strings built from `NASTY`.
+    fn fuzz_inputs(count: usize) -> Vec<String> {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        (0..count)
+            .map(|_| {
+                let len = next() % 12;
+                (0..len)
+                    .map(|_| NASTY[(next() % NASTY.len() as u64) as usize])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// What the generated query should look like: the same wildcards, with
+    /// every literal piece replaced by a harmless one.
+    fn skeleton(search: &str) -> String {
+        search
+            .split('*')
+            .map(|part| if part.is_empty() { "" } else { "x" })
+            .collect::<Vec<_>>()
+            .join("*")
+    }
+
+    #[test]
+    fn search_input_never_changes_query_structure() {
+        for input in fuzz_inputs(5000) {
+            let (query, params) = build_search_query(input.clone());
+            let (expected_query, expected_params) = build_search_query(skeleton(&input));
+
+            assert_eq!(query, expected_query, "input: {:?}", input);
+            assert_eq!(params.len(), expected_params.len(), "input: {:?}", input);
+            for (name, value) in &params {
+                assert!(input.contains(value.as_str()), "input: {:?}", input);
+                assert!(!value.contains('*'), "input: {:?}", input);
+                assert!(name.starts_with('p'), "input: {:?}", input);
+            }
+        }
+    }
+
+    /// Reference implementation of the wildcard match in Rust.
+    fn matches(search: &str, name: &str) -> bool {
+        let parts: Vec<&str> = search.split('*').collect();
+        if parts.len() == 1 {
+            return search.is_empty() || name == search;
+        }
+
+        name.starts_with(parts[0])
+            && name.ends_with(parts[parts.len() - 1])
+            && parts[1..parts.len() - 1]
+                .iter()
+                .all(|part| name.contains(part))
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn search_input_is_only_data() {
+        let db = surrealdb::Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        define_tag_table(&db).await.unwrap();
+
+        // Tags from before the tag name grammar may still have quotes and the
+        // like in their names, so these go into the table directly.
+        let names = ["cat_ears", "o'neil", "it's_(me)", "a{b}c", "x:y", "true"];
+        for (i, name) in names.into_iter().enumerate() {
+            let tag = Tag {
+                custom_id: i as u64 + 1,
+                name: String::from(name),
+                ..Tag::default()
+            };
+            let created: Option<Tag> = db.create("tag").content(tag).await.unwrap();
+            assert!(created.is_some());
+        }
+
+        let mut inputs = fuzz_inputs(300);
+        inputs.extend(
+            [
+                "' OR true OR name = '",
+                "o'neil",
+                "o\\'neil",
+                "*'*",
+                "\\'; DELETE tag; --",
+                "⟩; DELETE tag; SELECT * FROM ⟨tag",
+                "$p1",
+                "it's_(me)*",
+            ]
+            .map(String::from),
+        );
+
+        for input in inputs {
+            let mut found: Vec<String> = get_paginated_tags(&db, 1, 100, Some(input.clone()))
+                .await
+                .unwrap_or_else(|e| panic!("input {:?} failed: {}", input, e))
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect();
+            found.sort();
+
+            let mut expected: Vec<String> = names
+                .iter()
+                .filter(|name| matches(&input, name))
+                .map(|name| name.to_string())
+                .collect();
+            expected.sort();
+
+            assert_eq!(found, expected, "input: {:?}", input);
+        }
+
+        let remaining = get_paginated_tags(&db, 1, 100, None).await.unwrap();
+        assert_eq!(remaining.len(), names.len());
+    }
 }
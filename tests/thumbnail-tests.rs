@@ -0,0 +1,153 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use std::path::PathBuf;
+
+    use maerbooru::models::post::Post;
+    use maerbooru::server_only::post::add_new_post;
+    use maerbooru::server_only::thumbnail::{generate_thumbnails, regenerate_all_thumbnails};
+
+    use crate::common::{new_db, test_post};
+
+    fn uploads_dir(test_name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("maerbooru-{}-{}", test_name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a solid PNG of the given size into `dir` and returns its post.
+    fn write_test_image(dir: &std::path::Path, width: u32, height: u32, hash_byte: u8) -> Post {
+        let post = Post {
+            image_height: height,
+            image_width: width,
+            ..test_post(hash_byte, vec![])
+        };
+
+        image::RgbaImage::from_pixel(width, height, image::Rgba([200, 40, 40, 255]))
+            .save(dir.join(post.file_name()))
+            .unwrap();
+
+        post
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn thumbnails_are_scaled_down() {
+        let dir = uploads_dir("scaled-down");
+        let post = write_test_image(&dir, 2000, 1000, 1);
+
+        generate_thumbnails(&post, &dir).await.unwrap();
+
+        let thumbnail = image::open(dir.join(post.thumbnail_file_name())).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (180, 90));
+
+        let sample = image::open(dir.join(post.sample_file_name())).unwrap();
+        assert_eq!((sample.width(), sample.height()), (850, 425));
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn small_images_are_not_scaled_up() {
+        let dir = uploads_dir("not-scaled-up");
+        let post = write_test_image(&dir, 120, 80, 2);
+
+        generate_thumbnails(&post, &dir).await.unwrap();
+
+        let thumbnail = image::open(dir.join(post.thumbnail_file_name())).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (120, 80));
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn animated_images_use_the_first_frame() {
+        let dir = uploads_dir("first-frame");
+        let post = Post {
+            mime_type: mime::IMAGE_GIF,
+            frame_count: Some(2),
+            animated: true,
+            ..write_test_image(&dir, 8, 8, 6)
+        };
+
+        let file = std::fs::File::create(dir.join(post.file_name())).unwrap();
+        let mut encoder = image::codecs::gif::GifEncoder::new(file);
+        let frames = [[255, 0, 0, 255], [0, 0, 255, 255]].map(|colour| {
+            image::Frame::new(image::RgbaImage::from_pixel(8, 8, image::Rgba(colour)))
+        });
+        encoder.encode_frames(frames).unwrap();
+        drop(encoder);
+
+        generate_thumbnails(&post, &dir).await.unwrap();
+
+        let thumbnail = image::open(dir.join(post.thumbnail_file_name()))
+            .unwrap()
+            .to_rgb8();
+        let image::Rgb([red, _, blue]) = *thumbnail.get_pixel(4, 4);
+        assert!(red > 200 && blue < 50, "expected the red first frame");
+    }
+
+    #[cfg(unix)]
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn avif_images_are_decoded_by_ffmpeg() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // The image crate cannot decode AVIF, so these go through ffmpeg. A
+        // stand-in script answers with a PNG like ffmpeg would.
+        let dir = uploads_dir("avif");
+        let png = write_test_image(&dir, 400, 200, 7);
+        let post = Post {
+            mime_type: "image/avif".parse().unwrap(),
+            ..png.clone()
+        };
+        std::fs::write(dir.join(post.file_name()), b"\0\0\0\x1cftypavif").unwrap();
+
+        let ffmpeg = dir.join("ffmpeg");
+        std::fs::write(
+            &ffmpeg,
+            format!("#!/bin/sh\ncat '{}'\n", dir.join(png.file_name()).display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::env::set_var("FFMPEG_PATH", &ffmpeg);
+
+        generate_thumbnails(&post, &dir).await.unwrap();
+
+        let thumbnail = image::open(dir.join(post.thumbnail_file_name())).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (180, 90));
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn missing_original_is_an_error() {
+        let dir = uploads_dir("missing-original");
+        let post = write_test_image(&dir, 10, 10, 3);
+        std::fs::remove_file(dir.join(post.file_name())).unwrap();
+
+        assert!(generate_thumbnails(&post, &dir).await.is_err());
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn regenerate_every_post() {
+        let db = new_db().await;
+
+        let dir = uploads_dir("regenerate");
+        let posts = [
+            write_test_image(&dir, 300, 300, 4),
+            write_test_image(&dir, 50, 500, 5),
+        ];
+        for post in &posts {
+            add_new_post(&db, post).await.unwrap();
+        }
+
+        assert_eq!(regenerate_all_thumbnails(&db, &dir).await.unwrap(), 0);
+
+        for post in &posts {
+            assert!(dir.join(post.thumbnail_file_name()).exists());
+            assert!(dir.join(post.sample_file_name()).exists());
+        }
+    }
+}
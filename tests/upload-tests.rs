@@ -0,0 +1,111 @@
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use std::convert::Infallible;
+    use std::path::{Path, PathBuf};
+
+    use bytes::Bytes;
+    use leptos::ServerFnError;
+    use maerbooru::api::split_error;
+    use maerbooru::components::file_upload::UploadError;
+    use maerbooru::server_only::upload::receive_file;
+    use sha2::{Digest, Sha256};
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "maerbooru-upload-tests-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The sizes of the files in `dir`.
+    fn file_sizes(dir: &Path) -> Vec<u64> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .collect()
+    }
+
+    #[test]
+    fn upload_errors_come_back_as_the_inner_error() {
+        let result: anyhow::Result<u64> = Err(UploadError::Duplicate(42).into());
+        assert_eq!(
+            split_error::<u64, UploadError>(result),
+            Ok(Err(UploadError::Duplicate(42)))
+        );
+
+        let result: anyhow::Result<u64> = Ok(7);
+        assert_eq!(split_error::<u64, UploadError>(result), Ok(Ok(7)));
+    }
+
+    #[test]
+    fn other_errors_become_server_errors() {
+        let result: anyhow::Result<u64> = Err(anyhow::anyhow!("connection refused"));
+        assert_eq!(
+            split_error::<u64, UploadError>(result),
+            Err(ServerFnError::ServerError("connection refused".to_string()))
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn oversized_uploads_are_rejected_and_removed() {
+        let dir = temp_dir("oversized");
+        let chunks = futures::stream::iter(
+            [600, 600, 600].map(|len| Ok::<_, Infallible>(Bytes::from(vec![0u8; len]))),
+        );
+
+        let error = receive_file(chunks, &dir, 1000)
+            .await
+            .err()
+            .expect("the upload is over the limit");
+        assert_eq!(
+            error.downcast::<UploadError>().unwrap(),
+            UploadError::TooLarge(1000)
+        );
+        assert!(file_sizes(&dir).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn uploads_are_written_as_they_arrive() {
+        const CHUNK: usize = 1000;
+        const CHUNKS: usize = 5;
+        let dir = temp_dir("streamed");
+
+        // Before handing out each chunk, check that the earlier ones are
+        // already on disk rather than held back in memory. The latest one
+        // may still be in flight, as tokio writes files in the background.
+        let chunks = futures::stream::unfold(0usize, |sent| {
+            let dir = dir.clone();
+            async move {
+                let written: u64 = file_sizes(&dir).iter().sum();
+                assert!(written >= (sent.saturating_sub(1) * CHUNK) as u64);
+                assert!(written <= (sent * CHUNK) as u64);
+                (sent < CHUNKS).then(|| {
+                    let chunk = Bytes::from(vec![sent as u8; CHUNK]);
+                    (Ok::<_, Infallible>(chunk), sent + 1)
+                })
+            }
+        });
+
+        let received = receive_file(chunks, &dir, u64::MAX).await.unwrap();
+        assert_eq!(received.file_size, (CHUNK * CHUNKS) as u64);
+
+        let contents = std::fs::read(&received.temp_file.path).unwrap();
+        assert_eq!(contents.len(), CHUNK * CHUNKS);
+        let sha256_hash: [u8; 32] = Sha256::digest(&contents).into();
+        assert_eq!(received.sha256_hash, sha256_hash);
+
+        drop(received);
+        assert!(file_sizes(&dir).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
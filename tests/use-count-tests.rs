@@ -0,0 +1,121 @@
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use surrealdb::engine::local::Db;
+    use surrealdb::Surreal;
+
+    use maerbooru::server_only::implication::set_implications;
+    use maerbooru::server_only::post::{add_new_post, set_post_tags};
+    use maerbooru::server_only::tag::{get_tags_by_ids, recount_tag_uses};
+
+    use crate::common::{new_db, new_tag, test_post};
+
+    async fn new_tags(db: &Surreal<Db>, names: &[&str]) -> Vec<u64> {
+        let mut ids = vec![];
+        for name in names {
+            ids.push(new_tag(db, name).await);
+        }
+        ids
+    }
+
+    /// The use counts of `ids`, in the same order.
+    async fn counts(db: &Surreal<Db>, ids: &[u64]) -> Vec<u64> {
+        let tags = get_tags_by_ids(db, ids.to_vec()).await.unwrap();
+        ids.iter()
+            .map(|id| {
+                tags.iter()
+                    .find(|tag| tag.custom_id == *id)
+                    .unwrap()
+                    .use_count
+            })
+            .collect()
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn tagging_updates_counts() {
+        let db = new_db().await;
+        let ids = new_tags(&db, &["cat", "dog", "red"]).await;
+        let [cat, dog, red] = ids[..] else { panic!() };
+
+        let post = add_new_post(&db, &test_post(0, vec![cat, red]))
+            .await
+            .unwrap();
+        add_new_post(&db, &test_post(1, vec![cat])).await.unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![2, 0, 1]);
+
+        set_post_tags(&db, post, vec![dog, red], 0).await.unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![1, 1, 1]);
+
+        // Setting the same tags again changes nothing.
+        set_post_tags(&db, post, vec![red, dog], 0).await.unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![1, 1, 1]);
+
+        set_post_tags(&db, post, vec![], 0).await.unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![1, 0, 0]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn implied_tags_are_counted() {
+        let db = new_db().await;
+        let ids = new_tags(&db, &["cat_ears", "animal_ears", "ribbon"]).await;
+        let [cat_ears, animal_ears, ribbon] = ids[..] else {
+            panic!()
+        };
+
+        set_implications(&db, cat_ears, vec![animal_ears], false, 0)
+            .await
+            .unwrap();
+        add_new_post(&db, &test_post(0, vec![cat_ears]))
+            .await
+            .unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![1, 1, 0]);
+
+        set_implications(&db, animal_ears, vec![ribbon], true, 0)
+            .await
+            .unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![1, 1, 1]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn failed_uploads_are_not_counted() {
+        let db = new_db().await;
+        let ids = new_tags(&db, &["cat"]).await;
+
+        add_new_post(&db, &test_post(0, vec![ids[0]]))
+            .await
+            .unwrap();
+        // Same hash as the first post.
+        assert!(add_new_post(&db, &test_post(0, vec![ids[0]]))
+            .await
+            .is_err());
+        assert_eq!(counts(&db, &ids).await, vec![1]);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn recount_fixes_drift() {
+        let db = new_db().await;
+        let ids = new_tags(&db, &["cat", "dog", "unused"]).await;
+        let [cat, dog, _] = ids[..] else { panic!() };
+
+        add_new_post(&db, &test_post(0, vec![cat, dog]))
+            .await
+            .unwrap();
+        add_new_post(&db, &test_post(1, vec![cat])).await.unwrap();
+
+        db.query("UPDATE tag SET use_count = 40")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![40, 40, 40]);
+
+        recount_tag_uses(&db).await.unwrap();
+        assert_eq!(counts(&db, &ids).await, vec![2, 1, 0]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use maerbooru::models::user::{normalize_user_name, user_label, UserError};
+
+#[test]
+fn user_names() {
+    assert_eq!(normalize_user_name("  Maeru_99 "), Ok("maeru_99".into()));
+    assert_eq!(normalize_user_name("a-b"), Ok("a-b".into()));
+    for name in ["ab", "with space", "ümlaut", "semi;colon", &"x".repeat(33)] {
+        assert_eq!(
+            normalize_user_name(name),
+            Err(UserError::InvalidName),
+            "{name}"
+        );
+    }
+    assert_eq!(
+        normalize_user_name("Anonymous"),
+        Err(UserError::NameTaken("anonymous".into()))
+    );
+}
+
+#[test]
+fn user_labels() {
+    let names = HashMap::from([(3, "maeru".to_string())]);
+    assert_eq!(user_label(&names, 0), "Anonymous");
+    assert_eq!(user_label(&names, 3), "maeru");
+    assert_eq!(user_label(&names, 4), "User #4");
+}
+
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use http::header::COOKIE;
+    use http::{HeaderMap, HeaderValue};
+    use surrealdb::engine::local::Db;
+    use surrealdb::Surreal;
+
+    use maerbooru::models::permission::Role;
+    use maerbooru::models::user::{User, UserError};
+    use maerbooru::server_only::auth::{session_cookie, session_token};
+    use maerbooru::server_only::user::{
+        authenticate, create_session, end_session, get_session_user, get_user_by_name,
+        get_user_names, register_user, set_user_role,
+    };
+
+    use crate::common::new_db;
+
+    async fn user_error(result: anyhow::Result<impl std::fmt::Debug>) -> UserError {
+        result.unwrap_err().downcast::<UserError>().unwrap()
+    }
+
+    #[test]
+    fn session_cookies() {
+        let cookie = session_cookie("abc");
+        assert!(cookie.starts_with("session=abc; "));
+        for attribute in ["HttpOnly", "Secure", "SameSite=Lax", "Path=/"] {
+            assert!(cookie.contains(attribute), "{attribute}");
+        }
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(session_token(&headers), None);
+        headers.append(COOKIE, HeaderValue::from_static("theme=dark; session="));
+        assert_eq!(session_token(&headers), None);
+        headers.append(COOKIE, HeaderValue::from_static("a=b;session=F00D; c=d"));
+        assert_eq!(session_token(&headers), Some("F00D".into()));
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn register_and_log_in() {
+        let db = new_db().await;
+
+        let user = register_user(&db, "Maeru", "correct horse").await.unwrap();
+        assert_eq!(user.name, "maeru");
+        assert_eq!(
+            authenticate(&db, "MAERU", "correct horse").await.unwrap(),
+            user
+        );
+        assert_eq!(
+            get_user_by_name(&db, "maeru").await.unwrap(),
+            Some(user.clone())
+        );
+        assert_eq!(
+            get_user_names(&db, vec![user.custom_id, 99]).await.unwrap(),
+            [(user.custom_id, "maeru".to_string())].into()
+        );
+
+        assert_eq!(
+            user_error(authenticate(&db, "maeru", "wrong horse").await).await,
+            UserError::WrongCredentials
+        );
+        assert_eq!(
+            user_error(authenticate(&db, "nobody", "correct horse").await).await,
+            UserError::WrongCredentials
+        );
+        assert_eq!(
+            user_error(register_user(&db, "maeru", "another one").await).await,
+            UserError::NameTaken("maeru".into())
+        );
+        assert_eq!(
+            user_error(register_user(&db, "other", "short").await).await,
+            UserError::PasswordTooShort
+        );
+        assert_eq!(
+            user_error(register_user(&db, "x", "long enough").await).await,
+            UserError::InvalidName
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn sessions() {
+        let db = new_db().await;
+        let user = register_user(&db, "maeru", "correct horse").await.unwrap();
+
+        let first = create_session(&db, user.custom_id).await.unwrap();
+        let second = create_session(&db, user.custom_id).await.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            get_session_user(&db, &first).await.unwrap(),
+            Some(user.clone())
+        );
+
+        end_session(&db, &first).await.unwrap();
+        assert_eq!(get_session_user(&db, &first).await.unwrap(), None);
+        assert_eq!(get_session_user(&db, &second).await.unwrap(), Some(user));
+        assert_eq!(get_session_user(&db, "made up").await.unwrap(), None);
+
+        // The database only knows a hash of the token.
+        let stored: Option<String> = db
+            .query("SELECT VALUE token_hash FROM session")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_ne!(stored, Some(second));
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn roles() {
+        let db = new_db().await;
+        let user = register_user(&db, "maeru", "correct horse").await.unwrap();
+        assert_eq!(user.role, Role::Member);
+        let token = create_session(&db, user.custom_id).await.unwrap();
+
+        let janitor = set_user_role(&db, "Maeru", Role::Janitor, None)
+            .await
+            .unwrap();
+        assert_eq!(janitor.role, Role::Janitor);
+        assert_eq!(
+            get_session_user(&db, &token)
+                .await
+                .unwrap()
+                .map(|user| user.role),
+            Some(Role::Janitor)
+        );
+        assert_eq!(
+            authenticate(&db, "maeru", "correct horse").await.unwrap(),
+            janitor
+        );
+
+        assert_eq!(
+            user_error(set_user_role(&db, "nobody", Role::Admin, None).await).await,
+            UserError::UnknownUser("nobody".into())
+        );
+    }
+
+    /// Registers `name` with `role` and returns the user.
+    async fn user_with_role(db: &Surreal<Db>, name: &str, role: Role) -> User {
+        register_user(db, name, "correct horse").await.unwrap();
+        set_user_role(db, name, role, None).await.unwrap()
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn roles_cannot_be_raised_above_your_own() {
+        let db = new_db().await;
+        register_user(&db, "maeru", "correct horse").await.unwrap();
+        user_with_role(&db, "boss", Role::Admin).await;
+        let moderator = user_with_role(&db, "moderator", Role::Moderator).await;
+
+        assert_eq!(
+            user_error(set_user_role(&db, "maeru", Role::Admin, Some(&moderator)).await).await,
+            UserError::RoleAboveYours
+        );
+        assert_eq!(
+            user_error(set_user_role(&db, "boss", Role::Member, Some(&moderator)).await).await,
+            UserError::OutranksYou("boss".into())
+        );
+        let user = set_user_role(&db, "maeru", Role::Moderator, Some(&moderator))
+            .await
+            .unwrap();
+        assert_eq!(user.role, Role::Moderator);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn peers_cannot_change_each_others_roles() {
+        let db = new_db().await;
+        let first = user_with_role(&db, "first", Role::Moderator).await;
+        let second = user_with_role(&db, "second", Role::Moderator).await;
+
+        assert_eq!(
+            user_error(set_user_role(&db, "second", Role::Member, Some(&first)).await).await,
+            UserError::OutranksYou("second".into())
+        );
+        assert_eq!(
+            get_user_by_name(&db, "second").await.unwrap().unwrap().role,
+            Role::Moderator
+        );
+
+        // Stepping down yourself is fine, stepping up is not.
+        assert_eq!(
+            user_error(set_user_role(&db, "second", Role::Admin, Some(&second)).await).await,
+            UserError::RoleAboveYours
+        );
+        let user = set_user_role(&db, "second", Role::Member, Some(&second))
+            .await
+            .unwrap();
+        assert_eq!(user.role, Role::Member);
+
+        // The server itself may change anyone.
+        let user = set_user_role(&db, "first", Role::Member, None)
+            .await
+            .unwrap();
+        assert_eq!(user.role, Role::Member);
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use std::io::Cursor;
+
+    use maerbooru::server_only::media::sniff_mime;
+    use maerbooru::server_only::video::{video_metadata, VideoMetadata};
+
+    // The fixtures are built from their container structure rather than
+    // checked in as binaries: no frame data, only what the parsers read.
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    fn mp4_track(handler: &[u8; 4], width: u32, height: u32) -> Vec<u8> {
+        let mut tkhd = vec![0u8; 84];
+        tkhd[76..80].copy_from_slice(&(width << 16).to_be_bytes());
+        tkhd[80..84].copy_from_slice(&(height << 16).to_be_bytes());
+
+        let mut hdlr = vec![0u8; 25];
+        hdlr[8..12].copy_from_slice(handler);
+
+        let mdia = mp4_box(b"mdia", &mp4_box(b"hdlr", &hdlr));
+        mp4_box(b"trak", &[mp4_box(b"tkhd", &tkhd), mdia].concat())
+    }
+
+    /// A 320x240, 2.5 second MP4 with the moov box after the media data.
+    fn mp4(with_audio: bool) -> Vec<u8> {
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&2500u32.to_be_bytes());
+
+        let mut moov = [mp4_box(b"mvhd", &mvhd), mp4_track(b"vide", 320, 240)].concat();
+        if with_audio {
+            moov.extend(mp4_track(b"soun", 0, 0));
+        }
+
+        [
+            mp4_box(b"ftyp", b"isom\0\0\x02\0isommp41"),
+            mp4_box(b"mdat", &[0u8; 4096]),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat()
+    }
+
+    fn ebml(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut data = id.to_vec();
+        // Always use the 8 byte size form, like muxers that patch sizes later.
+        data.push(0x01);
+        data.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// A 320x240, 2.5 second WebM inside a segment of unknown size.
+    fn webm(with_audio: bool) -> Vec<u8> {
+        let header = ebml(&[0x1A, 0x45, 0xDF, 0xA3], &ebml(&[0x42, 0x82], b"webm"));
+
+        let info = ebml(
+            &[0x15, 0x49, 0xA9, 0x66],
+            &[
+                ebml(&[0x2A, 0xD7, 0xB1], &[0x0F, 0x42, 0x40]),
+                ebml(&[0x44, 0x89], &2500f64.to_be_bytes()),
+            ]
+            .concat(),
+        );
+
+        let video = ebml(
+            &[0xE0],
+            &[ebml(&[0xB0], &[0x01, 0x40]), ebml(&[0xBA], &[0xF0])].concat(),
+        );
+        let mut entries = ebml(&[0xAE], &[ebml(&[0x83], &[1]), video].concat());
+        if with_audio {
+            entries.extend(ebml(&[0xAE], &ebml(&[0x83], &[2])));
+        }
+        let tracks = ebml(&[0x16, 0x54, 0xAE, 0x6B], &entries);
+
+        let seek_head = ebml(&[0x11, 0x4D, 0x9B, 0x74], &[0u8; 32]);
+        let cluster = ebml(&[0x1F, 0x43, 0xB6, 0x75], &[0u8; 1024]);
+
+        [
+            header,
+            vec![
+                0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            ],
+            seek_head,
+            info,
+            tracks,
+            cluster,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn mp4_metadata() {
+        let file = mp4(true);
+        let mime_type = sniff_mime(&file).unwrap();
+
+        assert_eq!(
+            video_metadata(&mime_type, Cursor::new(file)),
+            Some(VideoMetadata {
+                width: 320,
+                height: 240,
+                duration: 2.5,
+                has_audio: true,
+            })
+        );
+    }
+
+    #[test]
+    fn mp4_without_audio() {
+        let file = mp4(false);
+        let mime_type = sniff_mime(&file).unwrap();
+
+        let metadata = video_metadata(&mime_type, Cursor::new(file)).unwrap();
+        assert!(!metadata.has_audio);
+    }
+
+    #[test]
+    fn mp4_without_moov() {
+        let file = [
+            mp4_box(b"ftyp", b"isom\0\0\x02\0isommp41"),
+            mp4_box(b"mdat", &[0u8; 64]),
+        ]
+        .concat();
+
+        assert_eq!(
+            video_metadata(&"video/mp4".parse().unwrap(), Cursor::new(file)),
+            None
+        );
+    }
+
+    #[test]
+    fn mp4_with_wrapping_box_size() {
+        // A 64-bit size of 2^64 - 8 used to turn into a seek back to the
+        // first box, which then repeated forever.
+        let mut large_box = 1u32.to_be_bytes().to_vec();
+        large_box.extend_from_slice(b"free");
+        large_box.extend_from_slice(&(u64::MAX - 7).to_be_bytes());
+        let file = [mp4_box(b"free", b""), large_box].concat();
+
+        assert_eq!(
+            video_metadata(&"video/mp4".parse().unwrap(), Cursor::new(file)),
+            None
+        );
+    }
+
+    #[test]
+    fn webm_metadata() {
+        let file = webm(true);
+        let mime_type = sniff_mime(&file).unwrap();
+
+        assert_eq!(
+            video_metadata(&mime_type, Cursor::new(file)),
+            Some(VideoMetadata {
+                width: 320,
+                height: 240,
+                duration: 2.5,
+                has_audio: true,
+            })
+        );
+    }
+
+    #[test]
+    fn webm_without_audio() {
+        let file = webm(false);
+        let mime_type = sniff_mime(&file).unwrap();
+
+        let metadata = video_metadata(&mime_type, Cursor::new(file)).unwrap();
+        assert!(!metadata.has_audio);
+    }
+
+    #[test]
+    fn truncated_webm() {
+        let file = webm(true);
+        // Cut off in the middle of the Info element.
+        let info_start = file
+            .windows(4)
+            .position(|window| window == [0x15, 0x49, 0xA9, 0x66])
+            .unwrap();
+
+        assert_eq!(
+            video_metadata(
+                &"video/webm".parse().unwrap(),
+                Cursor::new(&file[..info_start + 16])
+            ),
+            None
+        );
+    }
+}
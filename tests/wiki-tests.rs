@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use maerbooru::models::tag::{Tag, TagCategory};
+use maerbooru::models::wiki::{
+    diff_lines, example_posts, parse_markup, render_markup, tag_links, DiffLine, Inline,
+    MAX_WIKI_LINES,
+};
+
+fn render(body: &str) -> String {
+    render_markup(&parse_markup(body), &HashMap::new(), &HashMap::new())
+}
+
+#[test]
+fn paragraphs_and_line_breaks() {
+    assert_eq!(
+        render("one\ntwo\n\n\nthree"),
+        "<p>one<br>two</p><p>three</p>"
+    );
+    assert_eq!(render("\n  \n"), "");
+}
+
+#[test]
+fn bold_and_italic() {
+    assert_eq!(
+        render("**bold** and *italic **both***"),
+        "<p><strong>bold</strong> and <em>italic <strong>both</strong></em></p>"
+    );
+    assert_eq!(render("2 * 3 and ** alone"), "<p>2 * 3 and ** alone</p>");
+}
+
+#[test]
+fn links() {
+    assert_eq!(
+        render("[site](https://example.com/?a=1&b=2) [posts](/posts)"),
+        r#"<p><a href="https://example.com/?a=1&amp;b=2">site</a> <a href="/posts">posts</a></p>"#
+    );
+    for body in [
+        "[x](javascript:alert(1))",
+        "[x](//evil.example)",
+        "[](/posts)",
+    ] {
+        assert!(!render(body).contains("<a"), "{body}");
+    }
+}
+
+#[test]
+fn tag_links_resolve() {
+    let paragraphs = parse_markup("[[Blue Sky]], [[kitty|cats]] and [[nothing]] [[bad*]]");
+    assert_eq!(tag_links(&paragraphs), vec!["blue_sky", "kitty", "nothing"]);
+
+    let cat = Tag {
+        name: "cat".into(),
+        category: TagCategory::Species,
+        ..Tag::default()
+    };
+    let blue_sky = Tag {
+        name: "blue_sky".into(),
+        ..Tag::default()
+    };
+    let tags = HashMap::from([
+        ("kitty".to_string(), cat),
+        ("blue_sky".to_string(), blue_sky),
+    ]);
+
+    assert_eq!(
+        render_markup(&paragraphs, &tags, &HashMap::new()),
+        concat!(
+            r#"<p><a href="/tag/blue_sky" class="text-blue-600">blue sky</a>, "#,
+            r#"<a href="/tag/cat" class="text-teal-600">cats</a> and "#,
+            r#"<a href="/tag/nothing" class="text-gray-400">nothing</a> [[bad*]]</p>"#,
+        )
+    );
+}
+
+#[test]
+fn example_posts_are_collected() {
+    let paragraphs = parse_markup("!post #12 and **!post #3**\n!post #12 !post #x");
+    assert_eq!(example_posts(&paragraphs), vec![12, 3]);
+    assert_eq!(paragraphs[0][0], Inline::Post(12));
+    assert_eq!(
+        render_markup(&paragraphs, &HashMap::new(), &HashMap::new()),
+        "<p>post #12 and <strong>post #3</strong><br>post #12 !post #x</p>"
+    );
+}
+
+#[test]
+fn html_is_escaped() {
+    assert_eq!(
+        render("<script>alert(\"x\")</script> & [[a|<b>]]"),
+        concat!(
+            "<p>&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt; &amp; ",
+            r#"<a href="/tag/a" class="text-gray-400">&lt;b&gt;</a></p>"#,
+        )
+    );
+}
+
+#[test]
+fn diffs() {
+    assert_eq!(
+        diff_lines("a\nb\nc\nd", "a\nc\nd\ne"),
+        vec![
+            DiffLine::Same("a".into()),
+            DiffLine::Removed("b".into()),
+            DiffLine::Same("c".into()),
+            DiffLine::Same("d".into()),
+            DiffLine::Added("e".into()),
+        ]
+    );
+    assert_eq!(diff_lines("", "new"), vec![DiffLine::Added("new".into())]);
+    assert_eq!(diff_lines("old", ""), vec![DiffLine::Removed("old".into())]);
+    assert_eq!(
+        diff_lines("x", "y"),
+        vec![DiffLine::Removed("x".into()), DiffLine::Added("y".into())]
+    );
+}
+
+#[test]
+fn unclosed_markup_is_text() {
+    // Each of these used to search the rest of the line once per opener.
+    for opener in ["[", "[[", "**", "[a](", "[[a|"] {
+        let text = opener.repeat(50_000);
+        assert_eq!(parse_markup(&text), vec![vec![Inline::Text(text)]]);
+    }
+    let name = "a".repeat(300);
+    assert_eq!(
+        parse_markup(&format!("[[{name}]]")),
+        vec![vec![Inline::Text(format!("[[{name}]]"))]]
+    );
+}
+
+#[test]
+fn long_diffs() {
+    let old: Vec<String> = (0..MAX_WIKI_LINES).map(|i| format!("line {i}")).collect();
+    let new: Vec<String> = old
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match i % 10 {
+            0 => format!("changed {i}"),
+            _ => line.clone(),
+        })
+        .collect();
+
+    let diff = diff_lines(&old.join("\n"), &new.join("\n"));
+    let count = |f: fn(&DiffLine) -> bool| diff.iter().filter(|line| f(line)).count();
+    assert_eq!(count(|line| matches!(line, DiffLine::Same(_))), 1800);
+    assert_eq!(count(|line| matches!(line, DiffLine::Removed(_))), 200);
+    assert_eq!(count(|line| matches!(line, DiffLine::Added(_))), 200);
+    assert_eq!(
+        diff[..3],
+        [
+            DiffLine::Removed("line 0".into()),
+            DiffLine::Added("changed 0".into()),
+            DiffLine::Same("line 1".into()),
+        ]
+    );
+}
+
+#[cfg(feature = "ssr")]
+mod common;
+
+#[cfg(feature = "ssr")]
+pub mod server_only {
+    use maerbooru::models::tag::TagEdit;
+    use maerbooru::models::wiki::{WikiError, MAX_WIKI_BYTES, MAX_WIKI_LINES};
+    use maerbooru::server_only::post::add_new_post;
+    use maerbooru::server_only::tag::get_tag_by_id;
+    use maerbooru::server_only::tag_edit::edit_tag;
+    use maerbooru::server_only::wiki::{
+        get_wiki_page, revert_wiki_page, sanitize_wiki_html, save_wiki_page,
+    };
+
+    use crate::common::{new_alias, new_db, new_tag, test_post};
+
+    #[test]
+    fn sanitizer_strips_what_markup_cannot_make() {
+        assert_eq!(
+            sanitize_wiki_html(concat!(
+                r#"<p onclick="x()"><script>bad()</script><a href="javascript:bad()" class="evil">x</a>"#,
+                r#"<img src="/uploads/a.jpg" alt="a" onerror="bad()"></p>"#,
+            )),
+            concat!(
+                r#"<p><a class="" rel="nofollow noopener noreferrer">x</a>"#,
+                r#"<img src="/uploads/a.jpg" alt="a"></p>"#,
+            )
+        );
+        let link = r#"<a href="/tag/cat" class="text-blue-600" rel="nofollow noopener noreferrer">cat</a>"#;
+        assert_eq!(sanitize_wiki_html(link), link);
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn versions_are_saved_and_reverted() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+
+        let page = get_wiki_page(&db, "cat".into()).await.unwrap().unwrap();
+        assert_eq!(page.latest_version(), 0);
+        assert_eq!(page.html, "");
+
+        assert_eq!(
+            save_wiki_page(&db, "cat".into(), "A *small* animal.".into(), 0, 5)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            save_wiki_page(&db, "cat".into(), "A **small** animal.".into(), 1, 0)
+                .await
+                .unwrap(),
+            2
+        );
+        // Saving the same text again does not make a version.
+        assert_eq!(
+            save_wiki_page(&db, "cat".into(), "A **small** animal.".into(), 2, 0)
+                .await
+                .unwrap(),
+            2
+        );
+
+        let error = save_wiki_page(&db, "cat".into(), "Stale.".into(), 1, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<WikiError>().unwrap(),
+            WikiError::Outdated(2)
+        );
+
+        assert_eq!(revert_wiki_page(&db, "cat".into(), 1, 0).await.unwrap(), 3);
+        let error = revert_wiki_page(&db, "cat".into(), 7, 0).await.unwrap_err();
+        assert_eq!(
+            error.downcast::<WikiError>().unwrap(),
+            WikiError::UnknownVersion(7)
+        );
+        for body in [
+            "a".repeat(MAX_WIKI_BYTES + 1),
+            "a\n".repeat(MAX_WIKI_LINES + 1),
+        ] {
+            let error = save_wiki_page(&db, "cat".into(), body, 3, 0)
+                .await
+                .unwrap_err();
+            assert_eq!(error.downcast::<WikiError>().unwrap(), WikiError::TooLong);
+        }
+        let error = save_wiki_page(&db, "dog".into(), "".into(), 0, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast::<WikiError>().unwrap(),
+            WikiError::UnknownTag("dog".into())
+        );
+
+        let page = get_wiki_page(&db, "cat".into()).await.unwrap().unwrap();
+        assert_eq!(page.body, "A *small* animal.");
+        assert_eq!(page.html, "<p>A <em>small</em> animal.</p>");
+        let versions: Vec<_> = page
+            .versions
+            .iter()
+            .map(|version| (version.version, version.reverted_from, version.author_id))
+            .collect();
+        assert_eq!(versions, vec![(3, Some(1), 0), (2, None, 0), (1, None, 5)]);
+
+        let tag = get_tag_by_id(&db, cat).await.unwrap().unwrap();
+        assert_eq!(tag.description, "A *small* animal.");
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn links_and_posts_are_rendered() {
+        let db = new_db().await;
+        let cat = new_tag(&db, "cat").await;
+        new_alias(&db, "kitty", cat).await;
+        let post = test_post(0xAB, vec![cat]);
+        let id = add_new_post(&db, &post).await.unwrap();
+
+        let body = format!("See [[kitty]], [[dog]] and !post #{id} !post #999");
+        save_wiki_page(&db, "cat".into(), body, 0, 0).await.unwrap();
+
+        let html = get_wiki_page(&db, "cat".into())
+            .await
+            .unwrap()
+            .unwrap()
+            .html;
+        assert_eq!(
+            html,
+            format!(
+                concat!(
+                    r#"<p>See <a href="/tag/cat" class="text-blue-600" rel="nofollow noopener noreferrer">kitty</a>, "#,
+                    r#"<a href="/tag/dog" class="text-gray-400" rel="nofollow noopener noreferrer">dog</a> and "#,
+                    r#"<a href="/post/{id}" rel="nofollow noopener noreferrer"><img src="/uploads/{thumb}" alt="post #{id}"></a> post #999</p>"#,
+                ),
+                id = id,
+                thumb = post.thumbnail_file_name(),
+            )
+        );
+    }
+
+    #[allow(clippy::needless_return)]
+    #[tokio::test]
+    async fn descriptions_are_versioned() {
+        let db = new_db().await;
+        new_tag(&db, "cat").await;
+
+        let edit = TagEdit {
+            description: Some("Meow.".into()),
+            ..TagEdit::default()
+        };
+        edit_tag(&db, "cat".into(), edit.clone(), 0).await.unwrap();
+        edit_tag(&db, "cat".into(), edit, 0).await.unwrap();
+
+        let page = get_wiki_page(&db, "cat".into()).await.unwrap().unwrap();
+        assert_eq!(page.latest_version(), 1);
+        assert_eq!(page.body, "Meow.");
+    }
+}